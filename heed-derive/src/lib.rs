@@ -0,0 +1,254 @@
+//! Derive macros to generate the codecs of the types stored in heed databases.
+//!
+//! These macros are re-exported by heed when its `derive` feature is enabled.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, Index};
+
+/// Derives an order-preserving key codec for a struct.
+///
+/// The struct becomes a codec that encodes and decodes itself, its fields are
+/// encoded one after the other using their [`KeyField`] implementation, integers
+/// are stored in big-endian, therefore the keys are sorted by the first field,
+/// then by the second one, and so on, with the default lexicographic comparator.
+///
+/// Fields can be integers, booleans, `String`s, `Vec<u8>`s, arrays and tuples of
+/// them or any other type implementing [`KeyField`], like another `HeedKey` struct.
+///
+/// ```
+/// use heed::types::Str;
+/// use heed::{Database, EnvOpenOptions, HeedKey};
+///
+/// #[derive(Debug, PartialEq, HeedKey)]
+/// struct Event {
+///     user: String,
+///     timestamp: i64,
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let dir = tempfile::tempdir()?;
+/// # let env = EnvOpenOptions::new()
+/// #     .map_size(10 * 1024 * 1024) // 10MB
+/// #     .max_dbs(3000)
+/// #     .open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let db: Database<Event, Str> = env.create_database(&mut wtxn, Some("events"))?;
+///
+/// db.put(&mut wtxn, &Event { user: "kero".into(), timestamp: 12 }, "logged-out")?;
+/// db.put(&mut wtxn, &Event { user: "kero".into(), timestamp: -3 }, "logged-in")?;
+/// db.put(&mut wtxn, &Event { user: "alice".into(), timestamp: 5 }, "logged-in")?;
+///
+/// let mut iter = db.iter(&wtxn)?;
+/// assert_eq!(iter.next().transpose()?, Some((Event { user: "alice".into(), timestamp: 5 }, "logged-in")));
+/// assert_eq!(iter.next().transpose()?, Some((Event { user: "kero".into(), timestamp: -3 }, "logged-in")));
+/// assert_eq!(iter.next().transpose()?, Some((Event { user: "kero".into(), timestamp: 12 }, "logged-out")));
+/// assert_eq!(iter.next().transpose()?, None);
+///
+/// drop(iter);
+/// wtxn.commit()?;
+/// # Ok(()) }
+/// ```
+///
+/// [`KeyField`]: https://docs.rs/heed-types/latest/heed_types/trait.KeyField.html
+#[proc_macro_derive(HeedKey)]
+pub fn derive_heed_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    heed_key(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derives a zero-copy value codec for a struct, like the `UnalignedType` codec does.
+///
+/// The struct must implement the bytemuck `Pod` trait and have an alignment of one,
+/// by being `#[repr(C, packed)]` for example, so that it can always be borrowed from
+/// the database without being copied. Decoding it gives a reference to the struct.
+///
+/// ```
+/// use bytemuck::{Pod, Zeroable};
+/// use heed::types::Str;
+/// use heed::{Database, EnvOpenOptions, HeedValue};
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, HeedValue)]
+/// #[repr(C, packed)]
+/// struct Position {
+///     x: u32,
+///     y: u64,
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let dir = tempfile::tempdir()?;
+/// # let env = EnvOpenOptions::new()
+/// #     .map_size(10 * 1024 * 1024) // 10MB
+/// #     .max_dbs(3000)
+/// #     .open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let db: Database<Str, Position> = env.create_database(&mut wtxn, Some("positions"))?;
+///
+/// db.put(&mut wtxn, "kero", &Position { x: 3, y: 12 })?;
+///
+/// let ret = db.get(&wtxn, "kero")?;
+/// assert_eq!(ret, Some(&Position { x: 3, y: 12 }));
+///
+/// wtxn.commit()?;
+/// # Ok(()) }
+/// ```
+#[proc_macro_derive(HeedValue)]
+pub fn derive_heed_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    heed_value(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn heed_key(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new_spanned(&input, "HeedKey can only be derived on structs")),
+    };
+
+    let key_field = quote!(::heed::types::KeyField);
+    let mut generics = input.generics.clone();
+    let where_clause = generics.make_where_clause();
+    for field in fields {
+        let ty = &field.ty;
+        where_clause.predicates.push(parse_quote!(#ty: #key_field));
+    }
+
+    let encode_fields = fields.iter().enumerate().map(|(i, field)| {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(i);
+                quote!(#index)
+            }
+        };
+        quote!(#key_field::encode_key_field(&self.#member, buffer);)
+    });
+
+    let decode_field = quote!(#key_field::decode_key_field(bytes)?);
+    let construct = match fields {
+        Fields::Named(fields) => {
+            let idents = fields.named.iter().map(|field| &field.ident);
+            quote!(Self { #(#idents: #decode_field),* })
+        }
+        Fields::Unnamed(fields) => {
+            let decodes = fields.unnamed.iter().map(|_| &decode_field);
+            quote!(Self(#(#decodes),*))
+        }
+        Fields::Unit => quote!(Self),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let codec_generics = with_codec_lifetime(&generics);
+    let (codec_impl_generics, _, codec_where_clause) = codec_generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #key_field for #name #ty_generics #where_clause {
+            fn encode_key_field(&self, buffer: &mut ::std::vec::Vec<u8>) {
+                #(#encode_fields)*
+            }
+
+            fn decode_key_field(
+                bytes: &mut &[u8],
+            ) -> ::std::result::Result<Self, ::heed::BoxedError> {
+                ::std::result::Result::Ok(#construct)
+            }
+        }
+
+        impl #codec_impl_generics ::heed::BytesEncode<'__heed> for #name #ty_generics #codec_where_clause {
+            type EItem = Self;
+
+            fn bytes_encode(
+                item: &'__heed Self::EItem,
+            ) -> ::std::result::Result<::std::borrow::Cow<'__heed, [u8]>, ::heed::BoxedError> {
+                let mut buffer = ::std::vec::Vec::new();
+                #key_field::encode_key_field(item, &mut buffer);
+                ::std::result::Result::Ok(::std::borrow::Cow::Owned(buffer))
+            }
+
+            fn encode_into(
+                item: &'__heed Self::EItem,
+                buffer: &mut ::std::vec::Vec<u8>,
+            ) -> ::std::result::Result<(), ::heed::BoxedError> {
+                #key_field::encode_key_field(item, buffer);
+                ::std::result::Result::Ok(())
+            }
+        }
+
+        impl #codec_impl_generics ::heed::BytesDecode<'__heed> for #name #ty_generics #codec_where_clause {
+            type DItem = Self;
+
+            fn bytes_decode(
+                mut bytes: &'__heed [u8],
+            ) -> ::std::result::Result<Self::DItem, ::heed::BoxedError> {
+                let item = #key_field::decode_key_field(&mut bytes)?;
+                if bytes.is_empty() {
+                    ::std::result::Result::Ok(item)
+                } else {
+                    ::std::result::Result::Err("unexpected trailing bytes after the key".into())
+                }
+            }
+        }
+    })
+}
+
+fn heed_value(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    if !matches!(input.data, Data::Struct(_)) {
+        return Err(syn::Error::new_spanned(&input, "HeedValue can only be derived on structs"));
+    }
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "HeedValue cannot be derived on generic structs",
+        ));
+    }
+
+    let message = format!("{} must have an alignment of one to be borrowed without copy", name);
+    let assertion = format_ident!("_HEED_VALUE_ALIGNMENT_OF_{}", name);
+
+    Ok(quote! {
+        #[allow(non_upper_case_globals)]
+        const #assertion: () = ::std::assert!(::std::mem::align_of::<#name>() == 1, #message);
+
+        impl<'__heed> ::heed::BytesEncode<'__heed> for #name {
+            type EItem = Self;
+
+            fn bytes_encode(
+                item: &'__heed Self::EItem,
+            ) -> ::std::result::Result<::std::borrow::Cow<'__heed, [u8]>, ::heed::BoxedError> {
+                ::std::result::Result::Ok(::std::borrow::Cow::Borrowed(::heed::bytemuck::bytes_of(item)))
+            }
+
+            fn encoded_size(_item: &'__heed Self::EItem) -> ::std::option::Option<usize> {
+                ::std::option::Option::Some(::std::mem::size_of::<Self>())
+            }
+
+            fn encode_to_slice(
+                item: &'__heed Self::EItem,
+                output: &mut [u8],
+            ) -> ::std::result::Result<(), ::heed::BoxedError> {
+                output.copy_from_slice(::heed::bytemuck::bytes_of(item));
+                ::std::result::Result::Ok(())
+            }
+        }
+
+        impl<'__heed> ::heed::BytesDecode<'__heed> for #name {
+            type DItem = &'__heed Self;
+
+            fn bytes_decode(
+                bytes: &'__heed [u8],
+            ) -> ::std::result::Result<Self::DItem, ::heed::BoxedError> {
+                ::heed::bytemuck::try_from_bytes(bytes).map_err(::std::convert::Into::into)
+            }
+        }
+    })
+}
+
+/// Returns the generics with the `'__heed` lifetime the codec traits are implemented for.
+fn with_codec_lifetime(generics: &syn::Generics) -> syn::Generics {
+    let mut generics = generics.clone();
+    generics.params.insert(0, parse_quote!('__heed));
+    generics.make_where_clause().predicates.push(parse_quote!(Self: '__heed));
+    generics
+}
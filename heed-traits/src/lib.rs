@@ -5,6 +5,7 @@
 use std::borrow::Cow;
 use std::cmp::{Ord, Ordering};
 use std::error::Error as StdError;
+use std::marker::PhantomData;
 
 /// A boxed `Send + Sync + 'static` error.
 pub type BoxedError = Box<dyn StdError + Send + Sync + 'static>;
@@ -16,6 +17,37 @@ pub trait BytesEncode<'a> {
 
     /// Encode the given item as bytes
     fn bytes_encode(item: &'a Self::EItem) -> Result<Cow<'a, [u8]>, BoxedError>;
+
+    /// Encode the given item at the end of the buffer.
+    ///
+    /// The default implementation copies the bytes returned by [`BytesEncode::bytes_encode`],
+    /// codecs that allocate should override it to write directly into the buffer.
+    fn encode_into(item: &'a Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+        buffer.extend_from_slice(&Self::bytes_encode(item)?);
+        Ok(())
+    }
+
+    /// Returns the exact number of bytes the item will be encoded into, if it can be known
+    /// without encoding it. It allows the item to be encoded directly into the database.
+    ///
+    /// The default implementation returns `None`.
+    fn encoded_size(item: &'a Self::EItem) -> Option<usize> {
+        let _ = item;
+        None
+    }
+
+    /// Encode the given item into the output slice, it is exactly [`BytesEncode::encoded_size`] long.
+    ///
+    /// The default implementation copies the bytes returned by [`BytesEncode::bytes_encode`].
+    fn encode_to_slice(item: &'a Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+        let bytes = Self::bytes_encode(item)?;
+        if bytes.len() == output.len() {
+            output.copy_from_slice(&bytes);
+            Ok(())
+        } else {
+            Err("the encoded size doesn't match the size hint".into())
+        }
+    }
 }
 
 /// A trait that represents a decoding structure.
@@ -27,12 +59,90 @@ pub trait BytesDecode<'a> {
     fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError>;
 }
 
+/// A trait that represents an encoding structure that needs a runtime context.
+///
+/// A [`BytesEncode`] type can be used where a context is expected
+/// by wrapping it in the [`IgnoreContext`] adapter.
+pub trait BytesEncodeWith<'a, Ctx: ?Sized> {
+    /// The type to encode
+    type EItem: ?Sized + 'a;
+
+    /// Encode the given item as bytes with the help of the context
+    fn bytes_encode_with(item: &'a Self::EItem, ctx: &Ctx) -> Result<Cow<'a, [u8]>, BoxedError>;
+}
+
+/// A trait that represents a decoding structure that needs a runtime context.
+///
+/// A [`BytesDecode`] type can be used where a context is expected
+/// by wrapping it in the [`IgnoreContext`] adapter.
+pub trait BytesDecodeWith<'a, Ctx: ?Sized> {
+    /// The type to decode
+    type DItem: 'a;
+
+    /// Decode the given bytes as DItem with the help of the context
+    fn bytes_decode_with(bytes: &'a [u8], ctx: &Ctx) -> Result<Self::DItem, BoxedError>;
+}
+
+/// An adapter that implements [`BytesEncodeWith`] and [`BytesDecodeWith`]
+/// for any context by ignoring it and using the `C` codec.
+///
+/// It also implements [`BytesEncode`] and [`BytesDecode`] by forwarding to `C`,
+/// the database can therefore still be used without a context.
+pub struct IgnoreContext<C>(PhantomData<fn() -> C>);
+
+impl<'a, C: BytesEncode<'a>> BytesEncode<'a> for IgnoreContext<C> {
+    type EItem = C::EItem;
+
+    fn bytes_encode(item: &'a Self::EItem) -> Result<Cow<'a, [u8]>, BoxedError> {
+        C::bytes_encode(item)
+    }
+
+    fn encode_into(item: &'a Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+        C::encode_into(item, buffer)
+    }
+
+    fn encoded_size(item: &'a Self::EItem) -> Option<usize> {
+        C::encoded_size(item)
+    }
+
+    fn encode_to_slice(item: &'a Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+        C::encode_to_slice(item, output)
+    }
+}
+
+impl<'a, C: BytesDecode<'a>> BytesDecode<'a> for IgnoreContext<C> {
+    type DItem = C::DItem;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+        C::bytes_decode(bytes)
+    }
+}
+
+impl<'a, C: BytesEncode<'a>, Ctx: ?Sized> BytesEncodeWith<'a, Ctx> for IgnoreContext<C> {
+    type EItem = C::EItem;
+
+    fn bytes_encode_with(item: &'a Self::EItem, _ctx: &Ctx) -> Result<Cow<'a, [u8]>, BoxedError> {
+        C::bytes_encode(item)
+    }
+}
+
+impl<'a, C: BytesDecode<'a>, Ctx: ?Sized> BytesDecodeWith<'a, Ctx> for IgnoreContext<C> {
+    type DItem = C::DItem;
+
+    fn bytes_decode_with(bytes: &'a [u8], _ctx: &Ctx) -> Result<Self::DItem, BoxedError> {
+        C::bytes_decode(bytes)
+    }
+}
+
 /// Define a custom key comparison function for a database.
 ///
 /// The comparison function is called whenever it is necessary to compare a key specified
 /// by the application with a key currently stored in the database. If no comparison function
 /// is specified, and no special key flags were specified, the keys are compared lexically,
 /// with shorter keys collating before longer keys.
+///
+/// A panic of the comparison function is caught, the operation that triggered it fails
+/// and its write transaction is poisoned: it can no longer be committed, only aborted.
 pub trait Comparator {
     /// Compares the raw bytes representation of two keys.
     ///
@@ -43,6 +153,51 @@ pub trait Comparator {
     fn compare(a: &[u8], b: &[u8]) -> Ordering;
 }
 
+/// Define a key comparison function over the decoded keys of a database.
+///
+/// The keys are decoded with the `KC` codec before being compared, the [`TypedComparator`]
+/// adapter wraps an implementation of this trait into a [`Comparator`] that can be given
+/// to the database options, e.g. `key_comparator::<TypedComparator<KC, MyCmp>>()`.
+pub trait KeyComparator<KC>
+where
+    KC: for<'a> BytesDecode<'a>,
+{
+    /// Compares two decoded keys.
+    fn compare_keys<'a>(
+        a: &<KC as BytesDecode<'a>>::DItem,
+        b: &<KC as BytesDecode<'a>>::DItem,
+    ) -> Ordering;
+
+    /// Compares the raw bytes of two keys when at least one of them can't be decoded.
+    ///
+    /// By default the undecodable keys are sorted after the decodable ones,
+    /// and lexicographically between themselves.
+    fn compare_undecodable(a: &[u8], b: &[u8]) -> Ordering {
+        let a_undecodable = KC::bytes_decode(a).is_err();
+        let b_undecodable = KC::bytes_decode(b).is_err();
+        a_undecodable.cmp(&b_undecodable).then_with(|| a.cmp(b))
+    }
+}
+
+/// A [`Comparator`] that decodes the keys with `KC` and compares them with the `C` [`KeyComparator`].
+///
+/// It lets a database sort its keys by their logical value, like a
+/// `SerdeBincode<(String, u64)>` key that isn't ordered by its encoded bytes.
+pub struct TypedComparator<KC, C>(PhantomData<fn() -> (KC, C)>);
+
+impl<KC, C> Comparator for TypedComparator<KC, C>
+where
+    KC: for<'a> BytesDecode<'a>,
+    C: KeyComparator<KC>,
+{
+    fn compare(a: &[u8], b: &[u8]) -> Ordering {
+        match (KC::bytes_decode(a), KC::bytes_decode(b)) {
+            (Ok(a), Ok(b)) => C::compare_keys(&a, &b),
+            _ => C::compare_undecodable(a, b),
+        }
+    }
+}
+
 /// Define a lexicographic comparator, which is a special case of [`Comparator`].
 ///
 /// Types that implements [`LexicographicComparator`] will automatically have [`Comparator`]
@@ -55,6 +210,9 @@ pub trait LexicographicComparator: Comparator {
     /// Compare a single byte; this function is used to implement [`Comparator::compare`]
     /// by definition of lexicographic ordering.
     ///
+    /// It is only called on distinct bytes, the comparison moves on to the next bytes
+    /// when it returns [`Ordering::Equal`] for them.
+    ///
     /// # Safety
     ///
     /// This function must never crash.
@@ -81,9 +239,21 @@ impl<C: LexicographicComparator> Comparator for C {
     fn compare(a: &[u8], b: &[u8]) -> Ordering {
         for idx in 0..std::cmp::min(a.len(), b.len()) {
             if a[idx] != b[idx] {
-                return C::compare_elem(a[idx], b[idx]);
+                // Distinct bytes can be equal for the comparator, e.g. when ignoring the case.
+                match C::compare_elem(a[idx], b[idx]) {
+                    Ordering::Equal => continue,
+                    ordering => return ordering,
+                }
             }
         }
         Ord::cmp(&a.len(), &b.len())
     }
 }
+
+/// Marks a [`Comparator`] that keeps the keys starting with the same bytes next to each other.
+///
+/// The keys starting with a prefix must be contiguous and must not be sorted before the
+/// prefix itself. It lets the contiguous prefix iterators of heed seek on the prefix and
+/// stop on the first key that doesn't start with it, without requiring the comparator to be
+/// a [`LexicographicComparator`].
+pub trait ContiguousPrefixComparator: Comparator {}
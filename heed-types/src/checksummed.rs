@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::{error, fmt};
+
+use heed_traits::{BoxedError, BytesDecode, BytesEncode};
+
+/// A checksum algorithm that can be used by the [`Checksummed`] codec.
+pub trait Checksum {
+    /// The number of bytes of the checksum appended to the encoded bytes, at most 8.
+    const SIZE: usize;
+
+    /// Computes the checksum of the given bytes, only the
+    /// [`SIZE`](Checksum::SIZE) lowest bytes of it are stored.
+    fn checksum(bytes: &[u8]) -> u64;
+
+    /// Splits the checksum from the bytes and verifies it, returning the bytes without it.
+    fn verify(bytes: &[u8]) -> Result<&[u8], ChecksumMismatch> {
+        let mask = if Self::SIZE >= 8 { u64::MAX } else { (1 << (Self::SIZE * 8)) - 1 };
+        if bytes.len() < Self::SIZE {
+            let computed = Self::checksum(bytes) & mask;
+            return Err(ChecksumMismatch { stored: None, computed });
+        }
+
+        let (bytes, checksum) = bytes.split_at(bytes.len() - Self::SIZE);
+        let mut stored = [0; 8];
+        stored[..Self::SIZE].copy_from_slice(checksum);
+        let stored = u64::from_le_bytes(stored);
+        let computed = Self::checksum(bytes) & mask;
+
+        if stored == computed {
+            Ok(bytes)
+        } else {
+            Err(ChecksumMismatch { stored: Some(stored), computed })
+        }
+    }
+}
+
+/// The CRC32C (Castagnoli) checksum, hardware accelerated on most platforms.
+#[cfg(feature = "crc32c")]
+pub enum Crc32c {}
+
+#[cfg(feature = "crc32c")]
+impl Checksum for Crc32c {
+    const SIZE: usize = 4;
+
+    fn checksum(bytes: &[u8]) -> u64 {
+        crc32c::crc32c(bytes) as u64
+    }
+}
+
+/// The 64 bits [XXH3](https://xxhash.com) hash, very fast but not hardware accelerated.
+#[cfg(feature = "xxhash")]
+pub enum Xxh3 {}
+
+#[cfg(feature = "xxhash")]
+impl Checksum for Xxh3 {
+    const SIZE: usize = 8;
+
+    fn checksum(bytes: &[u8]) -> u64 {
+        xxhash_rust::xxh3::xxh3_64(bytes)
+    }
+}
+
+/// The decoding error returned when the checksum of the stored bytes doesn't match.
+///
+/// It can be retrieved by downcasting the error returned by the codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    /// The checksum that was stored along with the bytes,
+    /// `None` if the bytes were too short to contain one.
+    pub stored: Option<u64>,
+    /// The checksum computed from the stored bytes.
+    pub computed: u64,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.stored {
+            Some(stored) => write!(
+                f,
+                "checksum mismatch: stored {:#x} but computed {:#x}",
+                stored, self.computed
+            ),
+            None => f.write_str("checksum mismatch: the bytes are too short to contain one"),
+        }
+    }
+}
+
+impl error::Error for ChecksumMismatch {}
+
+/// Describes a type that is encoded by `C` and followed by
+/// a checksum of the encoded bytes, [`Crc32c`] by default.
+///
+/// The checksum is verified before decoding the bytes, a [`ChecksumMismatch`]
+/// error is returned if it doesn't match. As the checksum is stored after the
+/// encoded bytes, the inner codec can still borrow from the original slice.
+///
+/// ```
+/// # #[cfg(feature = "crc32c")] {
+/// use heed_types::{Checksummed, ChecksumMismatch, Str};
+/// use heed_traits::{BytesDecode, BytesEncode};
+///
+/// let mut bytes = Checksummed::<Str>::bytes_encode("hello").unwrap().into_owned();
+/// assert_eq!(Checksummed::<Str>::bytes_decode(&bytes).unwrap(), "hello");
+///
+/// bytes[0] = b'j';
+/// let error = Checksummed::<Str>::bytes_decode(&bytes).unwrap_err();
+/// assert!(error.downcast_ref::<ChecksumMismatch>().is_some());
+/// # }
+/// ```
+#[cfg(feature = "crc32c")]
+pub struct Checksummed<C, H = Crc32c>(PhantomData<(C, H)>);
+
+/// Describes a type that is encoded by `C` and followed by
+/// a checksum of the encoded bytes computed by `H`.
+///
+/// The `crc32c` feature makes the CRC32C checksum the default one.
+#[cfg(not(feature = "crc32c"))]
+pub struct Checksummed<C, H>(PhantomData<(C, H)>);
+
+impl<'a, C, H> BytesEncode<'a> for Checksummed<C, H>
+where
+    C: BytesEncode<'a>,
+    H: Checksum,
+{
+    type EItem = C::EItem;
+
+    fn bytes_encode(item: &'a Self::EItem) -> Result<Cow<'a, [u8]>, BoxedError> {
+        let mut buffer = C::bytes_encode(item)?.into_owned();
+        let checksum = H::checksum(&buffer).to_le_bytes();
+        buffer.extend_from_slice(&checksum[..H::SIZE]);
+        Ok(Cow::Owned(buffer))
+    }
+
+    fn encode_into(item: &'a Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+        let start = buffer.len();
+        C::encode_into(item, buffer)?;
+        let checksum = H::checksum(&buffer[start..]).to_le_bytes();
+        buffer.extend_from_slice(&checksum[..H::SIZE]);
+        Ok(())
+    }
+
+    fn encoded_size(item: &'a Self::EItem) -> Option<usize> {
+        C::encoded_size(item).map(|size| size + H::SIZE)
+    }
+
+    fn encode_to_slice(item: &'a Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+        let (bytes, checksum) = output.split_at_mut(output.len() - H::SIZE);
+        C::encode_to_slice(item, bytes)?;
+        checksum.copy_from_slice(&H::checksum(bytes).to_le_bytes()[..H::SIZE]);
+        Ok(())
+    }
+}
+
+impl<'a, C, H> BytesDecode<'a> for Checksummed<C, H>
+where
+    C: BytesDecode<'a>,
+    H: Checksum,
+{
+    type DItem = C::DItem;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+        C::bytes_decode(H::verify(bytes)?)
+    }
+}
+
+unsafe impl<C, H> Send for Checksummed<C, H> {}
+
+unsafe impl<C, H> Sync for Checksummed<C, H> {}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::BigEndian;
+
+    use super::*;
+    use crate::{Str, U32};
+
+    /// Encodes a value, checks it round trips and that flipping any byte is detected.
+    fn assert_detects_flipped_bytes<H: Checksum>() {
+        let mut bytes = Checksummed::<Str, H>::bytes_encode("hello world").unwrap().into_owned();
+        assert_eq!(bytes.len(), "hello world".len() + H::SIZE);
+        assert_eq!(Checksummed::<Str, H>::bytes_decode(&bytes).unwrap(), "hello world");
+
+        for i in 0..bytes.len() {
+            bytes[i] ^= 0x01;
+            let error = Checksummed::<Str, H>::bytes_decode(&bytes).unwrap_err();
+            let mismatch = error.downcast_ref::<ChecksumMismatch>().unwrap();
+            assert!(mismatch.stored.is_some());
+            assert_ne!(mismatch.stored, Some(mismatch.computed));
+            bytes[i] ^= 0x01;
+        }
+    }
+
+    fn assert_rejects_short_bytes<H: Checksum>() {
+        let bytes = vec![0; H::SIZE - 1];
+        let error = Checksummed::<Str, H>::bytes_decode(&bytes).unwrap_err();
+        let mismatch = error.downcast_ref::<ChecksumMismatch>().unwrap();
+        assert_eq!(mismatch.stored, None);
+    }
+
+    fn assert_encodings_agree<H: Checksum>() {
+        type C<H> = Checksummed<U32<BigEndian>, H>;
+        let encoded = C::<H>::bytes_encode(&42).unwrap();
+
+        let mut buffer = b"prefix".to_vec();
+        C::<H>::encode_into(&42, &mut buffer).unwrap();
+        assert_eq!(&buffer[..6], b"prefix");
+        assert_eq!(&buffer[6..], &encoded[..]);
+
+        let size = C::<H>::encoded_size(&42).unwrap();
+        let mut output = vec![0; size];
+        C::<H>::encode_to_slice(&42, &mut output).unwrap();
+        assert_eq!(&output[..], &encoded[..]);
+    }
+
+    #[cfg(feature = "crc32c")]
+    #[test]
+    fn crc32c() {
+        let bytes = Checksummed::<Str, Crc32c>::bytes_encode("hello").unwrap();
+        assert_eq!(bytes[5..], crc32c::crc32c(b"hello").to_le_bytes());
+
+        assert_detects_flipped_bytes::<Crc32c>();
+        assert_rejects_short_bytes::<Crc32c>();
+        assert_encodings_agree::<Crc32c>();
+    }
+
+    #[cfg(feature = "xxhash")]
+    #[test]
+    fn xxh3() {
+        let bytes = Checksummed::<Str, Xxh3>::bytes_encode("hello").unwrap();
+        assert_eq!(bytes[5..], xxhash_rust::xxh3::xxh3_64(b"hello").to_le_bytes());
+
+        assert_detects_flipped_bytes::<Xxh3>();
+        assert_rejects_short_bytes::<Xxh3>();
+        assert_encodings_agree::<Xxh3>();
+    }
+}
@@ -0,0 +1,362 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+use heed_traits::{BoxedError, BytesDecode, BytesEncode};
+
+/// The header byte of the items stored without compression.
+const RAW: u8 = 0;
+
+/// A compression algorithm that can be used by the [`Compressed`] codec.
+pub trait Compression {
+    /// The header byte identifying the items compressed with this algorithm, must not be `0`.
+    const ID: u8;
+
+    /// Compresses the given bytes, using the dictionary if one is given.
+    fn compress(bytes: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>, BoxedError>;
+
+    /// Decompresses the given bytes into `size` bytes, using the dictionary if one is given.
+    fn decompress(
+        bytes: &[u8],
+        size: usize,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Vec<u8>, BoxedError>;
+}
+
+/// Defines how the [`Compressed`] codec compresses the items.
+pub trait CompressionOptions {
+    /// The encoded items smaller than this number of bytes are stored raw.
+    const THRESHOLD: usize = 64;
+
+    /// The maximum size of the decompressed items, 64 MiB by default.
+    ///
+    /// The size stored in the header of a corrupted item is refused when it is bigger,
+    /// instead of allocating it. The bigger items are stored raw.
+    const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+    /// The dictionary shared by all the items, it must never change once items are written.
+    fn dictionary() -> Option<&'static [u8]> {
+        None
+    }
+}
+
+/// The default [`CompressionOptions`], items of 64 bytes and more are compressed without dictionary.
+pub enum DefaultCompression {}
+
+impl CompressionOptions for DefaultCompression {}
+
+/// Describes a type that is encoded by `C` and then compressed with the `A` algorithm.
+///
+/// The stored bytes are prefixed by a header byte that tells whether they
+/// are compressed. When they are, it is followed by the size of the
+/// uncompressed bytes as a little-endian `u32`. The items that are smaller than the
+/// [threshold](CompressionOptions::THRESHOLD) or that don't compress well are stored raw.
+///
+/// As the decoded items can't borrow from the decompressed bytes,
+/// the inner codec must decode into owned types.
+///
+/// ```
+/// # #[cfg(all(feature = "lz4", feature = "serde-json"))] {
+/// use heed_types::{Compressed, Lz4, SerdeJson};
+/// use heed_traits::{BytesDecode, BytesEncode};
+///
+/// type Codec = Compressed<Lz4, SerdeJson<Vec<String>>>;
+///
+/// let item = vec![String::from("hello world"); 100];
+/// let bytes = Codec::bytes_encode(&item).unwrap();
+/// assert!(bytes.len() < 100);
+/// assert_eq!(Codec::bytes_decode(&bytes).unwrap(), item);
+/// # }
+/// ```
+pub struct Compressed<A, C, O = DefaultCompression>(PhantomData<(A, C, O)>);
+
+impl<'a, A, C, O> BytesEncode<'a> for Compressed<A, C, O>
+where
+    A: Compression,
+    C: BytesEncode<'a>,
+    O: CompressionOptions,
+{
+    type EItem = C::EItem;
+
+    fn bytes_encode(item: &'a Self::EItem) -> Result<Cow<'a, [u8]>, BoxedError> {
+        let bytes = C::bytes_encode(item)?;
+
+        if bytes.len() >= O::THRESHOLD && bytes.len() <= O::MAX_DECOMPRESSED_SIZE {
+            if let Ok(size) = u32::try_from(bytes.len()) {
+                let compressed = A::compress(&bytes, O::dictionary())?;
+                if compressed.len() + 4 < bytes.len() {
+                    let mut buffer = Vec::with_capacity(5 + compressed.len());
+                    buffer.push(A::ID);
+                    buffer.extend_from_slice(&size.to_le_bytes());
+                    buffer.extend_from_slice(&compressed);
+                    return Ok(Cow::Owned(buffer));
+                }
+            }
+        }
+
+        let mut buffer = Vec::with_capacity(1 + bytes.len());
+        buffer.push(RAW);
+        buffer.extend_from_slice(&bytes);
+        Ok(Cow::Owned(buffer))
+    }
+}
+
+impl<'a, A, C, O, T> BytesDecode<'a> for Compressed<A, C, O>
+where
+    A: Compression,
+    C: for<'b> BytesDecode<'b, DItem = T>,
+    O: CompressionOptions,
+    T: 'a,
+{
+    type DItem = T;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+        match bytes.split_first() {
+            Some((&RAW, bytes)) => C::bytes_decode(bytes),
+            Some((&id, bytes)) if id == A::ID && bytes.len() >= 4 => {
+                let (size, bytes) = bytes.split_at(4);
+                let size = u32::from_le_bytes(size.try_into().unwrap()) as usize;
+                if size > O::MAX_DECOMPRESSED_SIZE {
+                    return Err(format!(
+                        "the decompressed size {} is bigger than the maximum of {}",
+                        size,
+                        O::MAX_DECOMPRESSED_SIZE
+                    )
+                    .into());
+                }
+                let decompressed = A::decompress(bytes, size, O::dictionary())?;
+                if decompressed.len() != size {
+                    return Err("the decompressed size doesn't match the header".into());
+                }
+                C::bytes_decode(&decompressed)
+            }
+            Some((id, _)) => Err(format!("unknown compression header {}", id).into()),
+            None => Err("missing the compression header".into()),
+        }
+    }
+}
+
+unsafe impl<A, C, O> Send for Compressed<A, C, O> {}
+
+unsafe impl<A, C, O> Sync for Compressed<A, C, O> {}
+
+/// The [LZ4](https://lz4.org) compression algorithm, fast but with a lower compression ratio.
+#[cfg(feature = "lz4")]
+pub enum Lz4 {}
+
+#[cfg(feature = "lz4")]
+impl Compression for Lz4 {
+    const ID: u8 = 1;
+
+    fn compress(bytes: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>, BoxedError> {
+        match dictionary {
+            Some(dictionary) => Ok(lz4_flex::block::compress_with_dict(bytes, dictionary)),
+            None => Ok(lz4_flex::block::compress(bytes)),
+        }
+    }
+
+    fn decompress(
+        bytes: &[u8],
+        size: usize,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Vec<u8>, BoxedError> {
+        match dictionary {
+            Some(dictionary) => lz4_flex::block::decompress_with_dict(bytes, size, dictionary),
+            None => lz4_flex::block::decompress(bytes, size),
+        }
+        .map_err(Into::into)
+    }
+}
+
+/// The [Zstandard](https://facebook.github.io/zstd) compression algorithm
+/// at its default level, slower than LZ4 but with a better compression ratio.
+#[cfg(feature = "zstd")]
+pub enum Zstd {}
+
+#[cfg(feature = "zstd")]
+impl Compression for Zstd {
+    const ID: u8 = 2;
+
+    fn compress(bytes: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>, BoxedError> {
+        let level = zstd::DEFAULT_COMPRESSION_LEVEL;
+        match dictionary {
+            Some(dictionary) => {
+                zstd::bulk::Compressor::with_dictionary(level, dictionary)?.compress(bytes)
+            }
+            None => zstd::bulk::compress(bytes, level),
+        }
+        .map_err(Into::into)
+    }
+
+    fn decompress(
+        bytes: &[u8],
+        size: usize,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Vec<u8>, BoxedError> {
+        match dictionary {
+            Some(dictionary) => {
+                zstd::bulk::Decompressor::with_dictionary(dictionary)?.decompress(bytes, size)
+            }
+            None => zstd::bulk::decompress(bytes, size),
+        }
+        .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedSlice;
+
+    type Bytes = OwnedSlice<u8>;
+
+    /// A naive run-length encoding, to test the codec without the compression features.
+    enum RunLength {}
+
+    impl Compression for RunLength {
+        const ID: u8 = 42;
+
+        fn compress(bytes: &[u8], _dictionary: Option<&[u8]>) -> Result<Vec<u8>, BoxedError> {
+            let mut output: Vec<u8> = Vec::new();
+            for &byte in bytes {
+                let len = output.len();
+                if len >= 2 && output[len - 1] == byte && output[len - 2] < u8::MAX {
+                    output[len - 2] += 1;
+                } else {
+                    output.extend_from_slice(&[1, byte]);
+                }
+            }
+            Ok(output)
+        }
+
+        fn decompress(
+            bytes: &[u8],
+            size: usize,
+            _dictionary: Option<&[u8]>,
+        ) -> Result<Vec<u8>, BoxedError> {
+            let mut output = Vec::with_capacity(size);
+            for pair in bytes.chunks(2) {
+                match pair {
+                    [count, byte] => output.resize(output.len() + *count as usize, *byte),
+                    _ => return Err("truncated run".into()),
+                }
+            }
+            Ok(output)
+        }
+    }
+
+    type Codec = Compressed<RunLength, Bytes>;
+
+    #[test]
+    fn small_and_incompressible_items_are_stored_raw() {
+        let small = [7u8; 63];
+        let bytes = Codec::bytes_encode(&small[..]).unwrap();
+        assert_eq!(bytes[0], RAW);
+        assert_eq!(&bytes[1..], &small[..]);
+        assert_eq!(Codec::bytes_decode(&bytes).unwrap(), &small[..]);
+
+        let incompressible: Vec<u8> = (0..=255).collect();
+        let bytes = Codec::bytes_encode(&incompressible).unwrap();
+        assert_eq!(bytes[0], RAW);
+        assert_eq!(Codec::bytes_decode(&bytes).unwrap(), &incompressible[..]);
+    }
+
+    #[test]
+    fn compressed_items_have_a_header() {
+        let item = [7u8; 64];
+        let bytes = Codec::bytes_encode(&item[..]).unwrap();
+        assert_eq!(bytes[0], RunLength::ID);
+        assert_eq!(bytes[1..5], 64u32.to_le_bytes());
+        assert_eq!(&bytes[5..], &[64, 7]);
+        assert_eq!(Codec::bytes_decode(&bytes).unwrap(), &item[..]);
+    }
+
+    #[test]
+    fn corrupted_headers() {
+        let bytes = Codec::bytes_encode(&[7u8; 100][..]).unwrap().into_owned();
+
+        // A huge size is refused before anything is allocated.
+        let mut corrupted = bytes.clone();
+        corrupted[1..5].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = Codec::bytes_decode(&corrupted).unwrap_err();
+        assert!(error.to_string().contains("bigger than the maximum"), "{}", error);
+
+        let mut corrupted = bytes.clone();
+        corrupted[1..5].copy_from_slice(&99u32.to_le_bytes());
+        let error = Codec::bytes_decode(&corrupted).unwrap_err();
+        assert_eq!(error.to_string(), "the decompressed size doesn't match the header");
+
+        let mut corrupted = bytes.clone();
+        corrupted[0] = 3;
+        let error = Codec::bytes_decode(&corrupted).unwrap_err();
+        assert_eq!(error.to_string(), "unknown compression header 3");
+
+        assert!(Codec::bytes_decode(&bytes[..3]).is_err());
+        assert!(Codec::bytes_decode(&[]).is_err());
+    }
+
+    #[test]
+    fn items_bigger_than_the_maximum_are_stored_raw() {
+        enum Tiny {}
+
+        impl CompressionOptions for Tiny {
+            const MAX_DECOMPRESSED_SIZE: usize = 100;
+        }
+
+        type TinyCodec = Compressed<RunLength, Bytes, Tiny>;
+
+        assert_eq!(TinyCodec::bytes_encode(&[7u8; 100][..]).unwrap()[0], RunLength::ID);
+        let item = [7u8; 101];
+        let bytes = TinyCodec::bytes_encode(&item[..]).unwrap();
+        assert_eq!(bytes[0], RAW);
+        assert_eq!(TinyCodec::bytes_decode(&bytes).unwrap(), &item[..]);
+    }
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    mod algorithms {
+        use super::*;
+
+        const DICTIONARY: &[u8] = b"the quick brown fox jumps over the lazy dog, \
+            the quick brown fox jumps over the lazy dog";
+
+        enum WithDictionary {}
+
+        impl CompressionOptions for WithDictionary {
+            fn dictionary() -> Option<&'static [u8]> {
+                Some(DICTIONARY)
+            }
+        }
+
+        fn assert_round_trips<A: Compression>() {
+            let item = b"the quick brown fox jumps over the lazy dog ".repeat(10);
+            let bytes = Compressed::<A, Bytes>::bytes_encode(&item[..]).unwrap();
+            assert_eq!(bytes[0], A::ID);
+            assert!(bytes.len() < item.len());
+            assert_eq!(Compressed::<A, Bytes>::bytes_decode(&bytes).unwrap(), &item[..]);
+
+            // The dictionary helps to compress items that look like it.
+            let item = b"the quick brown fox jumps over the lazy dog, the lazy cat sleeps all the day long";
+            let without = Compressed::<A, Bytes>::bytes_encode(&item[..]).unwrap();
+            let with = Compressed::<A, Bytes, WithDictionary>::bytes_encode(&item[..]).unwrap();
+            assert_eq!(with[0], A::ID);
+            assert!(with.len() < without.len());
+            let decoded = Compressed::<A, Bytes, WithDictionary>::bytes_decode(&with).unwrap();
+            assert_eq!(decoded, &item[..]);
+
+            let mut corrupted = with.into_owned();
+            corrupted[1..5].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(Compressed::<A, Bytes, WithDictionary>::bytes_decode(&corrupted).is_err());
+        }
+
+        #[test]
+        #[cfg(feature = "lz4")]
+        fn lz4() {
+            assert_round_trips::<Lz4>();
+        }
+
+        #[test]
+        #[cfg(feature = "zstd")]
+        fn zstd() {
+            assert_round_trips::<Zstd>();
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::{error, fmt};
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use heed_traits::{BoxedError, BytesDecode, BytesDecodeWith, BytesEncode, BytesEncodeWith};
+
+/// The size of the key id that prefixes the encrypted items.
+const KEY_ID_SIZE: usize = 4;
+
+/// The size of the random nonce that follows the key id.
+const NONCE_SIZE: usize = 24;
+
+/// The 256 bits keys used to encrypt and decrypt the items of the [`Encrypted`] codec.
+///
+/// Every key is identified by an id that is stored along with the encrypted items. The
+/// current key is used to encrypt the new items while the other ones are only used to
+/// decrypt the items that were encrypted before a key rotation.
+///
+/// They are given to the [`Encrypted`] codecs as their context, usually the
+/// keys installed in the environment with `Env::set_encryption_keys`.
+#[derive(Clone)]
+pub struct EncryptionKeys {
+    current: u32,
+    keys: HashMap<u32, Key>,
+}
+
+impl EncryptionKeys {
+    /// Creates a set of keys that uses the given key to encrypt the items.
+    pub fn new(id: u32, key: [u8; 32]) -> EncryptionKeys {
+        EncryptionKeys { current: id, keys: HashMap::from([(id, Key::from(key))]) }
+    }
+
+    /// Adds a key that is only used to decrypt the items previously encrypted with it.
+    pub fn with_old_key(mut self, id: u32, key: [u8; 32]) -> EncryptionKeys {
+        self.keys.entry(id).or_insert_with(|| Key::from(key));
+        self
+    }
+
+    /// Adds a key and uses it to encrypt the new items, the previous keys are kept to
+    /// decrypt the existing items.
+    pub fn rotate(&mut self, id: u32, key: [u8; 32]) {
+        self.keys.insert(id, Key::from(key));
+        self.current = id;
+    }
+
+    /// Returns the id of the key used to encrypt the new items.
+    pub fn current_id(&self) -> u32 {
+        self.current
+    }
+
+    fn key(&self, id: u32) -> Result<&Key, EncryptionError> {
+        self.keys.get(&id).ok_or(EncryptionError::UnknownKey(id))
+    }
+}
+
+impl fmt::Debug for EncryptionKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ids: Vec<_> = self.keys.keys().collect();
+        ids.sort_unstable();
+        f.debug_struct("EncryptionKeys").field("current", &self.current).field("ids", &ids).finish()
+    }
+}
+
+/// The error returned by the [`Encrypted`] codec when an item can't be encrypted or decrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionError {
+    /// The key the item was encrypted with is not part of the installed keys.
+    UnknownKey(u32),
+    /// The encrypted bytes are too short to contain the key id and the nonce.
+    Truncated,
+    /// The bytes were tampered with or not encrypted with this key.
+    Aead,
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncryptionError::UnknownKey(id) => write!(f, "unknown encryption key id {}", id),
+            EncryptionError::Truncated => f.write_str("the encrypted bytes are truncated"),
+            EncryptionError::Aead => f.write_str("authenticated encryption failed"),
+        }
+    }
+}
+
+impl error::Error for EncryptionError {}
+
+/// Describes a type that is encoded by `C` and then encrypted with XChaCha20-Poly1305.
+///
+/// The stored bytes are made of the id of the key used, as a little-endian `u32`,
+/// a random nonce and the encrypted bytes followed by the authentication tag. The key
+/// id is authenticated along with the bytes. The keys are given as the context of the
+/// codec, an [`EncryptionError`] is returned if an item can't be decrypted with them.
+///
+/// As the decoded items can't borrow from the decrypted bytes,
+/// the inner codec must decode into owned types.
+///
+/// ```
+/// # #[cfg(feature = "serde-bincode")] {
+/// use heed_types::{Encrypted, EncryptionKeys, SerdeBincode};
+/// use heed_traits::{BytesDecodeWith, BytesEncodeWith};
+///
+/// let keys = EncryptionKeys::new(1, [42; 32]);
+/// let secret = String::from("secret");
+/// let bytes = Encrypted::<SerdeBincode<String>>::bytes_encode_with(&secret, &keys).unwrap();
+///
+/// let mut keys = keys;
+/// keys.rotate(2, [43; 32]);
+/// assert_eq!(Encrypted::<SerdeBincode<String>>::bytes_decode_with(&bytes, &keys).unwrap(), "secret");
+/// # }
+/// ```
+pub struct Encrypted<C>(PhantomData<C>);
+
+impl<'a, C> BytesEncodeWith<'a, EncryptionKeys> for Encrypted<C>
+where
+    C: BytesEncode<'a>,
+{
+    type EItem = C::EItem;
+
+    fn bytes_encode_with(
+        item: &'a Self::EItem,
+        keys: &EncryptionKeys,
+    ) -> Result<Cow<'a, [u8]>, BoxedError> {
+        let bytes = C::bytes_encode(item)?;
+        let key = keys.key(keys.current)?;
+        let key_id = keys.current.to_le_bytes();
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload { msg: &bytes, aad: &key_id };
+        let encrypted = XChaCha20Poly1305::new(key)
+            .encrypt(&nonce, payload)
+            .map_err(|_| EncryptionError::Aead)?;
+
+        let mut buffer = Vec::with_capacity(KEY_ID_SIZE + NONCE_SIZE + encrypted.len());
+        buffer.extend_from_slice(&key_id);
+        buffer.extend_from_slice(&nonce);
+        buffer.extend_from_slice(&encrypted);
+        Ok(Cow::Owned(buffer))
+    }
+}
+
+impl<'a, C, T> BytesDecodeWith<'a, EncryptionKeys> for Encrypted<C>
+where
+    C: for<'b> BytesDecode<'b, DItem = T>,
+    T: 'a,
+{
+    type DItem = T;
+
+    fn bytes_decode_with(
+        bytes: &'a [u8],
+        keys: &EncryptionKeys,
+    ) -> Result<Self::DItem, BoxedError> {
+        if bytes.len() < KEY_ID_SIZE + NONCE_SIZE {
+            return Err(EncryptionError::Truncated.into());
+        }
+
+        let (key_id, bytes) = bytes.split_at(KEY_ID_SIZE);
+        let (nonce, encrypted) = bytes.split_at(NONCE_SIZE);
+        let id = u32::from_le_bytes(key_id.try_into().unwrap());
+
+        let key = keys.key(id)?;
+        let payload = Payload { msg: encrypted, aad: key_id };
+        let decrypted = XChaCha20Poly1305::new(key)
+            .decrypt(XNonce::from_slice(nonce), payload)
+            .map_err(|_| EncryptionError::Aead)?;
+
+        C::bytes_decode(&decrypted)
+    }
+}
+
+unsafe impl<C> Send for Encrypted<C> {}
+
+unsafe impl<C> Sync for Encrypted<C> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OwnedSlice, Str};
+
+    type Secret = Encrypted<OwnedSlice<u8>>;
+
+    fn decode_error(bytes: &[u8], keys: &EncryptionKeys) -> EncryptionError {
+        let error = Secret::bytes_decode_with(bytes, keys).unwrap_err();
+        *error.downcast_ref::<EncryptionError>().unwrap()
+    }
+
+    #[test]
+    fn round_trip_and_rotation() {
+        let mut keys = EncryptionKeys::new(1, [42; 32]);
+        let old = Secret::bytes_encode_with(b"hello", &keys).unwrap().into_owned();
+        assert_eq!(old[..KEY_ID_SIZE], 1u32.to_le_bytes());
+        assert_eq!(old.len(), KEY_ID_SIZE + NONCE_SIZE + b"hello".len() + 16);
+
+        keys.rotate(2, [43; 32]);
+        assert_eq!(keys.current_id(), 2);
+        let new = Secret::bytes_encode_with(b"hello", &keys).unwrap().into_owned();
+        assert_eq!(new[..KEY_ID_SIZE], 2u32.to_le_bytes());
+
+        assert_eq!(Secret::bytes_decode_with(&old, &keys).unwrap(), b"hello");
+        assert_eq!(Secret::bytes_decode_with(&new, &keys).unwrap(), b"hello");
+
+        // The same item is encrypted with a new nonce every time.
+        let again = Secret::bytes_encode_with(b"hello", &keys).unwrap();
+        assert_ne!(new, again.into_owned());
+    }
+
+    #[test]
+    fn wrong_and_unknown_keys() {
+        let keys = EncryptionKeys::new(1, [42; 32]);
+        let bytes = Secret::bytes_encode_with(b"hello", &keys).unwrap().into_owned();
+
+        let wrong = EncryptionKeys::new(1, [43; 32]);
+        assert_eq!(decode_error(&bytes, &wrong), EncryptionError::Aead);
+
+        let other = EncryptionKeys::new(2, [42; 32]);
+        assert_eq!(decode_error(&bytes, &other), EncryptionError::UnknownKey(1));
+    }
+
+    #[test]
+    fn tampered_and_truncated_bytes() {
+        let keys = EncryptionKeys::new(1, [42; 32]).with_old_key(2, [42; 32]);
+        let bytes = Secret::bytes_encode_with(b"hello", &keys).unwrap().into_owned();
+
+        for i in 0..bytes.len() {
+            let mut tampered = bytes.clone();
+            tampered[i] ^= 0x01;
+            let error = decode_error(&tampered, &keys);
+            // Flipping the key id selects the old key, which is the same,
+            // but the key id is authenticated along with the bytes.
+            assert!(matches!(error, EncryptionError::Aead | EncryptionError::UnknownKey(_)));
+        }
+
+        let mut changed_id = bytes.clone();
+        changed_id[..KEY_ID_SIZE].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(decode_error(&changed_id, &keys), EncryptionError::Aead);
+
+        let error = decode_error(&bytes[..KEY_ID_SIZE + NONCE_SIZE - 1], &keys);
+        assert_eq!(error, EncryptionError::Truncated);
+        assert_eq!(decode_error(&bytes[..bytes.len() - 1], &keys), EncryptionError::Aead);
+    }
+
+    #[test]
+    fn debug_does_not_leak_the_keys() {
+        let keys = EncryptionKeys::new(3, [42; 32]).with_old_key(1, [43; 32]);
+        assert_eq!(format!("{:?}", keys), "EncryptionKeys { current: 3, ids: [1, 3] }");
+
+        let bytes = Encrypted::<Str>::bytes_encode_with("hello", &keys).unwrap();
+        assert!(!bytes.windows(5).any(|window| window == b"hello"));
+    }
+}
@@ -14,6 +14,20 @@ impl BytesEncode<'_> for U8 {
     fn bytes_encode(item: &Self::EItem) -> Result<Cow<[u8]>, BoxedError> {
         Ok(Cow::from([*item].to_vec()))
     }
+
+    fn encode_into(item: &Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+        buffer.push(*item);
+        Ok(())
+    }
+
+    fn encoded_size(_item: &Self::EItem) -> Option<usize> {
+        Some(1)
+    }
+
+    fn encode_to_slice(item: &Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+        output[0] = *item;
+        Ok(())
+    }
 }
 
 impl BytesDecode<'_> for U8 {
@@ -33,6 +47,20 @@ impl BytesEncode<'_> for I8 {
     fn bytes_encode(item: &Self::EItem) -> Result<Cow<[u8]>, BoxedError> {
         Ok(Cow::from([*item as u8].to_vec()))
     }
+
+    fn encode_into(item: &Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+        buffer.push(*item as u8);
+        Ok(())
+    }
+
+    fn encoded_size(_item: &Self::EItem) -> Option<usize> {
+        Some(1)
+    }
+
+    fn encode_to_slice(item: &Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+        output[0] = *item as u8;
+        Ok(())
+    }
 }
 
 impl BytesDecode<'_> for I8 {
@@ -59,6 +87,22 @@ macro_rules! define_type {
                 O::$write_method(&mut buf, *item);
                 Ok(Cow::from(buf))
             }
+
+            fn encode_into(item: &Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+                let mut buf = [0; size_of::<Self::EItem>()];
+                O::$write_method(&mut buf, *item);
+                buffer.extend_from_slice(&buf);
+                Ok(())
+            }
+
+            fn encoded_size(_item: &Self::EItem) -> Option<usize> {
+                Some(size_of::<Self::EItem>())
+            }
+
+            fn encode_to_slice(item: &Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+                O::$write_method(output, *item);
+                Ok(())
+            }
         }
 
         impl<O: ByteOrder> BytesDecode<'_> for $name<O> {
@@ -0,0 +1,176 @@
+use heed_traits::BoxedError;
+
+/// A type that can be encoded as a part of an order-preserving key.
+///
+/// The encoded bytes of two values compare, lexicographically, in the same order as
+/// the values themselves. Concatenating the encodings of multiple fields therefore
+/// produces keys that sort like the fields compared one after the other, this is
+/// what the `HeedKey` derive macro generates for structs.
+///
+/// - unsigned integers are stored in big-endian,
+/// - signed integers are stored in big-endian with the sign bit flipped,
+/// - strings and byte vectors escape their `0x00` bytes as `0x00 0xFF`
+///   and are terminated by `0x00 0x00`,
+/// - arrays are the concatenation of their encoded elements.
+///
+/// ```
+/// use heed_types::KeyField;
+///
+/// fn encode<T: KeyField>(value: &T) -> Vec<u8> {
+///     let mut buffer = Vec::new();
+///     value.encode_key_field(&mut buffer);
+///     buffer
+/// }
+///
+/// assert!(encode(&-1i32) < encode(&0i32));
+/// assert!(encode(&String::from("ab")) < encode(&String::from("ab\0")));
+/// assert!(encode(&(String::from("a"), 2u8)) < encode(&(String::from("ab"), 1u8)));
+///
+/// let bytes = encode(&[3u16, 7]);
+/// assert_eq!(<[u16; 2]>::decode_key_field(&mut &bytes[..]).unwrap(), [3, 7]);
+/// ```
+pub trait KeyField: Sized {
+    /// Appends the order-preserving encoding of this value to the buffer.
+    fn encode_key_field(&self, buffer: &mut Vec<u8>);
+
+    /// Decodes a value from the start of the bytes and advances them past it.
+    fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError>;
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], BoxedError> {
+    if bytes.len() < len {
+        return Err(format!("expected {} more bytes to decode a key field", len).into());
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+macro_rules! impl_unsigned {
+    ($($ty:ty),+) => {
+        $(
+            impl KeyField for $ty {
+                fn encode_key_field(&self, buffer: &mut Vec<u8>) {
+                    buffer.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError> {
+                    let head = take(bytes, std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_be_bytes(head.try_into().unwrap()))
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! impl_signed {
+    ($($ty:ty => $unsigned:ty),+) => {
+        $(
+            impl KeyField for $ty {
+                fn encode_key_field(&self, buffer: &mut Vec<u8>) {
+                    let flipped = (*self as $unsigned) ^ (1 << (<$unsigned>::BITS - 1));
+                    flipped.encode_key_field(buffer);
+                }
+
+                fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError> {
+                    let flipped = <$unsigned>::decode_key_field(bytes)?;
+                    Ok((flipped ^ (1 << (<$unsigned>::BITS - 1))) as $ty)
+                }
+            }
+        )+
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128);
+impl_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+impl KeyField for bool {
+    fn encode_key_field(&self, buffer: &mut Vec<u8>) {
+        buffer.push(*self as u8);
+    }
+
+    fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError> {
+        match take(bytes, 1)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            [byte] => Err(format!("invalid boolean key field byte {}", byte).into()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl KeyField for Vec<u8> {
+    fn encode_key_field(&self, buffer: &mut Vec<u8>) {
+        for &byte in self {
+            buffer.push(byte);
+            if byte == 0 {
+                buffer.push(0xFF);
+            }
+        }
+        buffer.extend_from_slice(&[0, 0]);
+    }
+
+    fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError> {
+        let mut output = Vec::new();
+        loop {
+            match take(bytes, 1)? {
+                [0] => match take(bytes, 1)? {
+                    [0] => return Ok(output),
+                    [0xFF] => output.push(0),
+                    _ => return Err("invalid escape sequence in a key field".into()),
+                },
+                [byte] => output.push(*byte),
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+impl KeyField for String {
+    fn encode_key_field(&self, buffer: &mut Vec<u8>) {
+        for &byte in self.as_bytes() {
+            buffer.push(byte);
+            if byte == 0 {
+                buffer.push(0xFF);
+            }
+        }
+        buffer.extend_from_slice(&[0, 0]);
+    }
+
+    fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError> {
+        let output = Vec::<u8>::decode_key_field(bytes)?;
+        String::from_utf8(output).map_err(Into::into)
+    }
+}
+
+impl<T: KeyField, const N: usize> KeyField for [T; N] {
+    fn encode_key_field(&self, buffer: &mut Vec<u8>) {
+        self.iter().for_each(|item| item.encode_key_field(buffer));
+    }
+
+    fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError> {
+        let items = (0..N).map(|_| T::decode_key_field(bytes)).collect::<Result<Vec<_>, _>>()?;
+        Ok(items.try_into().unwrap_or_else(|_| unreachable!()))
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($t:ident),+) => {
+        impl<$($t: KeyField),+> KeyField for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn encode_key_field(&self, buffer: &mut Vec<u8>) {
+                let ($($t,)+) = self;
+                $($t.encode_key_field(buffer);)+
+            }
+
+            fn decode_key_field(bytes: &mut &[u8]) -> Result<Self, BoxedError> {
+                Ok(($($t::decode_key_field(bytes)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
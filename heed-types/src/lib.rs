@@ -5,6 +5,11 @@
 //!   - [`Str`] to store [`str`](primitive@str)s
 //!   - [`Unit`] to store `()` types
 //!   - [`SerdeBincode`],  [`SerdeJson`], or [`SerdeRmp`] to store [`Serialize`]/[`Deserialize`] types
+//!   - [`Checksummed`] to detect the corruption of the stored bytes
+//!   - [`Compressed`] to compress big values, using the `lz4` or `zstd` features
+//!   - [`Encrypted`] to encrypt the values at rest, using the `encryption` feature
+//!   - [`Versioned`] to store types that evolve over time and upgrade them when decoding
+//!   - [`KeyField`] types to build order-preserving composite keys, see the `HeedKey` derive
 //!
 //! But if you want to store big types that can be efficiently deserialized then
 //! here is a little table to help you in your quest:
@@ -23,9 +28,12 @@
 
 #![warn(missing_docs)]
 
+mod checksummed;
+mod compressed;
 mod cow_slice;
 mod cow_type;
 mod integer;
+mod key_field;
 mod lazy_decode;
 mod owned_slice;
 mod owned_type;
@@ -33,6 +41,10 @@ mod str;
 mod unaligned_slice;
 mod unaligned_type;
 mod unit;
+mod versioned;
+
+#[cfg(feature = "encryption")]
+mod encrypted;
 
 #[cfg(feature = "serde-bincode")]
 mod serde_bincode;
@@ -45,9 +57,14 @@ mod serde_rmp;
 
 use heed_traits::BoxedError;
 
+#[cfg(feature = "crc32c")]
+pub use self::checksummed::Crc32c;
+pub use self::checksummed::{Checksum, ChecksumMismatch, Checksummed};
+pub use self::compressed::{Compressed, Compression, CompressionOptions, DefaultCompression};
 pub use self::cow_slice::CowSlice;
 pub use self::cow_type::CowType;
 pub use self::integer::*;
+pub use self::key_field::KeyField;
 pub use self::lazy_decode::{Lazy, LazyDecode};
 pub use self::owned_slice::OwnedSlice;
 pub use self::owned_type::OwnedType;
@@ -55,6 +72,7 @@ pub use self::str::Str;
 pub use self::unaligned_slice::UnalignedSlice;
 pub use self::unaligned_type::UnalignedType;
 pub use self::unit::Unit;
+pub use self::versioned::Versioned;
 
 /// Describes a slice of bytes `[u8]` that is totally
 /// borrowed and doesn't depends on any [memory alignment].
@@ -76,9 +94,135 @@ impl heed_traits::BytesDecode<'_> for DecodeIgnore {
     }
 }
 
+#[cfg(feature = "xxhash")]
+pub use self::checksummed::Xxh3;
+#[cfg(feature = "lz4")]
+pub use self::compressed::Lz4;
+#[cfg(feature = "zstd")]
+pub use self::compressed::Zstd;
+#[cfg(feature = "encryption")]
+pub use self::encrypted::{Encrypted, EncryptionError, EncryptionKeys};
 #[cfg(feature = "serde-bincode")]
 pub use self::serde_bincode::SerdeBincode;
 #[cfg(feature = "serde-json")]
 pub use self::serde_json::SerdeJson;
 #[cfg(feature = "serde-rmp")]
 pub use self::serde_rmp::SerdeRmp;
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{BigEndian, LittleEndian};
+    use heed_traits::BytesEncode;
+
+    use super::*;
+
+    /// Checks that `encode_into` appends the bytes returned by `bytes_encode`
+    /// without touching what is already in the buffer.
+    fn assert_encode_into<'a, C>(item: &'a C::EItem)
+    where
+        C: BytesEncode<'a>,
+    {
+        let expected = C::bytes_encode(item).unwrap();
+
+        let mut buffer = Vec::new();
+        C::encode_into(item, &mut buffer).unwrap();
+        assert_eq!(buffer, &expected[..]);
+
+        let mut buffer = b"prefix".to_vec();
+        C::encode_into(item, &mut buffer).unwrap();
+        assert_eq!(&buffer[..6], b"prefix");
+        assert_eq!(&buffer[6..], &expected[..]);
+
+        if let Some(size) = C::encoded_size(item) {
+            assert_eq!(size, expected.len());
+        }
+    }
+
+    #[test]
+    fn encode_into_integers() {
+        assert_encode_into::<U8>(&0xab);
+        assert_encode_into::<I8>(&-42);
+        assert_encode_into::<U16<BigEndian>>(&0x0102);
+        assert_encode_into::<I32<LittleEndian>>(&-0x01020304);
+        assert_encode_into::<U64<BigEndian>>(&u64::MAX);
+        assert_encode_into::<I128<LittleEndian>>(&i128::MIN);
+
+        let mut buffer = vec![0xff];
+        U32::<BigEndian>::encode_into(&0x01020304, &mut buffer).unwrap();
+        assert_eq!(buffer, [0xff, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn encode_into_default_impls() {
+        assert_encode_into::<Str>("hello");
+        assert_encode_into::<ByteSlice>(&[1, 2, 3][..]);
+        assert_encode_into::<Unit>(&());
+        assert_encode_into::<OwnedType<u64>>(&42);
+        assert_encode_into::<OwnedSlice<u32>>(&[1, 2, 3][..]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[derive(serde::Serialize)]
+    struct Item {
+        id: u32,
+        name: String,
+        tags: Vec<String>,
+    }
+
+    #[cfg(feature = "serde")]
+    fn item() -> Item {
+        Item { id: 42, name: String::from("heed"), tags: vec![String::from("lmdb")] }
+    }
+
+    #[cfg(feature = "serde-bincode")]
+    #[test]
+    fn encode_into_serde_bincode() {
+        assert_encode_into::<SerdeBincode<Item>>(&item());
+    }
+
+    #[cfg(feature = "serde-bincode")]
+    #[test]
+    fn encode_to_slice_serde_bincode() {
+        let item = item();
+        let expected = SerdeBincode::<Item>::bytes_encode(&item).unwrap();
+        let size = SerdeBincode::<Item>::encoded_size(&item).unwrap();
+        assert_eq!(size, expected.len());
+
+        let mut output = vec![0; size];
+        SerdeBincode::<Item>::encode_to_slice(&item, &mut output).unwrap();
+        assert_eq!(output, &expected[..]);
+
+        // The output must be completely filled, neither too short nor too long.
+        let mut output = vec![0; size - 1];
+        assert!(SerdeBincode::<Item>::encode_to_slice(&item, &mut output).is_err());
+        let mut output = vec![0; size + 1];
+        assert!(SerdeBincode::<Item>::encode_to_slice(&item, &mut output).is_err());
+    }
+
+    #[cfg(feature = "serde-json")]
+    #[test]
+    fn encode_into_serde_json() {
+        assert_encode_into::<SerdeJson<Item>>(&item());
+    }
+
+    #[cfg(feature = "serde-rmp")]
+    #[test]
+    fn encode_into_serde_rmp() {
+        assert_encode_into::<SerdeRmp<Item>>(&item());
+    }
+
+    #[cfg(feature = "serde-rmp")]
+    #[test]
+    fn encode_to_slice_serde_rmp() {
+        let item = item();
+        let expected = SerdeRmp::<Item>::bytes_encode(&item).unwrap();
+        assert_eq!(SerdeRmp::<Item>::encoded_size(&item), None);
+
+        let mut output = vec![0; expected.len()];
+        SerdeRmp::<Item>::encode_to_slice(&item, &mut output).unwrap();
+        assert_eq!(output, &expected[..]);
+
+        let mut output = vec![0; expected.len() + 1];
+        assert!(SerdeRmp::<Item>::encode_to_slice(&item, &mut output).is_err());
+    }
+}
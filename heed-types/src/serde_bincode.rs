@@ -17,6 +17,24 @@ where
     fn bytes_encode(item: &'a Self::EItem) -> Result<Cow<[u8]>, BoxedError> {
         bincode::serialize(item).map(Cow::Owned).map_err(Into::into)
     }
+
+    fn encode_into(item: &'a Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+        bincode::serialize_into(buffer, item).map_err(Into::into)
+    }
+
+    fn encoded_size(item: &'a Self::EItem) -> Option<usize> {
+        bincode::serialized_size(item).ok().and_then(|size| usize::try_from(size).ok())
+    }
+
+    fn encode_to_slice(item: &'a Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+        let mut remaining = output;
+        bincode::serialize_into(&mut remaining, item)?;
+        if remaining.is_empty() {
+            Ok(())
+        } else {
+            Err("the encoded size doesn't match the size hint".into())
+        }
+    }
 }
 
 impl<'a, T: 'a> BytesDecode<'a> for SerdeBincode<T>
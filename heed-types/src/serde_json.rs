@@ -17,6 +17,10 @@ where
     fn bytes_encode(item: &Self::EItem) -> Result<Cow<[u8]>, BoxedError> {
         serde_json::to_vec(item).map(Cow::Owned).map_err(Into::into)
     }
+
+    fn encode_into(item: &Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+        serde_json::to_writer(buffer, item).map_err(Into::into)
+    }
 }
 
 impl<'a, T: 'a> BytesDecode<'a> for SerdeJson<T>
@@ -0,0 +1,286 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+use heed_traits::{BoxedError, BytesDecode, BytesEncode};
+
+/// Describes a type that went through multiple versions, the codecs of
+/// the versions are given as a tuple, from the oldest to the latest one.
+///
+/// The latest codec is used to encode the items, the encoded bytes are
+/// prefixed by the version number, starting at `1` for the first codec of the tuple.
+/// When decoding, the version byte is used to select the codec of the stored
+/// item, the decoded item is then upgraded to the latest type by going through
+/// the [`From`] implementations of each version to the next one.
+///
+/// It composes with any codec, for example `SerdeBincode`, `SerdeJson` or `SerdeRmp`,
+/// and supports up to 8 versions.
+///
+/// ```
+/// use heed_types::{Versioned, U32, U64};
+/// use heed_traits::{BytesDecode, BytesEncode};
+/// use byteorder::BigEndian;
+///
+/// type V1 = Versioned<(U32<BigEndian>,)>;
+/// type V2 = Versioned<(U32<BigEndian>, U64<BigEndian>)>;
+///
+/// let bytes = V1::bytes_encode(&42).unwrap();
+/// assert_eq!(bytes[0], 1);
+///
+/// // the old item is upgraded to the latest type using `From<u32> for u64`
+/// assert_eq!(V2::bytes_decode(&bytes).unwrap(), 42u64);
+/// ```
+pub struct Versioned<V>(PhantomData<V>);
+
+fn split_version(bytes: &[u8]) -> Result<(u8, &[u8]), BoxedError> {
+    match bytes.split_first() {
+        Some((version, bytes)) => Ok((*version, bytes)),
+        None => Err("missing the version byte of a versioned item".into()),
+    }
+}
+
+fn unknown_version(version: u8, latest: u8) -> BoxedError {
+    format!("unknown item version {} (latest version is {})", version, latest).into()
+}
+
+/// Upgrades the decoded item by going through the `From` implementations of each next version.
+macro_rules! upgrade {
+    ($lt:lifetime, $item:ident;) => {
+        Ok($item)
+    };
+    ($lt:lifetime, $item:ident; $next:ident $(, $rest:ident)*) => {{
+        let $item = <$next as BytesDecode<$lt>>::DItem::from($item);
+        upgrade!($lt, $item; $($rest),*)
+    }};
+}
+
+/// Dispatches the decoding to the codec matching the version and upgrades the decoded item.
+macro_rules! decode_version {
+    ($lt:lifetime, $version:ident, $bytes:ident, $latest:expr;) => {
+        Err(unknown_version($version, $latest))
+    };
+    ($lt:lifetime, $version:ident, $bytes:ident, $latest:expr; $n:literal $v:ident $(, $rn:literal $rv:ident)*) => {
+        if $version == $n {
+            let item = <$v as BytesDecode<$lt>>::bytes_decode($bytes)?;
+            upgrade!($lt, item; $($rv),*)
+        } else {
+            decode_version!($lt, $version, $bytes, $latest; $($rn $rv),*)
+        }
+    };
+}
+
+macro_rules! impl_versioned {
+    ($($n:literal $v:ident),+ ; $latest_n:literal $latest:ident ; $($prev:ident => $next:ident),*) => {
+        impl<'a, $($v: 'a),+> BytesEncode<'a> for Versioned<($($v,)+)>
+        where
+            $latest: BytesEncode<'a>,
+        {
+            type EItem = $latest::EItem;
+
+            fn bytes_encode(item: &'a Self::EItem) -> Result<Cow<'a, [u8]>, BoxedError> {
+                let bytes = $latest::bytes_encode(item)?;
+                let mut buffer = Vec::with_capacity(1 + bytes.len());
+                buffer.push($latest_n);
+                buffer.extend_from_slice(&bytes);
+                Ok(Cow::Owned(buffer))
+            }
+
+            fn encode_into(item: &'a Self::EItem, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+                buffer.push($latest_n);
+                $latest::encode_into(item, buffer)
+            }
+
+            fn encoded_size(item: &'a Self::EItem) -> Option<usize> {
+                $latest::encoded_size(item).map(|size| 1 + size)
+            }
+
+            fn encode_to_slice(item: &'a Self::EItem, output: &mut [u8]) -> Result<(), BoxedError> {
+                output[0] = $latest_n;
+                $latest::encode_to_slice(item, &mut output[1..])
+            }
+        }
+
+        impl<'a, $($v),+> BytesDecode<'a> for Versioned<($($v,)+)>
+        where
+            $($v: BytesDecode<'a>,)+
+            $(<$next as BytesDecode<'a>>::DItem: From<<$prev as BytesDecode<'a>>::DItem>,)*
+        {
+            type DItem = <$latest as BytesDecode<'a>>::DItem;
+
+            fn bytes_decode(bytes: &'a [u8]) -> Result<Self::DItem, BoxedError> {
+                let (version, bytes) = split_version(bytes)?;
+                decode_version!('a, version, bytes, $latest_n; $($n $v),+)
+            }
+        }
+    };
+}
+
+impl_versioned!(1 V1; 1 V1;);
+impl_versioned!(1 V1, 2 V2; 2 V2; V1 => V2);
+impl_versioned!(1 V1, 2 V2, 3 V3; 3 V3; V1 => V2, V2 => V3);
+impl_versioned!(1 V1, 2 V2, 3 V3, 4 V4; 4 V4; V1 => V2, V2 => V3, V3 => V4);
+impl_versioned!(
+    1 V1, 2 V2, 3 V3, 4 V4, 5 V5; 5 V5;
+    V1 => V2, V2 => V3, V3 => V4, V4 => V5
+);
+impl_versioned!(
+    1 V1, 2 V2, 3 V3, 4 V4, 5 V5, 6 V6; 6 V6;
+    V1 => V2, V2 => V3, V3 => V4, V4 => V5, V5 => V6
+);
+impl_versioned!(
+    1 V1, 2 V2, 3 V3, 4 V4, 5 V5, 6 V6, 7 V7; 7 V7;
+    V1 => V2, V2 => V3, V3 => V4, V4 => V5, V5 => V6, V6 => V7
+);
+impl_versioned!(
+    1 V1, 2 V2, 3 V3, 4 V4, 5 V5, 6 V6, 7 V7, 8 V8; 8 V8;
+    V1 => V2, V2 => V3, V3 => V4, V4 => V5, V5 => V6, V6 => V7, V7 => V8
+);
+
+unsafe impl<V> Send for Versioned<V> {}
+
+unsafe impl<V> Sync for Versioned<V> {}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::BigEndian;
+
+    use super::*;
+    use crate::{U32, U64};
+
+    #[cfg(any(feature = "serde-bincode", feature = "serde-json", feature = "serde-rmp"))]
+    mod users {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        pub struct UserV1 {
+            pub name: String,
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        pub struct UserV2 {
+            pub name: String,
+            pub age: Option<u32>,
+        }
+
+        impl From<UserV1> for UserV2 {
+            fn from(UserV1 { name }: UserV1) -> UserV2 {
+                UserV2 { name, age: None }
+            }
+        }
+
+        pub fn old_user() -> UserV1 {
+            UserV1 { name: "kerollmops".into() }
+        }
+
+        pub fn new_user() -> UserV2 {
+            UserV2 { name: "kerollmops".into(), age: Some(30) }
+        }
+    }
+
+    /// Encodes an item with the old codec and makes sure the new one upgrades it,
+    /// then that the new codec decodes what it encodes, with all the encoding methods.
+    #[cfg(any(feature = "serde-bincode", feature = "serde-json", feature = "serde-rmp"))]
+    fn assert_upgrades<Old, New>()
+    where
+        Old: for<'a> BytesEncode<'a, EItem = users::UserV1>,
+        New: for<'a> BytesEncode<'a, EItem = users::UserV2>
+            + for<'a> BytesDecode<'a, DItem = users::UserV2>,
+    {
+        let (old_user, new_user) = (users::old_user(), users::new_user());
+
+        let bytes = Old::bytes_encode(&old_user).unwrap();
+        assert_eq!(bytes[0], 1);
+        let expected = users::UserV2 { name: "kerollmops".into(), age: None };
+        assert_eq!(New::bytes_decode(&bytes).unwrap(), expected);
+
+        let bytes = New::bytes_encode(&new_user).unwrap();
+        assert_eq!(bytes[0], 2);
+        assert_eq!(New::bytes_decode(&bytes).unwrap(), new_user);
+
+        let mut buffer = Vec::new();
+        New::encode_into(&new_user, &mut buffer).unwrap();
+        assert_eq!(buffer, &bytes[..]);
+
+        if let Some(size) = New::encoded_size(&new_user) {
+            let mut output = vec![0; size];
+            New::encode_to_slice(&new_user, &mut output).unwrap();
+            assert_eq!(output, &bytes[..]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde-bincode")]
+    fn compose_with_serde_bincode() {
+        use crate::SerdeBincode;
+        use users::{UserV1, UserV2};
+
+        assert_upgrades::<
+            Versioned<(SerdeBincode<UserV1>,)>,
+            Versioned<(SerdeBincode<UserV1>, SerdeBincode<UserV2>)>,
+        >();
+    }
+
+    #[test]
+    #[cfg(feature = "serde-json")]
+    fn compose_with_serde_json() {
+        use crate::SerdeJson;
+        use users::{UserV1, UserV2};
+
+        assert_upgrades::<
+            Versioned<(SerdeJson<UserV1>,)>,
+            Versioned<(SerdeJson<UserV1>, SerdeJson<UserV2>)>,
+        >();
+    }
+
+    #[test]
+    #[cfg(feature = "serde-rmp")]
+    fn compose_with_serde_rmp() {
+        use crate::SerdeRmp;
+        use users::{UserV1, UserV2};
+
+        assert_upgrades::<
+            Versioned<(SerdeRmp<UserV1>,)>,
+            Versioned<(SerdeRmp<UserV1>, SerdeRmp<UserV2>)>,
+        >();
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde-bincode", feature = "serde-json"))]
+    fn change_the_codec_between_versions() {
+        use crate::{SerdeBincode, SerdeJson};
+        use users::{UserV1, UserV2};
+
+        assert_upgrades::<
+            Versioned<(SerdeBincode<UserV1>,)>,
+            Versioned<(SerdeBincode<UserV1>, SerdeJson<UserV2>)>,
+        >();
+    }
+
+    #[test]
+    fn unknown_version() {
+        type V2 = Versioned<(U32<BigEndian>, U64<BigEndian>)>;
+
+        for version in [0, 3, u8::MAX] {
+            let mut bytes = vec![version];
+            bytes.extend_from_slice(&42u64.to_be_bytes());
+            let error = V2::bytes_decode(&bytes).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("unknown item version {} (latest version is 2)", version)
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_input() {
+        type V2 = Versioned<(U32<BigEndian>, U64<BigEndian>)>;
+
+        let error = V2::bytes_decode(&[]).unwrap_err();
+        assert_eq!(error.to_string(), "missing the version byte of a versioned item");
+
+        // The version is valid but the item is cut, for both the old and the latest codecs.
+        assert!(V2::bytes_decode(&[1, 0, 0, 42]).is_err());
+        assert!(V2::bytes_decode(&[2, 0, 0, 0, 0, 0, 0, 42]).is_err());
+        assert!(V2::bytes_decode(&[2]).is_err());
+        assert_eq!(V2::bytes_decode(&[1, 0, 0, 0, 42]).unwrap(), 42);
+    }
+}
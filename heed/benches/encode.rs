@@ -0,0 +1,84 @@
+//! Compares the allocating `BytesEncode::bytes_encode` path
+//! with the `BytesEncode::encode_into` path using a reused buffer.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use heed::byteorder::BigEndian;
+use heed::types::*;
+use heed::{BytesEncode, Database, EnvOpenOptions};
+
+type Key = U64<BigEndian>;
+type Value = SerdeBincode<(u64, String)>;
+
+fn encode(c: &mut Criterion) {
+    let value = (42, String::from("hello world"));
+    let mut group = c.benchmark_group("encode");
+
+    group.bench_function("u64/bytes_encode", |b| {
+        b.iter(|| Key::bytes_encode(black_box(&42)).unwrap().len())
+    });
+    group.bench_function("u64/encode_into", |b| {
+        let mut buffer = Vec::new();
+        b.iter(|| {
+            buffer.clear();
+            Key::encode_into(black_box(&42), &mut buffer).unwrap();
+            buffer.len()
+        })
+    });
+    group.bench_function("bincode/bytes_encode", |b| {
+        b.iter(|| Value::bytes_encode(black_box(&value)).unwrap().len())
+    });
+    group.bench_function("bincode/encode_into", |b| {
+        let mut buffer = Vec::new();
+        b.iter(|| {
+            buffer.clear();
+            Value::encode_into(black_box(&value), &mut buffer).unwrap();
+            buffer.len()
+        })
+    });
+
+    group.finish();
+}
+
+fn put(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let env = EnvOpenOptions::new().map_size(100 * 1024 * 1024).open(dir.path()).unwrap(); // 100MB
+    let mut wtxn = env.write_txn().unwrap();
+    let db: Database<Key, Value> = env.create_database(&mut wtxn, None).unwrap();
+    wtxn.commit().unwrap();
+
+    let value = (42, String::from("hello world"));
+    let mut group = c.benchmark_group("put");
+
+    group.bench_function("allocating", |b| {
+        b.iter_batched(
+            || env.write_txn().unwrap(),
+            |mut wtxn| {
+                let raw = db.remap_types::<ByteSlice, ByteSlice>();
+                for i in 0..1000 {
+                    let key = Key::bytes_encode(&i).unwrap();
+                    let data = Value::bytes_encode(&value).unwrap();
+                    raw.put(&mut wtxn, &key, &data).unwrap();
+                }
+                wtxn
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("scratch buffer", |b| {
+        b.iter_batched(
+            || env.write_txn().unwrap(),
+            |mut wtxn| {
+                for i in 0..1000 {
+                    db.put(&mut wtxn, &i, &value).unwrap();
+                }
+                wtxn
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, encode, put);
+criterion_main!(benches);
@@ -22,20 +22,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     //
     // like here we specify that the key will be an array of two i32
     // and the data will be an str
-    let wtxn = env.write_txn()?;
-    let db: Database<OwnedType<[i32; 2]>, Str> = env.create_database(&wtxn, Some("kikou"))?;
+    let mut wtxn = env.write_txn()?;
+    let db: Database<OwnedType<[i32; 2]>, Str> = env.create_database(&mut wtxn, Some("kikou"))?;
 
-    db.put(&wtxn, &[2, 3], "what's up?")?;
+    db.put(&mut wtxn, &[2, 3], "what's up?")?;
     let ret: Option<&str> = db.get(&wtxn, &[2, 3])?;
 
     println!("{:?}", ret);
     wtxn.commit()?;
 
     // here the key will be an str and the data will be a slice of u8
-    let wtxn = env.write_txn()?;
-    let db: Database<Str, ByteSlice> = env.create_database(&wtxn, Some("kiki"))?;
+    let mut wtxn = env.write_txn()?;
+    let db: Database<Str, ByteSlice> = env.create_database(&mut wtxn, Some("kiki"))?;
 
-    db.put(&wtxn, "hello", &[2, 3][..])?;
+    db.put(&mut wtxn, "hello", &[2, 3][..])?;
     let ret: Option<&[u8]> = db.get(&wtxn, "hello")?;
 
     println!("{:?}", ret);
@@ -47,23 +47,23 @@ fn main() -> Result<(), Box<dyn Error>> {
         string: &'a str,
     }
 
-    let wtxn = env.write_txn()?;
+    let mut wtxn = env.write_txn()?;
     let db: Database<Str, SerdeBincode<Hello>> =
-        env.create_database(&wtxn, Some("serde-bincode"))?;
+        env.create_database(&mut wtxn, Some("serde-bincode"))?;
 
     let hello = Hello { string: "hi" };
-    db.put(&wtxn, "hello", &hello)?;
+    db.put(&mut wtxn, "hello", &hello)?;
 
     let ret: Option<Hello> = db.get(&wtxn, "hello")?;
     println!("serde-bincode:\t{:?}", ret);
 
     wtxn.commit()?;
 
-    let wtxn = env.write_txn()?;
-    let db: Database<Str, SerdeJson<Hello>> = env.create_database(&wtxn, Some("serde-json"))?;
+    let mut wtxn = env.write_txn()?;
+    let db: Database<Str, SerdeJson<Hello>> = env.create_database(&mut wtxn, Some("serde-json"))?;
 
     let hello = Hello { string: "hi" };
-    db.put(&wtxn, "hello", &hello)?;
+    db.put(&mut wtxn, "hello", &hello)?;
 
     let ret: Option<Hello> = db.get(&wtxn, "hello")?;
     println!("serde-json:\t{:?}", ret);
@@ -77,12 +77,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         bytes: [u8; 12],
     }
 
-    let wtxn = env.write_txn()?;
+    let mut wtxn = env.write_txn()?;
     let db: Database<Str, UnalignedType<ZeroBytes>> =
-        env.create_database(&wtxn, Some("simple-struct"))?;
+        env.create_database(&mut wtxn, Some("simple-struct"))?;
 
     let zerobytes = ZeroBytes { bytes: [24; 12] };
-    db.put(&wtxn, "zero", &zerobytes)?;
+    db.put(&mut wtxn, "zero", &zerobytes)?;
 
     let ret = db.get(&wtxn, "zero")?;
 
@@ -90,10 +90,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     wtxn.commit()?;
 
     // you can ignore the data
-    let wtxn = env.write_txn()?;
-    let db: Database<Str, Unit> = env.create_database(&wtxn, Some("ignored-data"))?;
+    let mut wtxn = env.write_txn()?;
+    let db: Database<Str, Unit> = env.create_database(&mut wtxn, Some("ignored-data"))?;
 
-    db.put(&wtxn, "hello", &())?;
+    db.put(&mut wtxn, "hello", &())?;
     let ret: Option<()> = db.get(&wtxn, "hello")?;
 
     println!("{:?}", ret);
@@ -107,7 +107,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     //
     // we try to open a database twice with the same types
     let mut wtxn = env.write_txn()?;
-    let _db: Database<Str, Unit> = env.create_database(&wtxn, Some("ignored-data"))?;
+    let _db: Database<Str, Unit> = env.create_database(&mut wtxn, Some("ignored-data"))?;
 
     // and here we try to open it with other types
     // asserting that it correctly returns an error
@@ -115,18 +115,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     // NOTE that those types are not saved upon runs and
     // therefore types cannot be checked upon different runs,
     // the first database opening fix the types for this run.
-    let result = env.create_database::<Str, OwnedSlice<i32>>(&wtxn, Some("ignored-data"));
+    let result = env.create_database::<Str, OwnedSlice<i32>>(&mut wtxn, Some("ignored-data"));
     assert!(result.is_err());
 
     // you can iterate over keys in order
     type BEI64 = I64<BE>;
 
-    let db: Database<BEI64, Unit> = env.create_database(&wtxn, Some("big-endian-iter"))?;
+    let db: Database<BEI64, Unit> = env.create_database(&mut wtxn, Some("big-endian-iter"))?;
 
-    db.put(&wtxn, &0, &())?;
-    db.put(&wtxn, &68, &())?;
-    db.put(&wtxn, &35, &())?;
-    db.put(&wtxn, &42, &())?;
+    db.put(&mut wtxn, &0, &())?;
+    db.put(&mut wtxn, &68, &())?;
+    db.put(&mut wtxn, &35, &())?;
+    db.put(&mut wtxn, &42, &())?;
 
     let rets: Result<Vec<(i64, _)>, _> = db.iter(&wtxn)?.collect();
 
@@ -18,12 +18,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         .max_dbs(3)
         .open(env_path)?;
 
-    let wtxn = env.write_txn()?;
-    let db: Database<Str, Str> = env.create_database(&wtxn, Some("first"))?;
+    let mut wtxn = env.write_txn()?;
+    let db: Database<Str, Str> = env.create_database(&mut wtxn, Some("first"))?;
 
     // We fill the db database with entries.
-    db.put(&wtxn, "I am here", "to test things")?;
-    db.put(&wtxn, "I am here too", "for the same purpose")?;
+    db.put(&mut wtxn, "I am here", "to test things")?;
+    db.put(&mut wtxn, "I am here too", "for the same purpose")?;
 
     wtxn.commit()?;
 
@@ -0,0 +1,423 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{self, Component, Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fmt, panic};
+
+use crate::{CompactionOption, Env, Result};
+
+const MANIFEST_NAME: &str = "manifest";
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+const SNAPSHOT_EXTENSION: &str = ".mdb";
+
+/// Writes timestamped and compacted snapshots of an environment into a directory.
+///
+/// Every snapshot is recorded in a `manifest` file along with the id of the last
+/// committed transaction and the size of the memory map at the time it was taken.
+/// Only the newest snapshots are kept, the older ones are removed from the directory.
+///
+/// ```
+/// use std::time::Duration;
+/// use heed::{BackupScheduler, EnvOpenOptions};
+/// use heed::types::*;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = tempfile::tempdir()?;
+/// let env = EnvOpenOptions::new().open(dir.path())?;
+///
+/// let mut wtxn = env.write_txn()?;
+/// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+/// db.put(&mut wtxn, "hello", "world")?;
+/// wtxn.commit()?;
+///
+/// let backups = tempfile::tempdir()?;
+/// let mut scheduler = BackupScheduler::new(env.clone(), backups.path());
+/// scheduler.interval(Duration::from_secs(60 * 60)).keep(24);
+///
+/// // Takes a snapshot right away, then every hour.
+/// let handle = scheduler.spawn();
+/// handle.stop()?;
+///
+/// let restored = tempfile::tempdir()?;
+/// let snapshot = BackupScheduler::restore_latest(backups.path(), restored.path())?;
+/// assert!(snapshot.is_some());
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BackupScheduler {
+    env: Env,
+    dir: PathBuf,
+    interval: Duration,
+    keep: usize,
+}
+
+impl BackupScheduler {
+    /// Creates a scheduler that writes the snapshots of the environment into `dir`.
+    ///
+    /// By default a snapshot is taken every hour and the seven newest are kept.
+    pub fn new<P: AsRef<Path>>(env: Env, dir: P) -> BackupScheduler {
+        BackupScheduler {
+            env,
+            dir: dir.as_ref().to_path_buf(),
+            interval: Duration::from_secs(60 * 60),
+            keep: 7,
+        }
+    }
+
+    /// Set the duration to wait between two snapshots.
+    pub fn interval(&mut self, interval: Duration) -> &mut Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the number of snapshots to keep, the oldest ones are removed first.
+    ///
+    /// The newest snapshot is always kept, even if this number is zero.
+    pub fn keep(&mut self, keep: usize) -> &mut Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Takes a compacted snapshot right away, removes the snapshots
+    /// that are too old and updates the manifest accordingly.
+    pub fn backup_now(&self) -> Result<Snapshot> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut timestamp =
+            SystemTime::now().duration_since(UNIX_EPOCH).map_err(io::Error::other)?.as_millis()
+                as u64;
+
+        // Two snapshots can be taken in the same millisecond,
+        // we bump the timestamp to find a free file name.
+        let mut path = self.dir.join(snapshot_file_name(timestamp));
+        while path.exists() {
+            timestamp += 1;
+            path = self.dir.join(snapshot_file_name(timestamp));
+        }
+
+        let info = self.env.info();
+        let file = self.env.copy_to_file(&path, CompactionOption::Enabled)?;
+        let size = file.metadata()?.len();
+
+        let snapshot =
+            Snapshot { path, timestamp, txn_id: info.last_txn_id, map_size: info.map_size, size };
+
+        let mut snapshots = read_manifest(&self.dir)?;
+        snapshots.push(snapshot.clone());
+        snapshots.sort_by_key(|s| s.timestamp);
+
+        let removed = snapshots.len().saturating_sub(self.keep.max(1));
+        let kept = snapshots.split_off(removed);
+        write_manifest(&self.dir, &kept)?;
+
+        for old in snapshots {
+            match fs::remove_file(&old.path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => (),
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Spawns a background thread that takes a snapshot right away and then
+    /// another one every time the interval elapses.
+    ///
+    /// The thread stops on the first error, which is returned by [`BackupHandle::stop`].
+    /// Note that the thread keeps a copy of the [`Env`] until it is stopped.
+    pub fn spawn(self) -> BackupHandle {
+        let (sender, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || loop {
+            self.backup_now()?;
+            loop {
+                match receiver.recv_timeout(self.interval) {
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Ok(Command::Backup(reply)) => {
+                        let _ = reply.send(self.backup_now());
+                    }
+                    Ok(Command::Stop) | Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            }
+        });
+
+        BackupHandle { sender, thread }
+    }
+
+    /// Returns the snapshots recorded in the manifest of the directory, the oldest first.
+    pub fn snapshots<P: AsRef<Path>>(dir: P) -> Result<Vec<Snapshot>> {
+        let mut snapshots = read_manifest(dir.as_ref())?;
+        snapshots.sort_by_key(|s| s.timestamp);
+        Ok(snapshots)
+    }
+
+    /// Restores the newest snapshot of the `dir` directory into the `target` directory.
+    ///
+    /// The snapshot is copied as the `data.mdb` file of the target directory, which must
+    /// not already exist, the environment can then be opened at this path.
+    /// Returns `None` if there is no snapshot in the directory.
+    pub fn restore_latest<P: AsRef<Path>, Q: AsRef<Path>>(
+        dir: P,
+        target: Q,
+    ) -> Result<Option<Snapshot>> {
+        let snapshot = match Self::snapshots(dir)?.pop() {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+
+        let target = target.as_ref();
+        fs::create_dir_all(target)?;
+        let mut source = File::open(&snapshot.path)?;
+        let mut file =
+            File::options().create_new(true).write(true).open(target.join("data.mdb"))?;
+        io::copy(&mut source, &mut file)?;
+        file.sync_all()?;
+
+        Ok(Some(snapshot))
+    }
+}
+
+/// A snapshot recorded in the manifest of a backup directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The path of the snapshot file.
+    pub path: PathBuf,
+    /// The number of milliseconds since the unix epoch at which the snapshot was taken.
+    pub timestamp: u64,
+    /// The ID of the last committed transaction when the snapshot was started.
+    pub txn_id: usize,
+    /// The size of the memory map of the environment when the snapshot was started.
+    pub map_size: usize,
+    /// The size of the snapshot file in bytes.
+    pub size: u64,
+}
+
+/// A handle to the background thread spawned by [`BackupScheduler::spawn`].
+pub struct BackupHandle {
+    sender: Sender<Command>,
+    thread: JoinHandle<Result<()>>,
+}
+
+/// A request sent to the background thread of a [`BackupHandle`].
+enum Command {
+    /// Takes a snapshot right away and sends back its result.
+    Backup(Sender<Result<Snapshot>>),
+    Stop,
+}
+
+impl BackupHandle {
+    /// Makes the background thread take a snapshot right away and waits for it,
+    /// the next scheduled snapshot is taken once the interval elapsed again.
+    ///
+    /// The error of this snapshot is returned here and doesn't stop the thread.
+    pub fn backup_now(&self) -> Result<Snapshot> {
+        let stopped = || io::Error::other("the backup thread has stopped");
+        let (reply, receiver) = mpsc::channel();
+        self.sender.send(Command::Backup(reply)).map_err(|_| stopped())?;
+        receiver.recv().map_err(|_| stopped())?
+    }
+
+    /// Stops the background thread, waiting for the current snapshot to be written,
+    /// and returns the error that stopped it earlier, if any.
+    pub fn stop(self) -> Result<()> {
+        // The thread may already have stopped because of an error.
+        let _ = self.sender.send(Command::Stop);
+        match self.thread.join() {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl fmt::Debug for BackupHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BackupHandle").finish()
+    }
+}
+
+fn snapshot_file_name(timestamp: u64) -> String {
+    format!("{}{:020}{}", SNAPSHOT_PREFIX, timestamp, SNAPSHOT_EXTENSION)
+}
+
+/// Reads the manifest, one snapshot per line: `file-name timestamp txn-id map-size size`.
+fn read_manifest(dir: &Path) -> Result<Vec<Snapshot>> {
+    let file = match File::open(dir.join(MANIFEST_NAME)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let invalid = |line: &str| {
+        let message = format!("invalid backup manifest line: {:?}", line);
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    };
+
+    let mut snapshots = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        let snapshot = (|| {
+            let name = fields.next().filter(|name| is_file_name(name))?;
+            let snapshot = Snapshot {
+                path: dir.join(name),
+                timestamp: fields.next()?.parse().ok()?,
+                txn_id: fields.next()?.parse().ok()?,
+                map_size: fields.next()?.parse().ok()?,
+                size: fields.next()?.parse().ok()?,
+            };
+            fields.next().is_none().then_some(snapshot)
+        })();
+
+        match snapshot {
+            Some(snapshot) => snapshots.push(snapshot),
+            None if line.trim().is_empty() => (),
+            None => return Err(invalid(&line).into()),
+        }
+    }
+
+    Ok(snapshots)
+}
+
+/// Returns `true` if the name is a single file name, without any path separator,
+/// so that a manifest can't reference files outside of its directory.
+fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(path::is_separator)
+        && matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+/// Writes the manifest into a temporary file and renames it so that it is replaced atomically.
+fn write_manifest(dir: &Path, snapshots: &[Snapshot]) -> Result<()> {
+    let tmp_path = dir.join(format!("{}.tmp", MANIFEST_NAME));
+    let mut file = File::create(&tmp_path)?;
+    for snapshot in snapshots {
+        let name = snapshot.path.file_name().and_then(|name| name.to_str());
+        let name = name.ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        writeln!(
+            file,
+            "{} {} {} {} {}",
+            name, snapshot.timestamp, snapshot.txn_id, snapshot.map_size, snapshot.size
+        )?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp_path, dir.join(MANIFEST_NAME))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::byteorder::BigEndian;
+    use crate::types::*;
+    use crate::EnvOpenOptions;
+
+    #[test]
+    fn rotate_and_restore_latest() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, U32<BigEndian>>(&mut wtxn, None).unwrap();
+        wtxn.commit().unwrap();
+
+        let backups = tempfile::tempdir().unwrap();
+        let mut scheduler = BackupScheduler::new(env.clone(), backups.path());
+        scheduler.keep(2);
+
+        let mut taken = Vec::new();
+        for i in 0..4 {
+            let mut wtxn = env.write_txn().unwrap();
+            db.put(&mut wtxn, &format!("entry-{i}"), &i).unwrap();
+            wtxn.commit().unwrap();
+            taken.push(scheduler.backup_now().unwrap());
+        }
+
+        let snapshots = BackupScheduler::snapshots(backups.path()).unwrap();
+        assert_eq!(snapshots, taken[2..]);
+        assert!(snapshots[0].txn_id < snapshots[1].txn_id);
+        assert!(snapshots.iter().all(|s| s.path.exists() && s.size > 0));
+        assert!(taken[..2].iter().all(|s| !s.path.exists()));
+
+        let target = tempfile::tempdir().unwrap();
+        let restored = BackupScheduler::restore_latest(backups.path(), target.path()).unwrap();
+        assert_eq!(restored.as_ref(), taken.last());
+
+        let restored_env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .open(target.path())
+            .unwrap();
+        let rtxn = restored_env.read_txn().unwrap();
+        let restored_db =
+            restored_env.open_database::<Str, U32<BigEndian>>(&rtxn, None).unwrap().unwrap();
+        assert_eq!(restored_db.len(&rtxn).unwrap(), 4);
+        assert_eq!(restored_db.get(&rtxn, "entry-3").unwrap(), Some(3));
+
+        // The target already contains a data file.
+        let result = BackupScheduler::restore_latest(backups.path(), target.path());
+        assert!(matches!(result, Err(crate::Error::Io(_))));
+    }
+
+    #[test]
+    fn restore_latest_without_snapshot() {
+        let backups = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let restored = BackupScheduler::restore_latest(backups.path(), target.path()).unwrap();
+        assert_eq!(restored, None);
+    }
+
+    #[test]
+    fn manifest_with_a_path() {
+        let backups = tempfile::tempdir().unwrap();
+        for name in ["../data.mdb", "sub/snapshot.mdb", "/etc/passwd", ".."] {
+            fs::write(backups.path().join(MANIFEST_NAME), format!("{name} 1 2 3 4\n")).unwrap();
+            let result = BackupScheduler::snapshots(backups.path());
+            assert!(
+                matches!(result, Err(crate::Error::Io(e)) if e.kind() == io::ErrorKind::InvalidData)
+            );
+        }
+    }
+
+    #[test]
+    fn spawn_and_stop() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .open(dir.path())
+            .unwrap();
+
+        let backups = tempfile::tempdir().unwrap();
+        let mut scheduler = BackupScheduler::new(env, backups.path());
+        scheduler.interval(Duration::from_secs(60 * 60)).keep(2);
+
+        // The thread takes a first snapshot when it starts, the requested
+        // ones are taken after it and rotate it out of the manifest.
+        let handle = scheduler.spawn();
+        let taken: Vec<_> = (0..3).map(|_| handle.backup_now().unwrap()).collect();
+        handle.stop().unwrap();
+
+        let snapshots = BackupScheduler::snapshots(backups.path()).unwrap();
+        assert_eq!(snapshots, taken[1..]);
+        assert!(!taken[0].path.exists());
+
+        let files = fs::read_dir(backups.path()).unwrap().count();
+        assert_eq!(files, 3); // two snapshots and the manifest
+    }
+
+    #[test]
+    fn backup_now_after_stopping_on_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().open(dir.path()).unwrap();
+
+        // The backup directory can't be created under a file.
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let scheduler = BackupScheduler::new(env, file.path().join("backups"));
+        let handle = scheduler.spawn();
+
+        assert!(matches!(handle.backup_now(), Err(crate::Error::Io(_))));
+        assert!(matches!(handle.stop(), Err(crate::Error::Io(_))));
+    }
+}
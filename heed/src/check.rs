@@ -0,0 +1,442 @@
+use std::cmp::Ordering;
+use std::{fmt, mem};
+
+use crate::cursor::MoveOperation;
+use crate::env::take_comparator_panic;
+use crate::mdb::error::mdb_result;
+use crate::mdb::ffi;
+use crate::{DatabaseFlags, MdbError, RoCursor, RoTxn};
+
+/// The outcome of an [`Env::check`](crate::Env::check) integrity verification.
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    /// The report of every database that was walked, the unnamed one first.
+    pub databases: Vec<DatabaseReport>,
+}
+
+impl CheckReport {
+    /// Returns `true` if no anomaly was found in any of the databases.
+    pub fn is_ok(&self) -> bool {
+        self.databases.iter().all(|db| db.anomalies.is_empty())
+    }
+
+    /// Returns an iterator over every anomaly found along with the name of
+    /// the database it was found in, `None` being the unnamed database.
+    pub fn anomalies(&self) -> impl Iterator<Item = (Option<&str>, &Anomaly)> {
+        self.databases
+            .iter()
+            .flat_map(|db| db.anomalies.iter().map(move |a| (db.name.as_deref(), a)))
+    }
+}
+
+/// The integrity report of a single database.
+#[derive(Debug, Clone)]
+pub struct DatabaseReport {
+    /// The name of the database, `None` for the unnamed database.
+    pub name: Option<String>,
+    /// The flags the database was created with.
+    pub flags: DatabaseFlags,
+    /// The number of entries, duplicates included, reached by walking the database.
+    pub entries: u64,
+    /// The number of entries LMDB reports for this database.
+    pub stat_entries: u64,
+    /// The anomalies found while walking the database.
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// An inconsistency found while checking a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anomaly {
+    /// A key is not strictly greater than the one preceding it.
+    KeyOrder {
+        /// The key that was visited before.
+        previous: Vec<u8>,
+        /// The key that is out of order.
+        key: Vec<u8>,
+    },
+    /// A duplicate data item is not strictly greater than the one preceding it.
+    DuplicateOrder {
+        /// The key the duplicate data items belong to.
+        key: Vec<u8>,
+        /// The data item that was visited before.
+        previous: Vec<u8>,
+        /// The data item that is out of order.
+        data: Vec<u8>,
+    },
+    /// The same key appears more than once in a database without `DUP_SORT`.
+    UnexpectedDuplicate {
+        /// The key that appears more than once.
+        key: Vec<u8>,
+    },
+    /// A key or a data item of an `INTEGER_KEY`/`INTEGER_DUP` database
+    /// doesn't have the size of an `u32` or an `usize` or differs from the previous one.
+    InvalidIntegerSize {
+        /// The bytes that have an invalid size.
+        bytes: Vec<u8>,
+    },
+    /// The number of entries walked doesn't match the one reported by LMDB.
+    EntriesMismatch {
+        /// The number of entries walked.
+        counted: u64,
+        /// The number of entries reported by LMDB.
+        stat: u64,
+    },
+    /// LMDB returned an error while opening or walking the database.
+    Mdb(MdbError),
+    /// The custom comparator of the database panicked, the walk of the database stopped.
+    ComparatorPanicked(String),
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Anomaly::KeyOrder { previous, key } => {
+                write!(f, "key {:?} is not greater than the previous key {:?}", key, previous)
+            }
+            Anomaly::DuplicateOrder { key, previous, data } => write!(
+                f,
+                "data {:?} of key {:?} is not greater than the previous data {:?}",
+                data, key, previous
+            ),
+            Anomaly::UnexpectedDuplicate { key } => {
+                write!(f, "key {:?} appears more than once in a non DUP_SORT database", key)
+            }
+            Anomaly::InvalidIntegerSize { bytes } => {
+                write!(f, "integer {:?} has an invalid size of {} bytes", bytes, bytes.len())
+            }
+            Anomaly::EntriesMismatch { counted, stat } => {
+                write!(f, "walked {} entries but LMDB reports {}", counted, stat)
+            }
+            Anomaly::Mdb(error) => write!(f, "{}", error),
+            Anomaly::ComparatorPanicked(message) => {
+                write!(f, "the custom comparator panicked: {}", message)
+            }
+        }
+    }
+}
+
+/// Walks the whole database, in the order LMDB returns the entries, and verifies that
+/// every key (and duplicate data item) is ordered per the database comparator.
+///
+/// The comparisons are made with `mdb_cmp`/`mdb_dcmp`, the comparators used are
+/// the ones LMDB derives from the database flags or the custom ones registered for this dbi.
+/// A panic of a custom comparator is reported as an anomaly and doesn't poison the transaction.
+pub(crate) fn check_database(
+    rtxn: &RoTxn,
+    name: Option<String>,
+    dbi: ffi::MDB_dbi,
+) -> DatabaseReport {
+    let mut report = DatabaseReport {
+        name,
+        flags: DatabaseFlags::empty(),
+        entries: 0,
+        stat_entries: 0,
+        anomalies: Vec::new(),
+    };
+
+    let mut flags = 0;
+    if let Err(e) = unsafe { mdb_result(ffi::mdb_dbi_flags(rtxn.txn, dbi, &mut flags)) } {
+        report.anomalies.push(Anomaly::Mdb(e));
+        return report;
+    }
+    report.flags = DatabaseFlags::from_bits_truncate(flags);
+
+    let mut stat = mem::MaybeUninit::uninit();
+    match unsafe { mdb_result(ffi::mdb_stat(rtxn.txn, dbi, stat.as_mut_ptr())) } {
+        Ok(()) => report.stat_entries = unsafe { stat.assume_init() }.ms_entries as u64,
+        Err(e) => {
+            report.anomalies.push(Anomaly::Mdb(e));
+            return report;
+        }
+    }
+
+    let integer_key = report.flags.contains(DatabaseFlags::INTEGER_KEY);
+    let dup_sort = report.flags.contains(DatabaseFlags::DUP_SORT);
+    let integer_dup = dup_sort && report.flags.contains(DatabaseFlags::INTEGER_DUP);
+
+    let mut cursor = match RoCursor::new(rtxn, dbi) {
+        Ok(cursor) => cursor,
+        Err(e) => {
+            report.anomalies.extend(mdb_anomaly(e));
+            return report;
+        }
+    };
+
+    let mut previous: Option<(&[u8], &[u8])> = None;
+    let mut result = cursor.move_on_first(MoveOperation::Any);
+    loop {
+        let (key, data) = match result {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(e) => {
+                report.anomalies.extend(mdb_anomaly(e));
+                break;
+            }
+        };
+
+        report.entries += 1;
+
+        let valid_key = !integer_key || valid_integer(key, previous.map(|(k, _)| k));
+        if !valid_key {
+            report.anomalies.push(Anomaly::InvalidIntegerSize { bytes: key.to_vec() });
+        }
+
+        let valid_data = !integer_dup || valid_integer(data, previous.map(|(_, d)| d));
+        if !valid_data {
+            report.anomalies.push(Anomaly::InvalidIntegerSize { bytes: data.to_vec() });
+        }
+
+        if let Some((prev_key, prev_data)) = previous.filter(|_| valid_key) {
+            let ordering = match compare(ffi::mdb_cmp, rtxn, dbi, prev_key, key) {
+                Ok(ordering) => ordering,
+                Err(message) => {
+                    report.anomalies.push(Anomaly::ComparatorPanicked(message));
+                    return report;
+                }
+            };
+            match ordering {
+                Ordering::Less => (),
+                Ordering::Equal if dup_sort => {
+                    let ordered = !valid_data
+                        || match compare(ffi::mdb_dcmp, rtxn, dbi, prev_data, data) {
+                            Ok(ordering) => ordering == Ordering::Less,
+                            Err(message) => {
+                                report.anomalies.push(Anomaly::ComparatorPanicked(message));
+                                return report;
+                            }
+                        };
+                    if !ordered {
+                        report.anomalies.push(Anomaly::DuplicateOrder {
+                            key: key.to_vec(),
+                            previous: prev_data.to_vec(),
+                            data: data.to_vec(),
+                        });
+                    }
+                }
+                Ordering::Equal => {
+                    report.anomalies.push(Anomaly::UnexpectedDuplicate { key: key.to_vec() })
+                }
+                Ordering::Greater => report
+                    .anomalies
+                    .push(Anomaly::KeyOrder { previous: prev_key.to_vec(), key: key.to_vec() }),
+            }
+        }
+
+        previous = Some((key, data));
+        result = cursor.move_on_next(MoveOperation::Any);
+    }
+
+    if report.entries != report.stat_entries {
+        report
+            .anomalies
+            .push(Anomaly::EntriesMismatch { counted: report.entries, stat: report.stat_entries });
+    }
+
+    report
+}
+
+/// Converts a walking error into an anomaly, keeping the I/O errors as LMDB ones.
+fn mdb_anomaly(error: crate::Error) -> Option<Anomaly> {
+    match error {
+        crate::Error::Mdb(e) => Some(Anomaly::Mdb(e)),
+        crate::Error::ComparatorPanicked(message) => Some(Anomaly::ComparatorPanicked(message)),
+        crate::Error::Io(e) => e.raw_os_error().map(|code| Anomaly::Mdb(MdbError::Other(code))),
+        _ => None,
+    }
+}
+
+/// LMDB integers must be `u32` or `usize` and all of the same size.
+fn valid_integer(bytes: &[u8], previous: Option<&[u8]>) -> bool {
+    let valid_size = bytes.len() == mem::size_of::<u32>() || bytes.len() == mem::size_of::<usize>();
+    valid_size && previous.is_none_or(|p| p.len() == bytes.len())
+}
+
+fn compare(
+    cmp: unsafe extern "C" fn(
+        *mut ffi::MDB_txn,
+        ffi::MDB_dbi,
+        *const ffi::MDB_val,
+        *const ffi::MDB_val,
+    ) -> i32,
+    rtxn: &RoTxn,
+    dbi: ffi::MDB_dbi,
+    a: &[u8],
+    b: &[u8],
+) -> Result<Ordering, String> {
+    let a = unsafe { ffi::into_val(a) };
+    let b = unsafe { ffi::into_val(b) };
+    let ordering = unsafe { cmp(rtxn.txn, dbi, &a, &b) }.cmp(&0);
+    match take_comparator_panic() {
+        Some(message) => Err(message),
+        None => Ok(ordering),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use heed_traits::Comparator;
+
+    use super::*;
+    use crate::byteorder::{BigEndian, NativeEndian};
+    use crate::types::*;
+    use crate::{EnvOpenOptions, PutFlags};
+
+    #[test]
+    fn check_valid_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .max_dbs(10)
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let strings = env.create_database::<Str, Str>(&mut wtxn, Some("strings")).unwrap();
+        let integers = env
+            .database_options()
+            .types::<U32<NativeEndian>, Unit>()
+            .flags(DatabaseFlags::INTEGER_KEY)
+            .name("integers")
+            .create(&mut wtxn)
+            .unwrap();
+        let reversed = env
+            .database_options()
+            .types::<Str, Unit>()
+            .flags(DatabaseFlags::REVERSE_KEY)
+            .name("reversed")
+            .create(&mut wtxn)
+            .unwrap();
+        let duplicates = env
+            .database_options()
+            .types::<U32<BigEndian>, U32<BigEndian>>()
+            .flags(DatabaseFlags::DUP_SORT)
+            .name("duplicates")
+            .create(&mut wtxn)
+            .unwrap();
+
+        for (i, s) in ["hello", "world", "bonjour", "holla"].iter().enumerate() {
+            strings.put(&mut wtxn, s, s).unwrap();
+            integers.put(&mut wtxn, &(i as u32 * 300), &()).unwrap();
+            reversed.put(&mut wtxn, s, &()).unwrap();
+            for j in 0..3 {
+                duplicates.put_with_flags(&mut wtxn, PutFlags::empty(), &(i as u32), &j).unwrap();
+            }
+        }
+        wtxn.commit().unwrap();
+
+        let report = env.check().unwrap();
+        assert!(report.is_ok(), "{:?}", report.anomalies().collect::<Vec<_>>());
+
+        let names: Vec<_> = report.databases.iter().map(|db| db.name.as_deref()).collect();
+        assert_eq!(
+            names,
+            [None, Some("duplicates"), Some("integers"), Some("reversed"), Some("strings")]
+        );
+
+        let duplicates = &report.databases[1];
+        assert!(duplicates.flags.contains(DatabaseFlags::DUP_SORT));
+        assert_eq!(duplicates.entries, 12);
+        assert_eq!(duplicates.stat_entries, 12);
+    }
+
+    #[test]
+    fn check_detects_unknown_comparator() {
+        enum ReverseCmp {}
+
+        impl Comparator for ReverseCmp {
+            fn compare(a: &[u8], b: &[u8]) -> Ordering {
+                b.cmp(a)
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = EnvOpenOptions::new().map_size(10 * 1024 * 1024).max_dbs(10).clone();
+
+        let env = options.open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Unit>()
+            .key_comparator::<ReverseCmp>()
+            .name("reversed")
+            .create(&mut wtxn)
+            .unwrap();
+        db.put(&mut wtxn, "a", &()).unwrap();
+        db.put(&mut wtxn, "b", &()).unwrap();
+        db.put(&mut wtxn, "c", &()).unwrap();
+        wtxn.commit().unwrap();
+
+        // The custom comparator is known by this env, the database looks fine.
+        assert!(env.check().unwrap().is_ok());
+        env.prepare_for_closing().wait();
+
+        // After a restart, LMDB only knows the default comparator.
+        let env = options.open(dir.path()).unwrap();
+        let report = env.check().unwrap();
+        let anomalies: Vec<_> = report.anomalies().collect();
+        assert_eq!(
+            anomalies,
+            [
+                (
+                    Some("reversed"),
+                    &Anomaly::KeyOrder { previous: b"c".to_vec(), key: b"b".to_vec() }
+                ),
+                (
+                    Some("reversed"),
+                    &Anomaly::KeyOrder { previous: b"b".to_vec(), key: b"a".to_vec() }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_reports_comparator_panics() {
+        use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+        static PANIC: AtomicBool = AtomicBool::new(false);
+
+        enum PanickingCmp {}
+
+        impl Comparator for PanickingCmp {
+            fn compare(a: &[u8], b: &[u8]) -> Ordering {
+                if PANIC.load(Relaxed) {
+                    panic!("cannot compare");
+                }
+                a.cmp(b)
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Unit>()
+            .key_comparator::<PanickingCmp>()
+            .name("panicking")
+            .create(&mut wtxn)
+            .unwrap();
+        db.put(&mut wtxn, "a", &()).unwrap();
+        db.put(&mut wtxn, "b", &()).unwrap();
+        wtxn.commit().unwrap();
+
+        PANIC.store(true, Relaxed);
+        let report = env.check().unwrap();
+        PANIC.store(false, Relaxed);
+        let anomalies: Vec<_> = report.anomalies().collect();
+        assert_eq!(
+            anomalies,
+            [(Some("panicking"), &Anomaly::ComparatorPanicked(String::from("cannot compare")))]
+        );
+
+        // The panic caught by the check doesn't poison the next transactions.
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(db.get(&rtxn, "a").unwrap(), Some(()));
+        drop(rtxn);
+        let mut wtxn = env.write_txn().unwrap();
+        db.put(&mut wtxn, "c", &()).unwrap();
+        wtxn.commit().unwrap();
+    }
+}
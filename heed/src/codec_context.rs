@@ -0,0 +1,380 @@
+use std::marker;
+use std::ops::{Bound, RangeBounds};
+
+use heed_traits::{BytesDecodeWith, BytesEncodeWith, Comparator, LexicographicComparator};
+use types::ByteSlice;
+
+use crate::env::DefaultComparator;
+use crate::*;
+
+/// A view of a [`Database`] that gives a context to every encoding and decoding.
+///
+/// The codecs must implement [`BytesEncodeWith`] and [`BytesDecodeWith`] for the
+/// context type, a [`BytesEncode`] and [`BytesDecode`] codec can be wrapped in the
+/// [`IgnoreContext`] adapter to ignore it.
+/// It can be used to give the codecs access to runtime state like encryption keys,
+/// compression dictionaries or string interning tables.
+///
+/// This view is created with [`Database::with_codec_context`].
+///
+/// ```
+/// # use heed::EnvOpenOptions;
+/// use std::borrow::Cow;
+///
+/// use heed::{BoxedError, BytesDecodeWith, BytesEncodeWith, Database, IgnoreContext};
+/// use heed::types::*;
+///
+/// /// Stores the strings xored with the key given as context.
+/// enum Xored {}
+///
+/// impl<'a> BytesEncodeWith<'a, u8> for Xored {
+///     type EItem = str;
+///
+///     fn bytes_encode_with(item: &'a str, key: &u8) -> Result<Cow<'a, [u8]>, BoxedError> {
+///         Ok(item.bytes().map(|b| b ^ key).collect())
+///     }
+/// }
+///
+/// impl<'a> BytesDecodeWith<'a, u8> for Xored {
+///     type DItem = String;
+///
+///     fn bytes_decode_with(bytes: &'a [u8], key: &u8) -> Result<String, BoxedError> {
+///         Ok(String::from_utf8(bytes.iter().map(|b| b ^ key).collect())?)
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let dir = tempfile::tempdir()?;
+/// # let env = EnvOpenOptions::new()
+/// #     .map_size(10 * 1024 * 1024) // 10MB
+/// #     .max_dbs(3000)
+/// #     .open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let db: Database<IgnoreContext<Str>, Xored> = env.create_database(&mut wtxn, Some("xored"))?;
+///
+/// let key = 42;
+/// let xored = db.with_codec_context(&key);
+/// xored.put(&mut wtxn, "hello", "world")?;
+/// assert_eq!(xored.get(&wtxn, "hello")?, Some(String::from("world")));
+///
+/// let raw = db.remap_data_type::<ByteSlice>().get(&wtxn, "hello")?;
+/// assert_ne!(raw, Some(&b"world"[..]));
+///
+/// wtxn.commit()?;
+/// # Ok(()) }
+/// ```
+pub struct ContextDatabase<'c, KC, DC, Ctx: ?Sized, C = DefaultComparator> {
+    db: Database<KC, DC, C>,
+    ctx: &'c Ctx,
+}
+
+impl<'c, KC, DC, Ctx: ?Sized, C> ContextDatabase<'c, KC, DC, Ctx, C> {
+    pub(crate) fn new(
+        db: Database<KC, DC, C>,
+        ctx: &'c Ctx,
+    ) -> ContextDatabase<'c, KC, DC, Ctx, C> {
+        ContextDatabase { db, ctx }
+    }
+
+    /// Returns the context given to the codecs.
+    pub fn context(&self) -> &'c Ctx {
+        self.ctx
+    }
+
+    /// Returns the database this view is created from.
+    pub fn database(&self) -> Database<KC, DC, C> {
+        self.db
+    }
+
+    /// Retrieves the value associated with a key.
+    ///
+    /// If the key does not exist, then `None` is returned.
+    pub fn get<'a, 'txn>(&self, txn: &'txn RoTxn, key: &'a KC::EItem) -> Result<Option<DC::DItem>>
+    where
+        KC: BytesEncodeWith<'a, Ctx>,
+        DC: BytesDecodeWith<'txn, Ctx>,
+    {
+        let key_bytes = KC::bytes_encode_with(key, self.ctx).map_err(Error::Encoding)?;
+        match self.db.remap_types::<ByteSlice, ByteSlice>().get(txn, &key_bytes)? {
+            Some(bytes) => {
+                DC::bytes_decode_with(bytes, self.ctx).map(Some).map_err(Error::Decoding)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Insert a key-value pair in this database.
+    ///
+    /// Like [`Database::put`], it returns an error if the key already exists.
+    pub fn put<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    where
+        KC: BytesEncodeWith<'a, Ctx>,
+        DC: BytesEncodeWith<'a, Ctx>,
+    {
+        let key_bytes = KC::bytes_encode_with(key, self.ctx).map_err(Error::Encoding)?;
+        let data_bytes = DC::bytes_encode_with(data, self.ctx).map_err(Error::Encoding)?;
+        self.db.remap_types::<ByteSlice, ByteSlice>().put(txn, &key_bytes, &data_bytes)
+    }
+
+    /// Deletes a key-value pair in this database.
+    ///
+    /// If the key does not exist, then `false` is returned.
+    pub fn delete<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem) -> Result<bool>
+    where
+        KC: BytesEncodeWith<'a, Ctx>,
+    {
+        let key_bytes = KC::bytes_encode_with(key, self.ctx).map_err(Error::Encoding)?;
+        self.db.remap_types::<ByteSlice, ByteSlice>().delete(txn, &key_bytes)
+    }
+
+    /// Return an ordered iterator of all key-value pairs in this database.
+    pub fn iter<'txn>(&self, txn: &'txn RoTxn) -> Result<ContextIter<'txn, 'c, KC, DC, Ctx>> {
+        let iter = self.db.remap_types::<ByteSlice, ByteSlice>().iter(txn)?;
+        Ok(ContextIter { iter, ctx: self.ctx, _phantom: marker::PhantomData })
+    }
+
+    /// Return an ordered iterator of a range of key-value pairs in this database.
+    pub fn range<'a, 'txn, R>(
+        &self,
+        txn: &'txn RoTxn,
+        range: &'a R,
+    ) -> Result<ContextRange<'txn, 'c, KC, DC, Ctx, C>>
+    where
+        KC: BytesEncodeWith<'a, Ctx>,
+        R: RangeBounds<KC::EItem>,
+    {
+        let start = self.encode_bound(range.start_bound())?;
+        let end = self.encode_bound(range.end_bound())?;
+        let range = (start.as_ref().map(Vec::as_slice), end.as_ref().map(Vec::as_slice));
+        let iter = self.db.remap_types::<ByteSlice, ByteSlice>().range(txn, &range)?;
+        Ok(ContextRange { iter, ctx: self.ctx, _phantom: marker::PhantomData })
+    }
+
+    /// Return a lexicographically ordered iterator of all key-value pairs
+    /// in this database that starts with the given prefix.
+    pub fn prefix_iter<'a, 'txn>(
+        &self,
+        txn: &'txn RoTxn,
+        prefix: &'a KC::EItem,
+    ) -> Result<ContextPrefix<'txn, 'c, KC, DC, Ctx, C>>
+    where
+        KC: BytesEncodeWith<'a, Ctx>,
+        C: LexicographicComparator,
+    {
+        let prefix_bytes = KC::bytes_encode_with(prefix, self.ctx).map_err(Error::Encoding)?;
+        let iter = self.db.remap_types::<ByteSlice, ByteSlice>().prefix_iter(txn, &prefix_bytes)?;
+        Ok(ContextPrefix { iter, ctx: self.ctx, _phantom: marker::PhantomData })
+    }
+
+    fn encode_bound<'a>(&self, bound: Bound<&'a KC::EItem>) -> Result<Bound<Vec<u8>>>
+    where
+        KC: BytesEncodeWith<'a, Ctx>,
+    {
+        let encode = |item| {
+            KC::bytes_encode_with(item, self.ctx)
+                .map(|bytes| bytes.into_owned())
+                .map_err(Error::Encoding)
+        };
+
+        match bound {
+            Bound::Included(item) => encode(item).map(Bound::Included),
+            Bound::Excluded(item) => encode(item).map(Bound::Excluded),
+            Bound::Unbounded => Ok(Bound::Unbounded),
+        }
+    }
+}
+
+impl<KC, DC, Ctx: ?Sized, C> Clone for ContextDatabase<'_, KC, DC, Ctx, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<KC, DC, Ctx: ?Sized, C> Copy for ContextDatabase<'_, KC, DC, Ctx, C> {}
+
+/// An iterator over the key-value pairs of a [`ContextDatabase`].
+pub struct ContextIter<'txn, 'c, KC, DC, Ctx: ?Sized> {
+    iter: RoIter<'txn, ByteSlice, ByteSlice>,
+    ctx: &'c Ctx,
+    _phantom: marker::PhantomData<(KC, DC)>,
+}
+
+impl<'txn, KC, DC, Ctx: ?Sized> Iterator for ContextIter<'txn, '_, KC, DC, Ctx>
+where
+    KC: BytesDecodeWith<'txn, Ctx>,
+    DC: BytesDecodeWith<'txn, Ctx>,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|result| decode_with::<KC, DC, Ctx>(result, self.ctx))
+    }
+}
+
+/// An iterator over a range of key-value pairs of a [`ContextDatabase`].
+pub struct ContextRange<'txn, 'c, KC, DC, Ctx: ?Sized, C = DefaultComparator> {
+    iter: RoRange<'txn, ByteSlice, ByteSlice, C>,
+    ctx: &'c Ctx,
+    _phantom: marker::PhantomData<(KC, DC)>,
+}
+
+impl<'txn, KC, DC, Ctx: ?Sized, C> Iterator for ContextRange<'txn, '_, KC, DC, Ctx, C>
+where
+    KC: BytesDecodeWith<'txn, Ctx>,
+    DC: BytesDecodeWith<'txn, Ctx>,
+    C: Comparator,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|result| decode_with::<KC, DC, Ctx>(result, self.ctx))
+    }
+}
+
+/// An iterator over the key-value pairs of a [`ContextDatabase`] that start with a prefix.
+pub struct ContextPrefix<'txn, 'c, KC, DC, Ctx: ?Sized, C = DefaultComparator> {
+    iter: RoPrefix<'txn, ByteSlice, ByteSlice, C>,
+    ctx: &'c Ctx,
+    _phantom: marker::PhantomData<(KC, DC)>,
+}
+
+impl<'txn, KC, DC, Ctx: ?Sized, C> Iterator for ContextPrefix<'txn, '_, KC, DC, Ctx, C>
+where
+    KC: BytesDecodeWith<'txn, Ctx>,
+    DC: BytesDecodeWith<'txn, Ctx>,
+    C: LexicographicComparator,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|result| decode_with::<KC, DC, Ctx>(result, self.ctx))
+    }
+}
+
+fn decode_with<'txn, KC, DC, Ctx: ?Sized>(
+    result: Result<(&'txn [u8], &'txn [u8])>,
+    ctx: &Ctx,
+) -> Result<(KC::DItem, DC::DItem)>
+where
+    KC: BytesDecodeWith<'txn, Ctx>,
+    DC: BytesDecodeWith<'txn, Ctx>,
+{
+    let (key, data) = result?;
+    let key = KC::bytes_decode_with(key, ctx).map_err(Error::Decoding)?;
+    let data = DC::bytes_decode_with(data, ctx).map_err(Error::Decoding)?;
+    Ok((key, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use byteorder::BigEndian;
+
+    use super::*;
+    use crate::types::*;
+
+    /// Stores the position of the strings in the interning table given as context.
+    enum Interned {}
+
+    impl<'a> BytesEncodeWith<'a, [&str]> for Interned {
+        type EItem = str;
+
+        fn bytes_encode_with(item: &'a str, table: &[&str]) -> StdResult<Cow<'a, [u8]>> {
+            match table.iter().position(|s| *s == item) {
+                Some(pos) => Ok(Cow::Owned(vec![pos as u8])),
+                None => Err(format!("{:?} is not interned", item).into()),
+            }
+        }
+    }
+
+    impl<'a> BytesDecodeWith<'a, [&str]> for Interned {
+        type DItem = String;
+
+        fn bytes_decode_with(bytes: &'a [u8], table: &[&str]) -> StdResult<String> {
+            match bytes {
+                [pos] => Ok(table[*pos as usize].to_string()),
+                _ => Err("invalid interned string".into()),
+            }
+        }
+    }
+
+    type StdResult<T> = std::result::Result<T, BoxedError>;
+
+    #[test]
+    fn interned_values_with_static_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().map_size(10 * 1024 * 1024).open(dir.path()).unwrap(); // 10MB
+
+        let table: &[&str] = &["red", "green", "blue"];
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<IgnoreContext<Str>, Interned>(&mut wtxn, None).unwrap();
+        let colors = db.with_codec_context(table);
+
+        colors.put(&mut wtxn, "sky", "blue").unwrap();
+        colors.put(&mut wtxn, "grass", "green").unwrap();
+        assert!(matches!(colors.put(&mut wtxn, "sun", "yellow"), Err(Error::Encoding(_))));
+
+        let raw = db.remap_data_type::<ByteSlice>().get(&wtxn, "sky").unwrap();
+        assert_eq!(raw, Some(&[2][..]));
+
+        let entries: Vec<_> = colors.iter(&wtxn).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(entries, [("grass", "green".to_string()), ("sky", "blue".to_string())]);
+
+        let entries: Vec<_> = colors
+            .range(&wtxn, &(Bound::Included("h"), Bound::Unbounded))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(entries, [("sky", "blue".to_string())]);
+
+        let entries: Vec<_> =
+            colors.prefix_iter(&wtxn, "gr").unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(entries, [("grass", "green".to_string())]);
+
+        assert!(colors.delete(&mut wtxn, "sky").unwrap());
+        assert_eq!(colors.get(&wtxn, "sky").unwrap(), None);
+        assert_eq!(colors.get(&wtxn, "grass").unwrap().as_deref(), Some("green"));
+    }
+
+    #[test]
+    fn interned_keys_in_ranges_and_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().map_size(10 * 1024 * 1024).open(dir.path()).unwrap(); // 10MB
+
+        let table: &[&str] = &["apple", "apricot", "banana", "cherry"];
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .create_database::<Interned, IgnoreContext<U32<BigEndian>>>(&mut wtxn, None)
+            .unwrap();
+        let fruits = db.with_codec_context(table);
+        for (i, fruit) in table.iter().enumerate() {
+            fruits.put(&mut wtxn, fruit, &(i as u32 * 10)).unwrap();
+        }
+
+        // The bounds are encoded with the context, the keys are compared by interned position.
+        let entries: Vec<_> = fruits
+            .range(&wtxn, &(Bound::Included("apricot"), Bound::Included("banana")))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(entries, [("apricot".to_string(), 10), ("banana".to_string(), 20)]);
+
+        let entries: Vec<_> = fruits
+            .range(&wtxn, &(Bound::Unbounded, Bound::Excluded("banana")))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(entries, [("apple".to_string(), 0), ("apricot".to_string(), 10)]);
+
+        let entries: Vec<_> =
+            fruits.prefix_iter(&wtxn, "cherry").unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(entries, [("cherry".to_string(), 30)]);
+
+        assert!(matches!(
+            fruits.range(&wtxn, &(Bound::Included("kiwi"), Bound::Unbounded)),
+            Err(Error::Encoding(_))
+        ));
+        assert!(matches!(fruits.prefix_iter(&wtxn, "kiwi"), Err(Error::Encoding(_))));
+    }
+}
@@ -0,0 +1,416 @@
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+use heed_traits::{Comparator, ContiguousPrefixComparator, LexicographicComparator};
+
+/// A lexicographic comparator where every byte is compared in descending order.
+///
+/// The keys are sorted in the reverse byte order, shorter keys still collating
+/// before longer ones, and the prefix iterators keep working with it.
+pub enum ReverseLexicographicComparator {}
+
+impl LexicographicComparator for ReverseLexicographicComparator {
+    #[inline]
+    fn compare_elem(a: u8, b: u8) -> Ordering {
+        b.cmp(&a)
+    }
+
+    #[inline]
+    fn successor(elem: u8) -> Option<u8> {
+        elem.checked_sub(1)
+    }
+
+    #[inline]
+    fn predecessor(elem: u8) -> Option<u8> {
+        elem.checked_add(1)
+    }
+
+    #[inline]
+    fn max_elem() -> u8 {
+        u8::MIN
+    }
+
+    #[inline]
+    fn min_elem() -> u8 {
+        u8::MAX
+    }
+}
+
+impl ContiguousPrefixComparator for ReverseLexicographicComparator {}
+
+/// A lexicographic comparator that ignores the case of the ASCII letters.
+///
+/// Keys only differing by the case of their ASCII letters are considered equal,
+/// therefore a database using it only stores one of them.
+pub enum AsciiCaseInsensitiveComparator {}
+
+impl LexicographicComparator for AsciiCaseInsensitiveComparator {
+    #[inline]
+    fn compare_elem(a: u8, b: u8) -> Ordering {
+        a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase())
+    }
+
+    #[inline]
+    fn successor(elem: u8) -> Option<u8> {
+        // The uppercase letters are equal to the lowercase ones, we skip them.
+        match elem.to_ascii_lowercase().checked_add(1)? {
+            next if next.is_ascii_uppercase() => Some(b'Z' + 1),
+            next => Some(next),
+        }
+    }
+
+    #[inline]
+    fn predecessor(elem: u8) -> Option<u8> {
+        match elem.to_ascii_lowercase().checked_sub(1)? {
+            prev if prev.is_ascii_uppercase() => Some(b'A' - 1),
+            prev => Some(prev),
+        }
+    }
+
+    #[inline]
+    fn max_elem() -> u8 {
+        u8::MAX
+    }
+
+    #[inline]
+    fn min_elem() -> u8 {
+        u8::MIN
+    }
+}
+
+/// A comparator that sorts the runs of ASCII digits by their numeric value.
+///
+/// It sorts `"file2"` before `"file10"`, the other bytes are compared one by one.
+/// Keys with numbers only differing by their leading zeros, like `"file02"` and
+/// `"file2"`, are ordered by their bytes so that they are never considered equal.
+///
+/// The keys starting with `"file1"` are not next to each other, `"file2"` sorting between
+/// `"file1"` and `"file10"`, it therefore can't be used with the prefix iterators.
+///
+/// ```compile_fail
+/// use heed::types::*;
+/// use heed::{EnvOpenOptions, NaturalComparator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = tempfile::tempdir()?;
+/// let env = EnvOpenOptions::new().open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let db = env
+///     .database_options()
+///     .types::<Str, Unit>()
+///     .key_comparator::<NaturalComparator>()
+///     .create(&mut wtxn)?;
+/// let files = db.prefix_iter(&wtxn, "file1")?;
+/// # Ok(()) }
+/// ```
+pub enum NaturalComparator {}
+
+impl Comparator for NaturalComparator {
+    fn compare(a: &[u8], b: &[u8]) -> Ordering {
+        let (mut i, mut j) = (0, 0);
+        loop {
+            match (a.get(i), b.get(j)) {
+                (None, None) => return a.cmp(b),
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                    let a_end = digits_end(a, i);
+                    let b_end = digits_end(b, j);
+                    let a_number = trim_leading_zeros(&a[i..a_end]);
+                    let b_number = trim_leading_zeros(&b[j..b_end]);
+                    let ordering =
+                        a_number.len().cmp(&b_number.len()).then_with(|| a_number.cmp(b_number));
+                    if ordering.is_ne() {
+                        return ordering;
+                    }
+                    i = a_end;
+                    j = b_end;
+                }
+                (Some(x), Some(y)) if x != y => return x.cmp(y),
+                (Some(_), Some(_)) => {
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+    }
+}
+
+fn digits_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..].iter().position(|b| !b.is_ascii_digit()).map_or(bytes.len(), |len| start + len)
+}
+
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&b| b == b'0').count();
+    &digits[zeros..]
+}
+
+/// A comparator that sorts the keys as native-endian unsigned integers, like the
+/// `INTEGER_KEY` database flag does, to be used with the `NativeEndian` integer codecs.
+///
+/// Keys that don't have the size of the integer are sorted by their length then by their bytes.
+///
+/// The keys starting with the same bytes are not next to each other on little-endian
+/// targets, it therefore can't be used with the prefix iterators.
+///
+/// ```compile_fail
+/// use heed::byteorder::NativeEndian;
+/// use heed::types::*;
+/// use heed::{EnvOpenOptions, NativeEndianComparator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = tempfile::tempdir()?;
+/// let env = EnvOpenOptions::new().open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let db = env
+///     .database_options()
+///     .types::<U32<NativeEndian>, Unit>()
+///     .key_comparator::<NativeEndianComparator<u32>>()
+///     .create(&mut wtxn)?;
+/// let keys = db.prefix_iter(&wtxn, &0)?;
+/// # Ok(()) }
+/// ```
+pub struct NativeEndianComparator<T>(PhantomData<fn() -> T>);
+
+macro_rules! impl_native_endian_comparator {
+    ($($ty:ty),+) => {
+        $(
+            impl Comparator for NativeEndianComparator<$ty> {
+                fn compare(a: &[u8], b: &[u8]) -> Ordering {
+                    match (<[u8; std::mem::size_of::<$ty>()]>::try_from(a), b.try_into()) {
+                        (Ok(a), Ok(b)) => <$ty>::from_ne_bytes(a).cmp(&<$ty>::from_ne_bytes(b)),
+                        _ => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_native_endian_comparator!(u16, u32, u64, u128);
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::ops::Bound::{Excluded, Included};
+
+    use heed_traits::Comparator;
+
+    use super::*;
+    use crate::byteorder::NativeEndian;
+    use crate::types::*;
+    use crate::EnvOpenOptions;
+
+    #[test]
+    fn reverse_lexicographic() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Unit>()
+            .key_comparator::<ReverseLexicographicComparator>()
+            .create(&mut wtxn)
+            .unwrap();
+        for key in ["a", "b", "ba", "bb", "bc", "c", "\u{7f}"] {
+            db.put(&mut wtxn, key, &()).unwrap();
+        }
+
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["\u{7f}", "c", "b", "bc", "bb", "ba", "a"]);
+
+        let keys: Vec<_> = db.prefix_iter(&wtxn, "b").unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["b", "bc", "bb", "ba"]);
+        let keys: Vec<_> = db.rev_prefix_iter(&wtxn, "b").unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["ba", "bb", "bc", "b"]);
+
+        let keys: Vec<_> = db
+            .range(&wtxn, &(Included("c"), Included("bb")))
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, ["c", "b", "bc", "bb"]);
+        let keys: Vec<_> = db
+            .rev_range(&wtxn, &(Included("c"), Excluded("bb")))
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, ["bc", "b", "c"]);
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn ascii_case_insensitive() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Unit>()
+            .key_comparator::<AsciiCaseInsensitiveComparator>()
+            .create(&mut wtxn)
+            .unwrap();
+        for key in ["apple", "Banana", "bAnjo", "BAR", "_under", "cherry", "[", "@"] {
+            db.put(&mut wtxn, key, &()).unwrap();
+        }
+        assert!(db.get(&wtxn, "BANANA").unwrap().is_some());
+
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["@", "[", "_under", "apple", "Banana", "bAnjo", "BAR", "cherry"]);
+
+        let keys: Vec<_> = db.prefix_iter(&wtxn, "BAN").unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["Banana", "bAnjo"]);
+        let keys: Vec<_> = db.rev_prefix_iter(&wtxn, "ba").unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["BAR", "bAnjo", "Banana"]);
+        // The successor of `@` is `[`, the uppercase letters being equal to the lowercase ones.
+        let keys: Vec<_> = db.prefix_iter(&wtxn, "@").unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["@"]);
+
+        let keys: Vec<_> = db
+            .range(&wtxn, &(Included("APPLE"), Included("banjo")))
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, ["apple", "Banana", "bAnjo"]);
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn natural() {
+        let cmp = |a: &str, b: &str| NaturalComparator::compare(a.as_bytes(), b.as_bytes());
+        assert_eq!(cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(cmp("file02", "file2"), Ordering::Less);
+        assert_eq!(cmp("file2", "file2"), Ordering::Equal);
+        assert_eq!(cmp("file2b", "file2a"), Ordering::Greater);
+        assert_eq!(cmp("file", "file0"), Ordering::Less);
+        assert_eq!(cmp("a100", "b2"), Ordering::Less);
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Unit>()
+            .key_comparator::<NaturalComparator>()
+            .create(&mut wtxn)
+            .unwrap();
+        for key in ["file10", "file2", "file1", "file100", "file20", "other3"] {
+            db.put(&mut wtxn, key, &()).unwrap();
+        }
+
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["file1", "file2", "file10", "file20", "file100", "other3"]);
+
+        // The keys starting with a prefix are not contiguous, the prefix iterators can't be used.
+        let positions: Vec<_> = (0..keys.len()).filter(|&i| keys[i].starts_with("file1")).collect();
+        assert_eq!(positions, [0, 2, 4]);
+        // A range still lists the keys between two numbers.
+        let keys: Vec<_> = db
+            .range(&wtxn, &(Included("file10"), Excluded("file20")))
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, ["file10"]);
+
+        let keys: Vec<_> = db
+            .range(&wtxn, &(Included("file2"), Included("file20")))
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, ["file2", "file10", "file20"]);
+        let keys: Vec<_> = db
+            .rev_range(&wtxn, &(Included("file3"), Excluded("file100")))
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, ["file20", "file10"]);
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn native_endian() {
+        macro_rules! check_native_endian {
+            ($ty:ty, $codec:ty) => {{
+                let dir = tempfile::tempdir().unwrap();
+                let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+                let mut wtxn = env.write_txn().unwrap();
+                let db = env
+                    .database_options()
+                    .types::<$codec, Unit>()
+                    .key_comparator::<NativeEndianComparator<$ty>>()
+                    .create(&mut wtxn)
+                    .unwrap();
+                for key in [256, 1, <$ty>::MAX / 2, 255, 0, <$ty>::MAX] {
+                    db.put(&mut wtxn, &key, &()).unwrap();
+                }
+
+                let keys: Vec<$ty> = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+                assert_eq!(keys, [0, 1, 255, 256, <$ty>::MAX / 2, <$ty>::MAX]);
+
+                let keys: Vec<$ty> =
+                    db.range(&wtxn, &(255..=256)).unwrap().map(|r| r.unwrap().0).collect();
+                assert_eq!(keys, [255, 256]);
+                let keys: Vec<$ty> =
+                    db.rev_range(&wtxn, &(1..256)).unwrap().map(|r| r.unwrap().0).collect();
+                assert_eq!(keys, [255, 1]);
+
+                // The keys sharing their first byte are not contiguous on little-endian
+                // targets, the prefix iterators can't be used.
+                let first_bytes: Vec<_> = db
+                    .remap_key_type::<ByteSlice>()
+                    .iter(&wtxn)
+                    .unwrap()
+                    .map(|r| r.unwrap().0[0])
+                    .collect();
+                if cfg!(target_endian = "little") {
+                    assert_eq!(first_bytes[..4], [0, 1, 255, 0]);
+                }
+                wtxn.commit().unwrap();
+            }};
+        }
+
+        check_native_endian!(u16, U16<NativeEndian>);
+        check_native_endian!(u32, U32<NativeEndian>);
+        check_native_endian!(u64, U64<NativeEndian>);
+        check_native_endian!(u128, U128<NativeEndian>);
+    }
+
+    #[test]
+    fn lexicographic_comparators_only_compare_distinct_bytes() {
+        enum Descending {}
+
+        impl LexicographicComparator for Descending {
+            fn compare_elem(a: u8, b: u8) -> Ordering {
+                assert_ne!(a, b, "equal bytes must not be compared");
+                b.cmp(&a)
+            }
+
+            fn successor(elem: u8) -> Option<u8> {
+                elem.checked_sub(1)
+            }
+
+            fn predecessor(elem: u8) -> Option<u8> {
+                elem.checked_add(1)
+            }
+
+            fn max_elem() -> u8 {
+                u8::MIN
+            }
+
+            fn min_elem() -> u8 {
+                u8::MAX
+            }
+        }
+
+        assert_eq!(Descending::compare(b"abc", b"abc"), Ordering::Equal);
+        assert_eq!(Descending::compare(b"abc", b"abd"), Ordering::Greater);
+        assert_eq!(Descending::compare(b"abd", b"abc"), Ordering::Less);
+        assert_eq!(Descending::compare(b"ab", b"abc"), Ordering::Less);
+        assert_eq!(Descending::compare(b"b", b"abc"), Ordering::Less);
+        assert_eq!(Descending::compare(b"", b""), Ordering::Equal);
+    }
+}
@@ -1,20 +1,55 @@
+use std::cmp::Ordering;
 use std::ops::{Deref, DerefMut};
-use std::{marker, mem, ptr};
+use std::{mem, ptr};
 
+use crate::env::catch_compare;
 use crate::mdb::error::mdb_result;
 use crate::mdb::ffi;
 use crate::*;
 
 pub struct RoCursor<'txn> {
     cursor: *mut ffi::MDB_cursor,
-    _marker: marker::PhantomData<&'txn ()>,
+    txn: &'txn RoTxn<'txn>,
 }
 
 impl<'txn> RoCursor<'txn> {
     pub(crate) fn new(txn: &'txn RoTxn, dbi: ffi::MDB_dbi) -> Result<RoCursor<'txn>> {
         let mut cursor: *mut ffi::MDB_cursor = ptr::null_mut();
         unsafe { mdb_result(ffi::mdb_cursor_open(txn.txn, dbi, &mut cursor))? }
-        Ok(RoCursor { cursor, _marker: marker::PhantomData })
+        Ok(RoCursor { cursor, txn })
+    }
+
+    /// Opens another cursor on the same database and transaction.
+    pub(crate) fn sibling(&self) -> Result<RoCursor<'txn>> {
+        let mut cursor: *mut ffi::MDB_cursor = ptr::null_mut();
+        unsafe {
+            let dbi = ffi::mdb_cursor_dbi(self.cursor);
+            mdb_result(ffi::mdb_cursor_open(self.txn.txn, dbi, &mut cursor))?
+        }
+        Ok(RoCursor { cursor, txn: self.txn })
+    }
+
+    /// Returns the transaction this cursor has been opened in.
+    pub(crate) fn txn(&self) -> &'txn RoTxn<'txn> {
+        self.txn
+    }
+
+    /// Compares two keys with the comparator of the database of this cursor.
+    pub(crate) fn compare_keys(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        let a_val = unsafe { crate::into_val(a) };
+        let b_val = unsafe { crate::into_val(b) };
+        let result = unsafe {
+            let dbi = ffi::mdb_cursor_dbi(self.cursor);
+            ffi::mdb_cmp(self.txn.txn, dbi, &a_val, &b_val)
+        };
+        self.txn.handle().check_comparator_panic()?;
+        Ok(result.cmp(&0))
+    }
+
+    /// Compares two keys with `C`, the comparator of the database of this cursor,
+    /// without going through LMDB, a panic of the comparator poisons the transaction.
+    pub(crate) fn compare_with<C: Comparator>(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        catch_compare::<C>(a, b).map_err(|message| self.txn.handle().poison(message))
     }
 
     pub fn current(&mut self) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
@@ -136,6 +171,7 @@ impl<'txn> RoCursor<'txn> {
                 ffi::cursor_op::MDB_SET,
             ))
         };
+        self.txn.handle().check_comparator_panic()?;
 
         match result {
             Ok(()) => Ok(true),
@@ -160,6 +196,7 @@ impl<'txn> RoCursor<'txn> {
                 ffi::cursor_op::MDB_SET_RANGE,
             ))
         };
+        self.txn.handle().check_comparator_panic()?;
 
         match result {
             Ok(()) => {
@@ -308,6 +345,7 @@ impl<'txn> RwCursor<'txn> {
             &mut data_val,
             ffi::MDB_CURRENT,
         ));
+        self.cursor.txn.handle().check_comparator_panic()?;
 
         match result {
             Ok(()) => Ok(true),
@@ -344,6 +382,7 @@ impl<'txn> RwCursor<'txn> {
 
         let result =
             mdb_result(ffi::mdb_cursor_put(self.cursor.cursor, &mut key_val, &mut reserved, flags));
+        self.cursor.txn.handle().check_comparator_panic()?;
 
         let found = match result {
             Ok(()) => true,
@@ -395,6 +434,7 @@ impl<'txn> RwCursor<'txn> {
             &mut data_val,
             flags.bits(),
         ));
+        self.cursor.txn.handle().check_comparator_panic()?;
 
         result.map_err(Into::into)
     }
@@ -1,5 +1,4 @@
 use std::any::TypeId;
-use std::borrow::Cow;
 use std::ops::{Bound, RangeBounds};
 use std::{any, fmt, marker, mem, ptr};
 
@@ -12,6 +11,8 @@ use crate::iteration_method::MoveOnCurrentKeyDuplicates;
 use crate::mdb::error::mdb_result;
 use crate::mdb::ffi;
 use crate::mdb::lmdb_flags::{AllDatabaseFlags, DatabaseFlags};
+use crate::metadata::{self, TypeMetadata};
+use crate::txn::TxnHandle;
 use crate::*;
 
 /// Options and flags which can be used to configure how a [`Database`] is opened.
@@ -59,6 +60,8 @@ pub struct DatabaseOpenOptions<'e, KC, DC, C> {
     types: marker::PhantomData<(KC, DC, C)>,
     name: Option<String>,
     flags: AllDatabaseFlags,
+    persist_types: bool,
+    type_tags: Option<(String, String)>,
 }
 
 impl<'e> DatabaseOpenOptions<'e, Unspecified, Unspecified, DefaultComparator> {
@@ -69,6 +72,8 @@ impl<'e> DatabaseOpenOptions<'e, Unspecified, Unspecified, DefaultComparator> {
             types: Default::default(),
             name: None,
             flags: AllDatabaseFlags::empty(),
+            persist_types: false,
+            type_tags: None,
         }
     }
 }
@@ -84,17 +89,22 @@ impl<'e, KC, DC, C> DatabaseOpenOptions<'e, KC, DC, C> {
             types: Default::default(),
             name: self.name,
             flags: self.flags,
+            persist_types: self.persist_types,
+            type_tags: None,
         }
     }
     /// Change the customized key compare function of the database.
     ///
     /// By default no customized compare function will be set when opening a database.
+    /// Use a [`TypedComparator`] to compare the decoded keys instead of their bytes.
     pub fn key_comparator<NC>(self) -> DatabaseOpenOptions<'e, KC, DC, NC> {
         DatabaseOpenOptions {
             env: self.env,
             types: Default::default(),
             name: self.name,
             flags: self.flags,
+            persist_types: self.persist_types,
+            type_tags: self.type_tags,
         }
     }
 
@@ -112,6 +122,79 @@ impl<'e, KC, DC, C> DatabaseOpenOptions<'e, KC, DC, C> {
         self
     }
 
+    /// Persist the codecs, comparator and flags of the database in the
+    /// [type metadata database](crate::TYPE_METADATA_DATABASE_NAME).
+    ///
+    /// They are recorded by [`create`](Self::create) and checked by both [`create`](Self::create)
+    /// and [`open`](Self::open), even across restarts, which fail with an
+    /// [`Error::TypeMetadataMismatch`] when they differ. The codecs are identified
+    /// by their [type name](std::any::type_name) unless [tags](Self::type_tags) are specified.
+    /// The flags are only recorded, LMDB keeps the ones a database was created with and
+    /// ignores the flags given to open it afterward.
+    ///
+    /// Note that the metadata database counts in the [`max_dbs`](crate::EnvOpenOptions::max_dbs).
+    ///
+    /// ```
+    /// # use heed::EnvOpenOptions;
+    /// use heed::types::*;
+    /// use heed::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let env = EnvOpenOptions::new()
+    /// #     .map_size(10 * 1024 * 1024) // 10MB
+    /// #     .max_dbs(3000)
+    /// #     .open(dir.path())?;
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.database_options()
+    ///     .types::<Str, Str>()
+    ///     .name("persisted-types")
+    ///     .persist_types()
+    ///     .create(&mut wtxn)?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.read_txn()?;
+    /// let result = env.database_options()
+    ///     .types::<Str, ByteSlice>()
+    ///     .name("persisted-types")
+    ///     .persist_types()
+    ///     .open(&rtxn);
+    /// assert!(matches!(result, Err(Error::TypeMetadataMismatch { .. })));
+    /// # Ok(()) }
+    /// ```
+    pub fn persist_types(&mut self) -> &mut Self {
+        self.persist_types = true;
+        self
+    }
+
+    /// Persist the codecs of the database under these tags instead of their type names.
+    ///
+    /// Tags are stable across refactorings of the codec types. This implies
+    /// [`persist_types`](Self::persist_types).
+    pub fn type_tags(&mut self, key: impl Into<String>, data: impl Into<String>) -> &mut Self {
+        self.persist_types = true;
+        self.type_tags = Some((key.into(), data.into()));
+        self
+    }
+
+    fn type_metadata(&self) -> Option<TypeMetadata> {
+        if !self.persist_types {
+            return None;
+        }
+
+        let (key, data) = match &self.type_tags {
+            Some((key, data)) => (key.clone(), data.clone()),
+            None => (any::type_name::<KC>().to_string(), any::type_name::<DC>().to_string()),
+        };
+
+        Some(TypeMetadata {
+            key,
+            data,
+            comparator: any::type_name::<C>().to_string(),
+            flags: self.flags.bits(),
+        })
+    }
+
     /// Opens a typed database that already exists in this environment.
     ///
     /// If the database was previously opened in this program run, types will be checked.
@@ -136,12 +219,20 @@ impl<'e, KC, DC, C> DatabaseOpenOptions<'e, KC, DC, C> {
         DC: 'static,
         C: Comparator + 'static,
     {
-        assert_eq_env_txn!(self.env, rtxn);
+        ensure_same_env_txn!(self.env, rtxn);
 
         let types = (TypeId::of::<KC>(), TypeId::of::<DC>(), TypeId::of::<C>());
         let name = self.name.as_deref();
-        match self.env.raw_init_database::<C>(rtxn.txn, name, types, self.flags) {
-            Ok(dbi) => Ok(Some(Database::new(self.env.env_mut_ptr() as _, dbi))),
+
+        // We check the persisted types before registering the types of this dbi.
+        if let Some(metadata) = self.type_metadata() {
+            metadata::check(self.env, rtxn, name, metadata)?;
+        }
+
+        match self.env.raw_init_database::<C>(rtxn, name, types, self.flags) {
+            Ok((dbi, generation)) => {
+                Ok(Some(Database::new(self.env.env_mut_ptr() as _, dbi, generation)))
+            }
             Err(Error::Mdb(e)) if e.not_found() => Ok(None),
             Err(e) => Err(e),
         }
@@ -156,19 +247,27 @@ impl<'e, KC, DC, C> DatabaseOpenOptions<'e, KC, DC, C> {
     /// LMDB have an important restriction on the unnamed database when named ones are opened,
     /// the names of the named databases are stored as keys in the unnamed one and are immutable,
     /// these keys can only be read and not written.
-    pub fn create(&self, wtxn: &RwTxn) -> Result<Database<KC, DC, C>>
+    pub fn create(&self, wtxn: &mut RwTxn) -> Result<Database<KC, DC, C>>
     where
         KC: 'static,
         DC: 'static,
         C: Comparator + 'static,
     {
-        assert_eq_env_txn!(self.env, wtxn);
+        ensure_same_env_txn!(self.env, wtxn);
 
         let types = (TypeId::of::<KC>(), TypeId::of::<DC>(), TypeId::of::<C>());
         let name = self.name.as_deref();
         let flags = self.flags | AllDatabaseFlags::CREATE;
-        match self.env.raw_init_database::<C>(wtxn.txn.txn, name, types, flags) {
-            Ok(dbi) => Ok(Database::new(self.env.env_mut_ptr() as _, dbi)),
+
+        // We check the persisted types before registering the types of this dbi.
+        if let Some(metadata) = self.type_metadata() {
+            metadata::record_or_check(self.env, wtxn, name, metadata)?;
+        }
+
+        match self.env.raw_init_database::<C>(wtxn, name, types, flags) {
+            Ok((dbi, generation)) => {
+                Ok(Database::new(self.env.env_mut_ptr() as _, dbi, generation))
+            }
             Err(e) => Err(e),
         }
     }
@@ -287,12 +386,19 @@ impl<'e, KC, DC, C> DatabaseOpenOptions<'e, KC, DC, C> {
 pub struct Database<KC, DC, C = DefaultComparator> {
     pub(crate) env_ident: usize,
     pub(crate) dbi: ffi::MDB_dbi,
+    pub(crate) generation: u32,
+    /// Whether the keys are compared by a custom comparator that may panic.
+    custom_comparator: bool,
     marker: marker::PhantomData<(KC, DC, C)>,
 }
 
 impl<KC, DC, C> Database<KC, DC, C> {
-    pub(crate) fn new(env_ident: usize, dbi: ffi::MDB_dbi) -> Database<KC, DC, C> {
-        Database { env_ident, dbi, marker: std::marker::PhantomData }
+    pub(crate) fn new(env_ident: usize, dbi: ffi::MDB_dbi, generation: u32) -> Database<KC, DC, C>
+    where
+        C: 'static,
+    {
+        let custom_comparator = TypeId::of::<C>() != TypeId::of::<DefaultComparator>();
+        Database { env_ident, dbi, generation, custom_comparator, marker: std::marker::PhantomData }
     }
 
     /// Retrieves the value associated with a key.
@@ -336,26 +442,31 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         DC: BytesDecode<'txn>,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        self.with_key_context(txn.handle(), "get", key, move || {
+            let result = txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
 
-        let mut key_val = unsafe { crate::into_val(&key_bytes) };
-        let mut data_val = mem::MaybeUninit::uninit();
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+                let mut data_val = mem::MaybeUninit::uninit();
 
-        let result = unsafe {
-            mdb_result(ffi::mdb_get(txn.txn, self.dbi, &mut key_val, data_val.as_mut_ptr()))
-        };
+                let result = unsafe {
+                    mdb_result(ffi::mdb_get(txn.txn, self.dbi, &mut key_val, data_val.as_mut_ptr()))
+                };
+
+                match result {
+                    Ok(()) => Ok(Some(unsafe { crate::from_val(data_val.assume_init()) })),
+                    Err(e) if e.not_found() => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            })?;
 
-        match result {
-            Ok(()) => {
-                let data = unsafe { crate::from_val(data_val.assume_init()) };
-                let data = DC::bytes_decode(data).map_err(Error::Decoding)?;
-                Ok(Some(data))
+            match result {
+                Some(data) => DC::bytes_decode(data).map(Some).map_err(Error::Decoding),
+                None => Ok(None),
             }
-            Err(e) if e.not_found() => Ok(None),
-            Err(e) => Err(e.into()),
-        }
+        })
     }
 
     /// Returns an iterator over all of the values of a single key.
@@ -414,15 +525,19 @@ impl<KC, DC, C> Database<KC, DC, C> {
     where
         KC: BytesEncode<'a>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        if cursor.move_on_key(&key_bytes)? {
-            Ok(Some(RoIter::new(cursor)))
-        } else {
-            Ok(None)
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "get_duplicates", key, move || {
+            let mut cursor = RoCursor::new(txn, self.dbi)?;
+            txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+                if cursor.move_on_key(key_bytes)? {
+                    Ok(Some(RoIter::new(cursor)))
+                } else {
+                    Ok(None)
+                }
+            })
+        })
     }
 
     /// Retrieves the key/value pair lower than the given one in this database.
@@ -477,20 +592,26 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a> + BytesDecode<'txn>,
         DC: BytesDecode<'txn>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        cursor.move_on_key_greater_than_or_equal_to(&key_bytes)?;
-
-        match cursor.move_on_prev(MoveOperation::NoDup) {
-            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
-            },
-            Ok(None) => Ok(None),
-            Err(e) => Err(e),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "get_lower_than", key, move || {
+            let mut cursor = RoCursor::new(txn, self.dbi)?;
+            txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+                cursor.move_on_key_greater_than_or_equal_to(key_bytes)?;
+
+                match cursor.move_on_prev(MoveOperation::NoDup) {
+                    Ok(Some((key, data))) => {
+                        match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                            (Ok(key), Ok(data)) => Ok(Some((key, data))),
+                            (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                        }
+                    }
+                    Ok(None) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            })
+        })
     }
 
     /// Retrieves the key/value pair lower than or equal to the given one in this database.
@@ -545,24 +666,30 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a> + BytesDecode<'txn>,
         DC: BytesDecode<'txn>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let result = match cursor.move_on_key_greater_than_or_equal_to(&key_bytes) {
-            Ok(Some((key, data))) if key == &key_bytes[..] => Ok(Some((key, data))),
-            Ok(_) => cursor.move_on_prev(MoveOperation::NoDup),
-            Err(e) => Err(e),
-        };
-
-        match result {
-            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
-            },
-            Ok(None) => Ok(None),
-            Err(e) => Err(e),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "get_lower_than_or_equal_to", key, move || {
+            let mut cursor = RoCursor::new(txn, self.dbi)?;
+            txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+                let result = match cursor.move_on_key_greater_than_or_equal_to(key_bytes) {
+                    Ok(Some((key, data))) if key == &key_bytes[..] => Ok(Some((key, data))),
+                    Ok(_) => cursor.move_on_prev(MoveOperation::NoDup),
+                    Err(e) => Err(e),
+                };
+
+                match result {
+                    Ok(Some((key, data))) => {
+                        match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                            (Ok(key), Ok(data)) => Ok(Some((key, data))),
+                            (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                        }
+                    }
+                    Ok(None) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            })
+        })
     }
 
     /// Retrieves the key/value pair greater than the given one in this database.
@@ -617,23 +744,27 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a> + BytesDecode<'txn>,
         DC: BytesDecode<'txn>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let entry = match cursor.move_on_key_greater_than_or_equal_to(&key_bytes)? {
-            Some((key, data)) if key > &key_bytes[..] => Some((key, data)),
-            Some((_key, _data)) => cursor.move_on_next(MoveOperation::NoDup)?,
-            None => None,
-        };
-
-        match entry {
-            Some((key, data)) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
-            },
-            None => Ok(None),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "get_greater_than", key, move || {
+            let mut cursor = RoCursor::new(txn, self.dbi)?;
+            txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+                let entry = match cursor.move_on_key_greater_than_or_equal_to(key_bytes)? {
+                    Some((key, data)) if key > &key_bytes[..] => Some((key, data)),
+                    Some((_key, _data)) => cursor.move_on_next(MoveOperation::NoDup)?,
+                    None => None,
+                };
+
+                match entry {
+                    Some((key, data)) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                        (Ok(key), Ok(data)) => Ok(Some((key, data))),
+                        (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                    },
+                    None => Ok(None),
+                }
+            })
+        })
     }
 
     /// Retrieves the key/value pair greater than or equal to the given one in this database.
@@ -688,18 +819,24 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a> + BytesDecode<'txn>,
         DC: BytesDecode<'txn>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        match cursor.move_on_key_greater_than_or_equal_to(&key_bytes) {
-            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
-            },
-            Ok(None) => Ok(None),
-            Err(e) => Err(e),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "get_greater_than_or_equal_to", key, move || {
+            let mut cursor = RoCursor::new(txn, self.dbi)?;
+            txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+                match cursor.move_on_key_greater_than_or_equal_to(key_bytes) {
+                    Ok(Some((key, data))) => {
+                        match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                            (Ok(key), Ok(data)) => Ok(Some((key, data))),
+                            (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                        }
+                    }
+                    Ok(None) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            })
+        })
     }
 
     /// Retrieves the first key/value pair of this database.
@@ -742,17 +879,19 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesDecode<'txn>,
         DC: BytesDecode<'txn>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        match cursor.move_on_first(MoveOperation::Any) {
-            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
-            },
-            Ok(None) => Ok(None),
-            Err(e) => Err(e),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_context(txn.handle(), "first", move || {
+            let mut cursor = RoCursor::new(txn, self.dbi)?;
+            match cursor.move_on_first(MoveOperation::Any) {
+                Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Ok(Some((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                },
+                Ok(None) => Ok(None),
+                Err(e) => Err(e),
+            }
+        })
     }
 
     /// Retrieves the last key/value pair of this database.
@@ -795,17 +934,19 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesDecode<'txn>,
         DC: BytesDecode<'txn>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        match cursor.move_on_last(MoveOperation::Any) {
-            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
-            },
-            Ok(None) => Ok(None),
-            Err(e) => Err(e),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_context(txn.handle(), "last", move || {
+            let mut cursor = RoCursor::new(txn, self.dbi)?;
+            match cursor.move_on_last(MoveOperation::Any) {
+                Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Ok(Some((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                },
+                Ok(None) => Ok(None),
+                Err(e) => Err(e),
+            }
+        })
     }
 
     /// Returns the number of elements in this database.
@@ -847,18 +988,21 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// # Ok(()) }
     /// ```
     pub fn len(&self, txn: &RoTxn) -> Result<u64> {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut db_stat = mem::MaybeUninit::uninit();
-        let result = unsafe { mdb_result(ffi::mdb_stat(txn.txn, self.dbi, db_stat.as_mut_ptr())) };
-
-        match result {
-            Ok(()) => {
-                let stats = unsafe { db_stat.assume_init() };
-                Ok(stats.ms_entries as u64)
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_context(txn.handle(), "len", move || {
+            let mut db_stat = mem::MaybeUninit::uninit();
+            let result =
+                unsafe { mdb_result(ffi::mdb_stat(txn.txn, self.dbi, db_stat.as_mut_ptr())) };
+
+            match result {
+                Ok(()) => {
+                    let stats = unsafe { db_stat.assume_init() };
+                    Ok(stats.ms_entries as u64)
+                }
+                Err(e) => Err(e.into()),
             }
-            Err(e) => Err(e.into()),
-        }
+        })
     }
 
     /// Returns `true` if and only if this database is empty.
@@ -940,8 +1084,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// # Ok(()) }
     /// ```
     pub fn iter<'txn>(&self, txn: &'txn RoTxn) -> Result<RoIter<'txn, KC, DC>> {
-        assert_eq_env_db_txn!(self, txn);
-        RoCursor::new(txn, self.dbi).map(|cursor| RoIter::new(cursor))
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_context(txn.handle(), "iter", move || {
+            RoCursor::new(txn, self.dbi).map(|cursor| RoIter::new(cursor))
+        })
     }
 
     /// Return a mutable lexicographically ordered iterator of all key-value pairs in this database.
@@ -994,9 +1141,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// # Ok(()) }
     /// ```
     pub fn iter_mut<'txn>(&self, txn: &'txn mut RwTxn) -> Result<RwIter<'txn, KC, DC>> {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwIter::new(cursor))
+        self.with_context(txn.handle(), "iter_mut", move || {
+            RwCursor::new(txn, self.dbi).map(|cursor| RwIter::new(cursor))
+        })
     }
 
     /// Return a reversed lexicographically ordered iterator of all key-value pairs in this database.
@@ -1036,9 +1185,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// # Ok(()) }
     /// ```
     pub fn rev_iter<'txn>(&self, txn: &'txn RoTxn) -> Result<RoRevIter<'txn, KC, DC>> {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRevIter::new(cursor))
+        self.with_context(txn.handle(), "rev_iter", move || {
+            RoCursor::new(txn, self.dbi).map(|cursor| RoRevIter::new(cursor))
+        })
     }
 
     /// Return a mutable reversed lexicographically ordered iterator of all key-value\
@@ -1092,9 +1243,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// # Ok(()) }
     /// ```
     pub fn rev_iter_mut<'txn>(&self, txn: &'txn mut RwTxn) -> Result<RwRevIter<'txn, KC, DC>> {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRevIter::new(cursor))
+        self.with_context(txn.handle(), "rev_iter_mut", move || {
+            RwCursor::new(txn, self.dbi).map(|cursor| RwRevIter::new(cursor))
+        })
     }
 
     /// Return a lexicographically ordered iterator of a range of key-value pairs in this database.
@@ -1140,38 +1293,20 @@ impl<KC, DC, C> Database<KC, DC, C> {
         &self,
         txn: &'txn RoTxn,
         range: &'a R,
-    ) -> Result<RoRange<'txn, KC, DC>>
+    ) -> Result<RoRange<'txn, KC, DC, C>>
     where
         KC: BytesEncode<'a>,
         R: RangeBounds<KC::EItem>,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let start_bound = match range.start_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+        self.with_context(txn.handle(), "range", move || {
+            let start_bound = encode_bound::<KC>(txn, range.start_bound())?;
 
-        let end_bound = match range.end_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+            let end_bound = encode_bound::<KC>(txn, range.end_bound())?;
 
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRange::new(cursor, start_bound, end_bound))
+            RoCursor::new(txn, self.dbi).map(|cursor| RoRange::new(cursor, start_bound, end_bound))
+        })
     }
 
     /// Return a mutable lexicographically ordered iterator of a range of
@@ -1231,38 +1366,20 @@ impl<KC, DC, C> Database<KC, DC, C> {
         &self,
         txn: &'txn mut RwTxn,
         range: &'a R,
-    ) -> Result<RwRange<'txn, KC, DC>>
+    ) -> Result<RwRange<'txn, KC, DC, C>>
     where
         KC: BytesEncode<'a>,
         R: RangeBounds<KC::EItem>,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let start_bound = match range.start_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+        self.with_context(txn.handle(), "range_mut", move || {
+            let start_bound = encode_bound::<KC>(txn, range.start_bound())?;
 
-        let end_bound = match range.end_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+            let end_bound = encode_bound::<KC>(txn, range.end_bound())?;
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRange::new(cursor, start_bound, end_bound))
+            RwCursor::new(txn, self.dbi).map(|cursor| RwRange::new(cursor, start_bound, end_bound))
+        })
     }
 
     /// Return a reversed lexicographically ordered iterator of a range of key-value
@@ -1309,38 +1426,21 @@ impl<KC, DC, C> Database<KC, DC, C> {
         &self,
         txn: &'txn RoTxn,
         range: &'a R,
-    ) -> Result<RoRevRange<'txn, KC, DC>>
+    ) -> Result<RoRevRange<'txn, KC, DC, C>>
     where
         KC: BytesEncode<'a>,
         R: RangeBounds<KC::EItem>,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let start_bound = match range.start_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+        self.with_context(txn.handle(), "rev_range", move || {
+            let start_bound = encode_bound::<KC>(txn, range.start_bound())?;
 
-        let end_bound = match range.end_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+            let end_bound = encode_bound::<KC>(txn, range.end_bound())?;
 
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRevRange::new(cursor, start_bound, end_bound))
+            RoCursor::new(txn, self.dbi)
+                .map(|cursor| RoRevRange::new(cursor, start_bound, end_bound))
+        })
     }
 
     /// Return a mutable reversed lexicographically ordered iterator of a range of
@@ -1400,38 +1500,21 @@ impl<KC, DC, C> Database<KC, DC, C> {
         &self,
         txn: &'txn mut RwTxn,
         range: &'a R,
-    ) -> Result<RwRevRange<'txn, KC, DC>>
+    ) -> Result<RwRevRange<'txn, KC, DC, C>>
     where
         KC: BytesEncode<'a>,
         R: RangeBounds<KC::EItem>,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let start_bound = match range.start_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+        self.with_context(txn.handle(), "rev_range_mut", move || {
+            let start_bound = encode_bound::<KC>(txn, range.start_bound())?;
 
-        let end_bound = match range.end_bound() {
-            Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Included(bytes.into_owned())
-            }
-            Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
-                Bound::Excluded(bytes.into_owned())
-            }
-            Bound::Unbounded => Bound::Unbounded,
-        };
+            let end_bound = encode_bound::<KC>(txn, range.end_bound())?;
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRevRange::new(cursor, start_bound, end_bound))
+            RwCursor::new(txn, self.dbi)
+                .map(|cursor| RwRevRange::new(cursor, start_bound, end_bound))
+        })
     }
 
     /// Return a lexicographically ordered iterator of all key-value pairs
@@ -1484,11 +1567,12 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         C: LexicographicComparator,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
-        let prefix_bytes = prefix_bytes.into_owned();
-        RoCursor::new(txn, self.dbi).map(|cursor| RoPrefix::new(cursor, prefix_bytes))
+        self.with_key_context(txn.handle(), "prefix_iter", prefix, move || {
+            let prefix_bytes = encode_owned::<KC>(txn, prefix)?;
+            RoCursor::new(txn, self.dbi).map(|cursor| RoPrefix::new(cursor, prefix_bytes))
+        })
     }
 
     /// Return a mutable lexicographically ordered iterator of all key-value pairs
@@ -1554,11 +1638,109 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         C: LexicographicComparator,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
-        let prefix_bytes = prefix_bytes.into_owned();
-        RwCursor::new(txn, self.dbi).map(|cursor| RwPrefix::new(cursor, prefix_bytes))
+        self.with_key_context(txn.handle(), "prefix_iter_mut", prefix, move || {
+            let prefix_bytes = encode_owned::<KC>(txn, prefix)?;
+            RwCursor::new(txn, self.dbi).map(|cursor| RwPrefix::new(cursor, prefix_bytes))
+        })
+    }
+
+    /// Return an iterator of all key-value pairs in this database that starts with the
+    /// given prefix, ordered by a comparator that isn't necessarily lexicographic.
+    ///
+    /// The iterator seeks on the prefix and stops on the first key that doesn't start
+    /// with its bytes, the [`ContiguousPrefixComparator`] must therefore keep the keys
+    /// starting with a prefix next to each other and after the prefix itself.
+    ///
+    /// ```
+    /// # use heed::EnvOpenOptions;
+    /// use std::cmp::Ordering;
+    ///
+    /// use heed::types::*;
+    /// use heed::{Comparator, ContiguousPrefixComparator};
+    ///
+    /// /// Sorts the keys by their first byte in descending order,
+    /// /// then by their following bytes in ascending order.
+    /// enum FirstByteDescending {}
+    ///
+    /// impl Comparator for FirstByteDescending {
+    ///     fn compare(a: &[u8], b: &[u8]) -> Ordering {
+    ///         match (a.split_first(), b.split_first()) {
+    ///             (Some((x, a)), Some((y, b))) => y.cmp(x).then_with(|| a.cmp(b)),
+    ///             _ => a.len().cmp(&b.len()),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// impl ContiguousPrefixComparator for FirstByteDescending {}
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let env = EnvOpenOptions::new()
+    /// #     .map_size(10 * 1024 * 1024) // 10MB
+    /// #     .max_dbs(3000)
+    /// #     .open(dir.path())?;
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env
+    ///     .database_options()
+    ///     .types::<Str, Unit>()
+    ///     .key_comparator::<FirstByteDescending>()
+    ///     .name("first-byte-descending")
+    ///     .create(&mut wtxn)?;
+    ///
+    /// db.put(&mut wtxn, "a", &())?;
+    /// db.put(&mut wtxn, "bb", &())?;
+    /// db.put(&mut wtxn, "b", &())?;
+    /// db.put(&mut wtxn, "ba", &())?;
+    /// db.put(&mut wtxn, "c", &())?;
+    ///
+    /// let mut iter = db.contiguous_prefix_iter(&wtxn, "b")?;
+    /// assert_eq!(iter.next().transpose()?, Some(("b", ())));
+    /// assert_eq!(iter.next().transpose()?, Some(("ba", ())));
+    /// assert_eq!(iter.next().transpose()?, Some(("bb", ())));
+    /// assert_eq!(iter.next().transpose()?, None);
+    ///
+    /// drop(iter);
+    /// wtxn.commit()?;
+    /// # Ok(()) }
+    /// ```
+    pub fn contiguous_prefix_iter<'a, 'txn>(
+        &self,
+        txn: &'txn RoTxn,
+        prefix: &'a KC::EItem,
+    ) -> Result<RoContiguousPrefix<'txn, KC, DC, C>>
+    where
+        KC: BytesEncode<'a>,
+        C: ContiguousPrefixComparator,
+    {
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "contiguous_prefix_iter", prefix, move || {
+            let prefix_bytes = encode_owned::<KC>(txn, prefix)?;
+            RoCursor::new(txn, self.dbi).map(|cursor| RoContiguousPrefix::new(cursor, prefix_bytes))
+        })
+    }
+
+    /// Return a mutable iterator of all key-value pairs in this database that starts with
+    /// the given prefix, ordered by a comparator that isn't necessarily lexicographic.
+    ///
+    /// For more info, see [`Database::contiguous_prefix_iter`].
+    pub fn contiguous_prefix_iter_mut<'a, 'txn>(
+        &self,
+        txn: &'txn mut RwTxn,
+        prefix: &'a KC::EItem,
+    ) -> Result<RwContiguousPrefix<'txn, KC, DC, C>>
+    where
+        KC: BytesEncode<'a>,
+        C: ContiguousPrefixComparator,
+    {
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "contiguous_prefix_iter_mut", prefix, move || {
+            let prefix_bytes = encode_owned::<KC>(txn, prefix)?;
+            RwCursor::new(txn, self.dbi).map(|cursor| RwContiguousPrefix::new(cursor, prefix_bytes))
+        })
     }
 
     /// Return a reversed lexicographically ordered iterator of all key-value pairs
@@ -1611,11 +1793,12 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         C: LexicographicComparator,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
-        let prefix_bytes = prefix_bytes.into_owned();
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRevPrefix::new(cursor, prefix_bytes))
+        self.with_key_context(txn.handle(), "rev_prefix_iter", prefix, move || {
+            let prefix_bytes = encode_owned::<KC>(txn, prefix)?;
+            RoCursor::new(txn, self.dbi).map(|cursor| RoRevPrefix::new(cursor, prefix_bytes))
+        })
     }
 
     /// Return a mutable reversed lexicographically ordered iterator of all key-value pairs
@@ -1681,15 +1864,20 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         C: LexicographicComparator,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
-        let prefix_bytes = prefix_bytes.into_owned();
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRevPrefix::new(cursor, prefix_bytes))
+        self.with_key_context(txn.handle(), "rev_prefix_iter_mut", prefix, move || {
+            let prefix_bytes = encode_owned::<KC>(txn, prefix)?;
+            RwCursor::new(txn, self.dbi).map(|cursor| RwRevPrefix::new(cursor, prefix_bytes))
+        })
     }
 
     /// Insert a key-value pair in this database. The entry is written with no specific flag.
     ///
+    /// When the data codec knows the [encoded size](BytesEncode::encoded_size) of the value
+    /// and the database isn't `DUP_SORT`, the value is directly encoded into the LMDB
+    /// reserved space, avoiding an intermediate copy.
+    ///
     /// ```
     /// # use std::fs;
     /// # use std::path::Path;
@@ -1722,46 +1910,179 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// wtxn.commit()?;
     /// # Ok(()) }
     /// ```
-    pub fn put<'a>(&self, txn: &RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    pub fn put<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
     where
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        assert_eq_env_db_txn!(self, txn);
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "put", key, move || {
+            txn.with_scratch(|buffer| {
+                KC::encode_into(key, buffer).map_err(Error::Encoding)?;
+
+                // When the codec knows the size of the value we ask LMDB to reserve the space
+                // and serialize directly into it, LMDB doesn't support it for DUP_SORT databases.
+                if let Some(data_size) = DC::encoded_size(data) {
+                    if !self.is_dup_sort(txn)? {
+                        return self.put_encoded_reserved(txn, buffer, data, data_size);
+                    }
+                }
+
+                let key_len = buffer.len();
+                DC::encode_into(data, buffer).map_err(Error::Encoding)?;
+                let (key_bytes, data_bytes) = buffer.split_at(key_len);
+
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+                let mut data_val = unsafe { crate::into_val(data_bytes) };
+                let flags = ffi::MDB_NOOVERWRITE;
+
+                unsafe {
+                    mdb_result(ffi::mdb_put(
+                        txn.txn.txn,
+                        self.dbi,
+                        &mut key_val,
+                        &mut data_val,
+                        flags,
+                    ))?
+                }
+
+                Ok(())
+            })
+        })
+    }
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+    /// Attaches the name of this database and the operation to the error of the operation.
+    fn with_context<T>(
+        &self,
+        txn: TxnHandle,
+        operation: &'static str,
+        f: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        self.run_checked(txn, f).map_err(|e| {
+            if txn.env.error_context() {
+                e.with_context(txn.env.dbi_name(self.dbi), operation, None)
+            } else {
+                e
+            }
+        })
+    }
 
-        let mut key_val = unsafe { crate::into_val(&key_bytes) };
-        let mut data_val = unsafe { crate::into_val(&data_bytes) };
-        let flags = ffi::MDB_NOOVERWRITE;
+    /// Attaches the name of this database, the operation and the encoded key to the error
+    /// of the operation, the key is only encoded again when an error occurs.
+    fn with_key_context<'a, T>(
+        &self,
+        txn: TxnHandle,
+        operation: &'static str,
+        key: &'a KC::EItem,
+        f: impl FnOnce() -> Result<T>,
+    ) -> Result<T>
+    where
+        KC: BytesEncode<'a>,
+    {
+        self.run_checked(txn, f).map_err(|e| {
+            if txn.env.error_context() {
+                let key = KC::bytes_encode(key).ok();
+                e.with_context(txn.env.dbi_name(self.dbi), operation, key.as_deref())
+            } else {
+                e
+            }
+        })
+    }
 
-        unsafe {
-            mdb_result(ffi::mdb_put(txn.txn.txn, self.dbi, &mut key_val, &mut data_val, flags))?
+    /// Runs the operation unless the transaction is poisoned, and reports
+    /// the panics of the custom comparator that happened during it.
+    ///
+    /// The default comparator can't panic, its operations still run on a poisoned
+    /// transaction and only report the panics caught while they ran.
+    fn run_checked<T>(&self, txn: TxnHandle, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if !self.custom_comparator {
+            let result = f();
+            txn.report_comparator_panic()?;
+            return result;
         }
+        txn.check_comparator_panic()?;
+        let result = f();
+        txn.check_comparator_panic()?;
+        result
+    }
 
-        Ok(())
+    /// Returns `true` if this database has been created with the `DUP_SORT` flag.
+    fn is_dup_sort(&self, txn: &RoTxn) -> Result<bool> {
+        let mut flags = 0;
+        unsafe { mdb_result(ffi::mdb_dbi_flags(txn.txn, self.dbi, &mut flags))? };
+        Ok(DatabaseFlags::from_bits_truncate(flags).contains(DatabaseFlags::DUP_SORT))
     }
 
-    pub fn update<'a>(&self, txn: &RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    /// Reserves `data_size` bytes under the already encoded key and encodes the data into it.
+    ///
+    /// If the codec fails to encode the data the entry is removed, the key must not exist.
+    fn put_encoded_reserved<'a>(
+        &self,
+        txn: &RwTxn,
+        key_bytes: &[u8],
+        data: &'a DC::EItem,
+        data_size: usize,
+    ) -> Result<()>
     where
-        KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
-
-        let mut key_val = unsafe { crate::into_val(&key_bytes) };
-        let mut data_val = unsafe { crate::into_val(&data_bytes) };
-        let flags = 0;
+        let mut key_val = unsafe { crate::into_val(key_bytes) };
+        let mut reserved = ffi::reserve_size_val(data_size);
+        let flags = ffi::MDB_NOOVERWRITE | ffi::MDB_RESERVE;
 
         unsafe {
-            mdb_result(ffi::mdb_put(txn.txn.txn, self.dbi, &mut key_val, &mut data_val, flags))?
+            mdb_result(ffi::mdb_put(txn.txn.txn, self.dbi, &mut key_val, &mut reserved, flags))?
         }
+        // The entry may have been reserved at the wrong place, it is left empty.
+        txn.handle().check_comparator_panic()?;
+
+        let output = unsafe {
+            std::slice::from_raw_parts_mut(reserved.mv_data as *mut u8, reserved.mv_size)
+        };
+        match DC::encode_to_slice(data, output) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+                unsafe {
+                    mdb_result(ffi::mdb_del(txn.txn.txn, self.dbi, &mut key_val, ptr::null_mut()))?
+                }
+                Err(Error::Encoding(e))
+            }
+        }
+    }
 
-        Ok(())
+    pub fn update<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>,
+    {
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "update", key, move || {
+            txn.with_scratch(|buffer| {
+                KC::encode_into(key, buffer).map_err(Error::Encoding)?;
+                let key_len = buffer.len();
+                DC::encode_into(data, buffer).map_err(Error::Encoding)?;
+                let (key_bytes, data_bytes) = buffer.split_at(key_len);
+
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+                let mut data_val = unsafe { crate::into_val(data_bytes) };
+                let flags = 0;
+
+                unsafe {
+                    mdb_result(ffi::mdb_put(
+                        txn.txn.txn,
+                        self.dbi,
+                        &mut key_val,
+                        &mut data_val,
+                        flags,
+                    ))?
+                }
+
+                Ok(())
+            })
+        })
     }
 
     /// Insert a key-value pair where the value can directly be written to disk.
@@ -1800,7 +2121,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// ```
     pub fn put_reserved<'a, F>(
         &self,
-        txn: &RwTxn,
+        txn: &mut RwTxn,
         key: &'a KC::EItem,
         data_size: usize,
         mut write_func: F,
@@ -1809,24 +2130,34 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         F: FnMut(&mut ReservedSpace) -> io::Result<()>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let mut key_val = unsafe { crate::into_val(&key_bytes) };
-        let mut reserved = ffi::reserve_size_val(data_size);
-        let flags = ffi::MDB_RESERVE;
-
-        unsafe {
-            mdb_result(ffi::mdb_put(txn.txn.txn, self.dbi, &mut key_val, &mut reserved, flags))?
-        }
-
-        let mut reserved = unsafe { ReservedSpace::from_val(reserved) };
-        (write_func)(&mut reserved)?;
-        if reserved.remaining() == 0 {
-            Ok(())
-        } else {
-            Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "put_reserved", key, move || {
+            txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+                let mut reserved = ffi::reserve_size_val(data_size);
+                let flags = ffi::MDB_RESERVE;
+
+                unsafe {
+                    mdb_result(ffi::mdb_put(
+                        txn.txn.txn,
+                        self.dbi,
+                        &mut key_val,
+                        &mut reserved,
+                        flags,
+                    ))?
+                }
+
+                let mut reserved = unsafe { ReservedSpace::from_val(reserved) };
+                (write_func)(&mut reserved)?;
+                if reserved.remaining() == 0 {
+                    Ok(())
+                } else {
+                    Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+                }
+            })
+        })
     }
 
     /// Insert a key-value pair in this database. The entry is written with the specified flags.
@@ -1888,7 +2219,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// ```
     pub fn put_with_flags<'a>(
         &self,
-        txn: &RwTxn,
+        txn: &mut RwTxn,
         flags: PutFlags,
         key: &'a KC::EItem,
         data: &'a DC::EItem,
@@ -1897,20 +2228,32 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
-
-        let mut key_val = unsafe { crate::into_val(&key_bytes) };
-        let mut data_val = unsafe { crate::into_val(&data_bytes) };
-        let flags = flags.bits();
-
-        unsafe {
-            mdb_result(ffi::mdb_put(txn.txn.txn, self.dbi, &mut key_val, &mut data_val, flags))?
-        }
-
-        Ok(())
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "put_with_flags", key, move || {
+            txn.with_scratch(|buffer| {
+                KC::encode_into(key, buffer).map_err(Error::Encoding)?;
+                let key_len = buffer.len();
+                DC::encode_into(data, buffer).map_err(Error::Encoding)?;
+                let (key_bytes, data_bytes) = buffer.split_at(key_len);
+
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+                let mut data_val = unsafe { crate::into_val(data_bytes) };
+                let flags = flags.bits();
+
+                unsafe {
+                    mdb_result(ffi::mdb_put(
+                        txn.txn.txn,
+                        self.dbi,
+                        &mut key_val,
+                        &mut data_val,
+                        flags,
+                    ))?
+                }
+
+                Ok(())
+            })
+        })
     }
 
     /// Deletes an entry or every duplicate data items of a key
@@ -1955,24 +2298,28 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// wtxn.commit()?;
     /// # Ok(()) }
     /// ```
-    pub fn delete<'a>(&self, txn: &RwTxn, key: &'a KC::EItem) -> Result<bool>
+    pub fn delete<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem) -> Result<bool>
     where
         KC: BytesEncode<'a>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let mut key_val = unsafe { crate::into_val(&key_bytes) };
-
-        let result = unsafe {
-            mdb_result(ffi::mdb_del(txn.txn.txn, self.dbi, &mut key_val, ptr::null_mut()))
-        };
-
-        match result {
-            Ok(()) => Ok(true),
-            Err(e) if e.not_found() => Ok(false),
-            Err(e) => Err(e.into()),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "delete", key, move || {
+            txn.with_scratch(|key_bytes| -> Result<_> {
+                KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+
+                let result = unsafe {
+                    mdb_result(ffi::mdb_del(txn.txn.txn, self.dbi, &mut key_val, ptr::null_mut()))
+                };
+
+                match result {
+                    Ok(()) => Ok(true),
+                    Err(e) if e.not_found() => Ok(false),
+                    Err(e) => Err(e.into()),
+                }
+            })
+        })
     }
 
     /// Deletes a single key-value pair in this database.
@@ -2043,21 +2390,28 @@ impl<KC, DC, C> Database<KC, DC, C> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
-        let mut key_val = unsafe { crate::into_val(&key_bytes) };
-        let mut data_val = unsafe { crate::into_val(&data_bytes) };
-
-        let result =
-            unsafe { mdb_result(ffi::mdb_del(txn.txn.txn, self.dbi, &mut key_val, &mut data_val)) };
-
-        match result {
-            Ok(()) => Ok(true),
-            Err(e) if e.not_found() => Ok(false),
-            Err(e) => Err(e.into()),
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_key_context(txn.handle(), "delete_one_duplicate", key, move || {
+            txn.with_scratch(|buffer| {
+                KC::encode_into(key, buffer).map_err(Error::Encoding)?;
+                let key_len = buffer.len();
+                DC::encode_into(data, buffer).map_err(Error::Encoding)?;
+                let (key_bytes, data_bytes) = buffer.split_at(key_len);
+                let mut key_val = unsafe { crate::into_val(key_bytes) };
+                let mut data_val = unsafe { crate::into_val(data_bytes) };
+
+                let result = unsafe {
+                    mdb_result(ffi::mdb_del(txn.txn.txn, self.dbi, &mut key_val, &mut data_val))
+                };
+
+                match result {
+                    Ok(()) => Ok(true),
+                    Err(e) if e.not_found() => Ok(false),
+                    Err(e) => Err(e.into()),
+                }
+            })
+        })
     }
 
     /// Deletes a range of key-value pairs in this database.
@@ -2111,22 +2465,25 @@ impl<KC, DC, C> Database<KC, DC, C> {
     pub fn delete_range<'a, 'txn, R>(&self, txn: &'txn mut RwTxn, range: &'a R) -> Result<usize>
     where
         KC: BytesEncode<'a> + BytesDecode<'txn>,
+        C: Comparator,
         R: RangeBounds<KC::EItem>,
     {
-        assert_eq_env_db_txn!(self, txn);
-
-        let mut count = 0;
-        let mut iter = self.remap_data_type::<DecodeIgnore>().range_mut(txn, range)?;
-
-        while iter.next().is_some() {
-            // safety: We do not keep any reference from the database while using `del_current`.
-            //         The user can't keep any reference inside of the database as we ask for a
-            //         mutable reference to the `txn`.
-            unsafe { iter.del_current()? };
-            count += 1;
-        }
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_context(txn.handle(), "delete_range", move || {
+            let mut count = 0;
+            let mut iter = self.remap_data_type::<DecodeIgnore>().range_mut(txn, range)?;
+
+            while iter.next().is_some() {
+                // safety: We do not keep any reference from the database while using `del_current`.
+                //         The user can't keep any reference inside of the database as we ask for a
+                //         mutable reference to the `txn`.
+                unsafe { iter.del_current()? };
+                count += 1;
+            }
 
-        Ok(count)
+            Ok(count)
+        })
     }
 
     /// Deletes all key/value pairs in this database.
@@ -2169,17 +2526,50 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// wtxn.commit()?;
     /// # Ok(()) }
     /// ```
-    pub fn clear(&self, txn: &RwTxn) -> Result<()> {
-        assert_eq_env_db_txn!(self, txn);
+    pub fn clear(&self, txn: &mut RwTxn) -> Result<()> {
+        ensure_same_env_db_txn!(self, txn);
 
-        unsafe { mdb_result(ffi::mdb_drop(txn.txn.txn, self.dbi, 0)).map_err(Into::into) }
+        self.with_context(txn.handle(), "clear", move || unsafe {
+            mdb_result(ffi::mdb_drop(txn.txn.txn, self.dbi, 0)).map_err(Into::into)
+        })
     }
 
     /// Drops this database from its environment.
-    pub fn drop(self, txn: &RwTxn) -> Result<()> {
-        assert_eq_env_db_txn!(self, txn);
+    ///
+    /// The copies of this database handle can no longer be used,
+    /// they return an [`MdbError::BadDbi`] error.
+    ///
+    /// Its [type metadata](DatabaseOpenOptions::persist_types) is removed as well,
+    /// a database with the same name can then be created with other types.
+    pub fn drop(self, txn: &mut RwTxn) -> Result<()> {
+        ensure_same_env_db_txn!(self, txn);
+
+        self.with_context(txn.handle(), "drop", move || {
+            let env = txn.env();
+            let name = env.dbi_name(self.dbi);
+            unsafe { mdb_result(ffi::mdb_drop(txn.txn.txn, self.dbi, 1))? };
+            txn.record_dropped_dbi(self.dbi);
+            metadata::remove(env, txn, name.as_deref())
+        })
+    }
+
+    /// Returns a view of this database restricted to the keys starting with `prefix`.
+    ///
+    /// The prefix is transparently prepended to and stripped from the keys,
+    /// see [`Namespace`] for more information.
+    pub fn namespace(&self, prefix: &[u8]) -> Namespace<KC, DC, C> {
+        Namespace::new(*self, prefix.to_vec())
+    }
 
-        unsafe { mdb_result(ffi::mdb_drop(txn.txn.txn, self.dbi, 1)).map_err(Into::into) }
+    /// Returns a view of this database that gives `ctx` to every encoding and decoding.
+    ///
+    /// The codecs must implement [`BytesEncodeWith`](crate::BytesEncodeWith) and
+    /// [`BytesDecodeWith`](crate::BytesDecodeWith), see [`ContextDatabase`] for more information.
+    pub fn with_codec_context<'c, Ctx: ?Sized>(
+        &self,
+        ctx: &'c Ctx,
+    ) -> ContextDatabase<'c, KC, DC, Ctx, C> {
+        ContextDatabase::new(*self, ctx)
     }
 
     /// Change the codec types of this uniform database, specifying the codecs.
@@ -2223,7 +2613,13 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// # Ok(()) }
     /// ```
     pub fn remap_types<KC2, DC2>(&self) -> Database<KC2, DC2, C> {
-        Database::new(self.env_ident, self.dbi)
+        Database {
+            env_ident: self.env_ident,
+            dbi: self.dbi,
+            generation: self.generation,
+            custom_comparator: self.custom_comparator,
+            marker: std::marker::PhantomData,
+        }
     }
 
     /// Change the key codec type of this uniform database, specifying the new codec.
@@ -2244,7 +2640,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
 impl<KC, DC, C> Clone for Database<KC, DC, C> {
     fn clone(&self) -> Database<KC, DC, C> {
-        Database { env_ident: self.env_ident, dbi: self.dbi, marker: marker::PhantomData }
+        *self
     }
 }
 
@@ -2259,3 +2655,135 @@ impl<KC, DC, C> fmt::Debug for Database<KC, DC, C> {
             .finish()
     }
 }
+
+/// Encodes a range bound into an owned buffer that the range iterators can keep.
+fn encode_bound<'a, KC>(txn: &RoTxn, bound: Bound<&'a KC::EItem>) -> Result<Bound<Vec<u8>>>
+where
+    KC: BytesEncode<'a>,
+{
+    match bound {
+        Bound::Included(bound) => encode_owned::<KC>(txn, bound).map(Bound::Included),
+        Bound::Excluded(bound) => encode_owned::<KC>(txn, bound).map(Bound::Excluded),
+        Bound::Unbounded => Ok(Bound::Unbounded),
+    }
+}
+
+/// Encodes an item into the scratch buffer of the transaction and copies it
+/// into an owned buffer of the exact size, for the iterators to keep it.
+fn encode_owned<'a, KC>(txn: &RoTxn, item: &'a KC::EItem) -> Result<Vec<u8>>
+where
+    KC: BytesEncode<'a>,
+{
+    txn.with_scratch(|buffer| {
+        KC::encode_into(item, buffer).map_err(Error::Encoding)?;
+        Ok(buffer.to_vec())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn put_values_of_known_size_into_reserved_space() {
+        use std::borrow::Cow;
+
+        use crate::byteorder::BigEndian;
+        use crate::types::{ByteSlice, Str, U64};
+        use crate::{BoxedError, MdbError};
+
+        /// Announces the size of the value but fails to encode it into the reserved space.
+        enum Failing {}
+
+        impl<'a> BytesEncode<'a> for Failing {
+            type EItem = str;
+
+            fn bytes_encode(item: &'a str) -> std::result::Result<Cow<'a, [u8]>, BoxedError> {
+                Ok(Cow::Borrowed(item.as_bytes()))
+            }
+
+            fn encoded_size(item: &'a str) -> Option<usize> {
+                Some(item.len())
+            }
+
+            fn encode_to_slice(
+                item: &'a str,
+                output: &mut [u8],
+            ) -> std::result::Result<(), BoxedError> {
+                output[..1].copy_from_slice(&item.as_bytes()[..1]);
+                Err("failed in the middle of the encoding".into())
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, U64<BigEndian>>(&mut wtxn, None).unwrap();
+
+        db.put(&mut wtxn, "answer", &42).unwrap();
+        let raw = db.remap_data_type::<ByteSlice>().get(&wtxn, "answer").unwrap();
+        assert_eq!(raw, Some(&42u64.to_be_bytes()[..]));
+        assert!(db.put(&mut wtxn, "answer", &43).is_err());
+        assert_eq!(db.get(&wtxn, "answer").unwrap(), Some(42));
+
+        // The reserved entry is deleted when the value fails to be encoded into it.
+        let failing = db.remap_data_type::<Failing>();
+        let result = failing.put(&mut wtxn, "question", "unknown");
+        assert!(matches!(result, Err(Error::Encoding(_))));
+        assert_eq!(db.get(&wtxn, "question").unwrap(), None);
+        assert_eq!(db.len(&wtxn).unwrap(), 1);
+
+        // An existing key is never reserved, its value is kept as it was.
+        let result = failing.put(&mut wtxn, "answer", "eight!");
+        assert!(matches!(result, Err(Error::Mdb(MdbError::KeyExist))));
+        assert_eq!(db.get(&wtxn, "answer").unwrap(), Some(42));
+        assert_eq!(db.len(&wtxn).unwrap(), 1);
+
+        // The rollback doesn't prevent the transaction from being used and committed.
+        db.put(&mut wtxn, "question", &0).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        let entries: Vec<_> = db.iter(&rtxn).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(entries, [("answer", 42), ("question", 0)]);
+    }
+
+    #[test]
+    fn database_errors_have_context() {
+        use crate::byteorder::BigEndian;
+        use crate::types::{Str, U32};
+
+        // The context is opt-in, the errors can be matched directly by default.
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, None).unwrap();
+        db.put(&mut wtxn, "hello", "hi").unwrap();
+        let error = db.put(&mut wtxn, "hello", "hi").unwrap_err();
+        assert!(matches!(error, Error::Mdb(MdbError::KeyExist)));
+        assert!(error.context().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).error_context(true).open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("greetings")).unwrap();
+        db.put(&mut wtxn, "hello", "hi").unwrap();
+
+        let error = db.remap_data_type::<U32<BigEndian>>().get(&wtxn, "hello").unwrap_err();
+        assert!(matches!(error.without_context(), Error::Decoding(_)));
+        let context = error.context().unwrap();
+        assert_eq!(context.database(), Some("greetings"));
+        assert_eq!(context.operation(), "get");
+        assert_eq!(context.key(), Some(&b"hello"[..]));
+        assert!(error
+            .to_string()
+            .ends_with(r#"(during get in database "greetings" with key b"hello")"#));
+
+        let long_key = "a".repeat(100);
+        let error = db.put(&mut wtxn, &long_key, "").and(db.put(&mut wtxn, &long_key, ""));
+        let error = error.unwrap_err();
+        assert!(matches!(error.without_context(), Error::Mdb(MdbError::KeyExist)));
+        assert_eq!(error.context().unwrap().key().unwrap().len(), ErrorContext::MAX_KEY_LEN);
+        assert!(error.to_string().ends_with(r#"aaa...")"#));
+    }
+}
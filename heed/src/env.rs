@@ -1,4 +1,5 @@
-use std::any::TypeId;
+use std::any::{Any, TypeId};
+use std::cell::Cell;
 use std::cmp::Ordering;
 use std::collections::hash_map::{Entry, HashMap};
 use std::ffi::{c_void, CString};
@@ -10,6 +11,7 @@ use std::os::unix::{
     io::{AsRawFd, BorrowedFd, RawFd},
 };
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicU32};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 #[cfg(windows)]
@@ -17,18 +19,24 @@ use std::{
     ffi::OsStr,
     os::windows::io::{AsRawHandle, BorrowedHandle, RawHandle},
 };
-use std::{fmt, io, mem, ptr, sync};
+use std::{fmt, io, mem, panic, ptr, result, sync, thread};
 
-use heed_traits::{Comparator, LexicographicComparator};
+use heed_traits::{Comparator, ContiguousPrefixComparator, LexicographicComparator};
 use once_cell::sync::Lazy;
 use synchronoise::event::SignalEvent;
 
+use crate::check::{self, Anomaly, CheckReport, DatabaseReport};
 use crate::cursor::MoveOperation;
 use crate::database::DatabaseOpenOptions;
 use crate::mdb::error::mdb_result;
 use crate::mdb::ffi;
 use crate::mdb::lmdb_flags::AllDatabaseFlags;
-use crate::{Database, EnvFlags, Error, Result, RoCursor, RoTxn, RwTxn, Unspecified};
+use crate::txn::DroppedDbi;
+use crate::types::{ByteSlice, Checksum, ChecksumMismatch, Checksummed};
+use crate::{
+    ensure_same_env_db_txn, ensure_same_env_txn, Database, DatabaseFlags, EnvFlags, Error,
+    MdbError, Result, RoCursor, RoTxn, RwTxn, Unspecified,
+};
 
 /// The list of opened environments, the value is an optional environment, it is None
 /// when someone asks to close the environment, closing is a two-phase step, to make sure
@@ -73,12 +81,12 @@ impl OsStrExtLmdb for OsStr {
 }
 
 #[cfg(unix)]
-fn get_file_fd(file: &File) -> RawFd {
+fn get_file_fd<F: AsRawFd>(file: &F) -> RawFd {
     file.as_raw_fd()
 }
 
 #[cfg(windows)]
-fn get_file_fd(file: &File) -> RawHandle {
+fn get_file_fd<F: AsRawHandle>(file: &F) -> RawHandle {
     file.as_raw_handle()
 }
 
@@ -106,6 +114,10 @@ pub struct EnvOpenOptions {
     max_readers: Option<u32>,
     max_dbs: Option<u32>,
     flags: EnvFlags,
+    #[cfg_attr(feature = "serde", serde(default))]
+    env_mismatch_errors: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    error_context: bool,
 }
 
 impl Default for EnvOpenOptions {
@@ -122,6 +134,8 @@ impl EnvOpenOptions {
             max_readers: None,
             max_dbs: None,
             flags: EnvFlags::empty(),
+            env_mismatch_errors: false,
+            error_context: false,
         }
     }
 
@@ -143,6 +157,59 @@ impl EnvOpenOptions {
         self
     }
 
+    /// Makes the operations return an [`Error::EnvMismatch`] instead of panicking when they are
+    /// given a database or an environment that doesn't belong to the environment of the transaction.
+    ///
+    /// ```
+    /// use heed::{EnvOpenOptions, Error};
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir1 = tempfile::tempdir()?;
+    /// let dir2 = tempfile::tempdir()?;
+    /// let env1 = EnvOpenOptions::new().open(dir1.path())?;
+    /// let env2 = EnvOpenOptions::new().env_mismatch_errors(true).open(dir2.path())?;
+    ///
+    /// let mut wtxn1 = env1.write_txn()?;
+    /// let db1 = env1.create_database::<Str, Str>(&mut wtxn1, None)?;
+    /// wtxn1.commit()?;
+    ///
+    /// let rtxn2 = env2.read_txn()?;
+    /// assert!(matches!(db1.get(&rtxn2, "hello"), Err(Error::EnvMismatch)));
+    /// # Ok(()) }
+    /// ```
+    pub fn env_mismatch_errors(&mut self, enabled: bool) -> &mut Self {
+        self.env_mismatch_errors = enabled;
+        self
+    }
+
+    /// Makes the [`Database`] operations of the environment wrap their errors in an
+    /// [`Error::WithContext`] that tells the database, the operation and the key involved.
+    ///
+    /// It is disabled by default so that the errors can be matched directly,
+    /// use [`Error::without_context`] to match on them once it is enabled.
+    ///
+    /// ```
+    /// use heed::{EnvOpenOptions, Error, MdbError};
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = EnvOpenOptions::new().error_context(true).open(dir.path())?;
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// let error = db.put(&mut wtxn, "hello", "world").unwrap_err();
+    /// assert!(matches!(error.without_context(), Error::Mdb(MdbError::KeyExist)));
+    /// assert_eq!(error.context().unwrap().key(), Some(&b"hello"[..]));
+    /// # Ok(()) }
+    /// ```
+    pub fn error_context(&mut self, enabled: bool) -> &mut Self {
+        self.error_context = enabled;
+        self
+    }
+
     /// Set one or [more LMDB flags](http://www.lmdb.tech/doc/group__mdb__env.html).
     /// ```
     /// use std::fs;
@@ -269,6 +336,11 @@ impl EnvOpenOptions {
                             let inner = EnvInner {
                                 env,
                                 dbi_open_mutex: sync::Mutex::default(),
+                                dbi_generations: dbi_generations(self.max_dbs),
+                                error_context: self.error_context,
+                                env_mismatch_errors: self.env_mismatch_errors,
+                                #[cfg(feature = "encryption")]
+                                encryption_keys: RwLock::default(),
                                 path: path.clone(),
                             };
                             let env = Env(Arc::new(inner));
@@ -303,17 +375,54 @@ pub struct Env(Arc<EnvInner>);
 
 impl fmt::Debug for Env {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let EnvInner { env: _, dbi_open_mutex: _, path } = self.0.as_ref();
+        let EnvInner {
+            env: _,
+            dbi_open_mutex: _,
+            dbi_generations: _,
+            error_context: _,
+            env_mismatch_errors: _,
+            #[cfg(feature = "encryption")]
+                encryption_keys: _,
+            path,
+        } = self.0.as_ref();
         f.debug_struct("Env").field("path", &path.display()).finish_non_exhaustive()
     }
 }
 
 struct EnvInner {
     env: *mut ffi::MDB_env,
-    dbi_open_mutex: sync::Mutex<HashMap<u32, (TypeId, TypeId, TypeId)>>,
+    dbi_open_mutex: sync::Mutex<HashMap<u32, DbiSlot>>,
+    /// The committed generation of every dbi, checked by every database operation.
+    dbi_generations: Box<[AtomicU32]>,
+    /// Whether the database operations attach their context to their errors.
+    error_context: bool,
+    /// Whether a database or an environment of another environment is an error or a panic.
+    env_mismatch_errors: bool,
+    /// The keys given as context to the `Encrypted` codecs of this environment.
+    #[cfg(feature = "encryption")]
+    encryption_keys: RwLock<Option<Arc<crate::types::EncryptionKeys>>>,
     path: PathBuf,
 }
 
+/// The state of a dbi opened in this environment.
+#[derive(Default)]
+struct DbiSlot {
+    /// The types the dbi was opened with, `None` if only opened untyped or dropped.
+    types: Option<(TypeId, TypeId, TypeId)>,
+    /// The name of the database, `None` for the unnamed one.
+    name: Option<String>,
+}
+
+/// Creates the generations of the dbis of an environment, LMDB can reuse the dbi of a
+/// dropped database for another one, the generation is bumped when the drop is committed
+/// so that the stale [`Database`] handles can be detected.
+///
+/// LMDB reserves two dbis for its own use in addition to the named databases.
+fn dbi_generations(max_dbs: Option<u32>) -> Box<[AtomicU32]> {
+    let len = max_dbs.unwrap_or(0) as usize + 2;
+    (0..len).map(|_| AtomicU32::new(0)).collect()
+}
+
 unsafe impl Send for EnvInner {}
 
 unsafe impl Sync for EnvInner {}
@@ -335,21 +444,56 @@ impl Drop for EnvInner {
     }
 }
 
+thread_local! {
+    /// The message of the last comparator panic caught on this thread and
+    /// not yet reported by the operation that triggered the comparison.
+    static COMPARATOR_PANIC: Cell<Option<String>> = const { Cell::new(None) };
+}
+
+/// Takes the message of the comparator panic caught on this thread, if any.
+pub(crate) fn take_comparator_panic() -> Option<String> {
+    COMPARATOR_PANIC.with(Cell::take)
+}
+
+/// Compares two keys with `C`, returning the message of the panic of the comparator, if any.
+pub(crate) fn catch_compare<C: Comparator>(a: &[u8], b: &[u8]) -> result::Result<Ordering, String> {
+    panic::catch_unwind(|| C::compare(a, b)).map_err(|payload| panic_message(payload.as_ref()))
+}
+
 /// An helping function that transforms the LMDB types into Rust types (`MDB_val` into slices)
 /// and vice versa, the Rust types into C types (`Ordering` into an integer).
+///
+/// Unwinding out of an `extern "C"` function aborts the process, the panics of the
+/// comparator are therefore caught and reported by the operation once LMDB returns.
+/// The keys are then compared bytewise, LMDB must never consider two different keys
+/// equal as it would overwrite or delete the entry of another key.
 extern "C" fn custom_key_cmp_wrapper<C: Comparator>(
     a: *const ffi::MDB_val,
     b: *const ffi::MDB_val,
 ) -> i32 {
     let a = unsafe { ffi::from_val(*a) };
     let b = unsafe { ffi::from_val(*b) };
-    match C::compare(a, b) {
+    let ordering = catch_compare::<C>(a, b).unwrap_or_else(|message| {
+        COMPARATOR_PANIC.with(|panic| panic.set(Some(message)));
+        a.cmp(b)
+    });
+    match ordering {
         Ordering::Less => -1,
         Ordering::Equal => 0,
         Ordering::Greater => 1,
     }
 }
 
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => String::from("Box<dyn Any>"),
+        },
+    }
+}
+
 /// A representation of LMDB's default comparator behavior.
 ///
 /// This enum is used to indicate the absence of a custom comparator for an LMDB
@@ -369,6 +513,8 @@ extern "C" fn custom_key_cmp_wrapper<C: Comparator>(
 /// behavior of LMDB with the user-defined logic.
 pub enum DefaultComparator {}
 
+impl ContiguousPrefixComparator for DefaultComparator {}
+
 impl LexicographicComparator for DefaultComparator {
     #[inline]
     fn compare_elem(a: u8, b: u8) -> Ordering {
@@ -523,6 +669,182 @@ impl Env {
         Ok(size)
     }
 
+    /// Verifies the integrity of every database of this environment, the unnamed one included.
+    ///
+    /// Every database is walked with a cursor, keys and duplicate data items are checked
+    /// to be ordered according to the database flags (e.g. `INTEGER_KEY`, `REVERSE_KEY`,
+    /// `DUP_SORT`) and the number of entries walked is cross-checked with the LMDB stats.
+    ///
+    /// The anomalies are reported in the returned [`CheckReport`] instead of being returned
+    /// as errors, an error is only returned when the check cannot be started.
+    ///
+    /// Databases using a custom [`Comparator`] must have been opened in this environment
+    /// before calling this method, otherwise LMDB compares their keys with the default
+    /// comparator and reports misordered keys.
+    ///
+    /// ```
+    /// use heed::EnvOpenOptions;
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = EnvOpenOptions::new().max_dbs(10).open(dir.path())?;
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, Some("greetings"))?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// wtxn.commit()?;
+    ///
+    /// let report = env.check()?;
+    /// assert!(report.is_ok());
+    /// assert_eq!(report.databases.len(), 2);
+    /// # Ok(()) }
+    /// ```
+    pub fn check(&self) -> Result<CheckReport> {
+        let rtxn = self.read_txn()?;
+        let main_dbi = self.raw_open_dbi::<DefaultComparator>(rtxn.txn, None, 0)?;
+
+        // we don’t want anyone to open a database while we’re walking them
+        let dbi_open = self.0.dbi_open_mutex.lock().unwrap();
+
+        let mut report = CheckReport::default();
+        report.databases.push(check::check_database(&rtxn, None, main_dbi));
+
+        // We’re going to iterate on the unnamed database to find the named ones
+        let mut cursor = RoCursor::new(&rtxn, main_dbi)?;
+        while let Some((key, _value)) = cursor.move_on_next(MoveOperation::NoDup)? {
+            let name = match std::str::from_utf8(key) {
+                Ok(name) if !name.contains('\0') => name.to_string(),
+                _ => continue,
+            };
+
+            match self.raw_open_dbi::<DefaultComparator>(rtxn.txn, Some(&name), 0) {
+                Ok(dbi) => {
+                    report.databases.push(check::check_database(&rtxn, Some(name), dbi));
+
+                    // if the db wasn’t already opened
+                    if !dbi_open.contains_key(&dbi) {
+                        unsafe { ffi::mdb_dbi_close(self.env_mut_ptr(), dbi) }
+                    }
+                }
+                // This is a plain entry of the unnamed database, not a named database.
+                Err(MdbError::Incompatible) => (),
+                Err(e) => report.databases.push(DatabaseReport {
+                    name: Some(name),
+                    flags: DatabaseFlags::empty(),
+                    entries: 0,
+                    stat_entries: 0,
+                    anomalies: vec![Anomaly::Mdb(e)],
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Verifies the checksum of every value of a database that uses the [`Checksummed`] codec.
+    ///
+    /// It returns the keys of the values whose checksum doesn't match along with
+    /// the mismatch, the values are not decoded by the inner codec.
+    ///
+    /// ```
+    /// # #[cfg(feature = "crc32c")] {
+    /// use heed::EnvOpenOptions;
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = EnvOpenOptions::new().open(dir.path())?;
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Checksummed<Str>>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// db.remap_data_type::<ByteSlice>().update(&mut wtxn, "corrupted", b"world\0\0\0\0")?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.read_txn()?;
+    /// let mismatches = env.verify_checksums(&rtxn, db)?;
+    /// assert_eq!(mismatches.len(), 1);
+    /// assert_eq!(mismatches[0].0, b"corrupted");
+    /// # Ok(()) }
+    /// # }
+    /// ```
+    ///
+    /// [`Checksummed`]: crate::types::Checksummed
+    pub fn verify_checksums<KC, C, H, CMP>(
+        &self,
+        rtxn: &RoTxn,
+        database: Database<KC, Checksummed<C, H>, CMP>,
+    ) -> Result<Vec<(Vec<u8>, ChecksumMismatch)>>
+    where
+        H: Checksum,
+    {
+        ensure_same_env_txn!(self, rtxn);
+        ensure_same_env_db_txn!(database, rtxn);
+
+        let mut mismatches = Vec::new();
+        for result in database.remap_types::<ByteSlice, ByteSlice>().iter(rtxn)? {
+            let (key, value) = result?;
+            if let Err(mismatch) = H::verify(value) {
+                mismatches.push((key.to_vec(), mismatch));
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Returns `true` if the database operations attach their context to their errors.
+    pub(crate) fn error_context(&self) -> bool {
+        self.0.error_context
+    }
+
+    /// Returns `true` if a mismatching environment is reported as an [`Error::EnvMismatch`].
+    pub(crate) fn env_mismatch_errors(&self) -> bool {
+        self.0.env_mismatch_errors
+    }
+
+    /// Installs the keys used by the [`Encrypted`] codecs of this environment
+    /// to encrypt and decrypt the values, replacing the previous ones.
+    ///
+    /// The keys are retrieved with [`RoTxn::encryption_keys`] and given to the codecs as
+    /// the context of a [`ContextDatabase`](crate::ContextDatabase). A failure to decrypt
+    /// a value is reported as an [`Error::Decoding`].
+    ///
+    /// ```
+    /// use heed::{EnvOpenOptions, IgnoreContext};
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = EnvOpenOptions::new().open(dir.path())?;
+    /// env.set_encryption_keys(EncryptionKeys::new(1, [42; 32]));
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<IgnoreContext<Str>, Encrypted<SerdeJson<String>>>(&mut wtxn, None)?;
+    /// let keys = wtxn.encryption_keys().unwrap();
+    /// let secrets = db.with_codec_context(&*keys);
+    /// secrets.put(&mut wtxn, "name", &String::from("kerollmops"))?;
+    /// assert_eq!(secrets.get(&wtxn, "name")?, Some(String::from("kerollmops")));
+    ///
+    /// env.set_encryption_keys(EncryptionKeys::new(2, [43; 32]));
+    /// let keys = wtxn.encryption_keys().unwrap();
+    /// let result = db.with_codec_context(&*keys).get(&wtxn, "name");
+    /// assert!(matches!(result, Err(heed::Error::Decoding(_))));
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// [`Encrypted`]: crate::types::Encrypted
+    #[cfg(feature = "encryption")]
+    pub fn set_encryption_keys(&self, keys: crate::types::EncryptionKeys) {
+        *self.0.encryption_keys.write().unwrap() = Some(Arc::new(keys));
+    }
+
+    /// Returns the keys installed with [`Env::set_encryption_keys`], if any.
+    #[cfg(feature = "encryption")]
+    pub fn encryption_keys(&self) -> Option<Arc<crate::types::EncryptionKeys>> {
+        self.0.encryption_keys.read().unwrap().clone()
+    }
+
     /// Options and flags which can be used to configure how a [`Database`] is opened.
     pub fn database_options(
         &self,
@@ -576,7 +898,7 @@ impl Env {
     /// these keys can only be read and not written.
     pub fn create_database<KC, DC>(
         &self,
-        wtxn: &RwTxn,
+        wtxn: &mut RwTxn,
         name: Option<&str>,
     ) -> Result<Database<KC, DC, DefaultComparator>>
     where
@@ -593,17 +915,23 @@ impl Env {
 
     pub(crate) fn raw_init_database<C: Comparator + 'static>(
         &self,
-        raw_txn: *mut ffi::MDB_txn,
+        txn: &RoTxn,
         name: Option<&str>,
         types: (TypeId, TypeId, TypeId),
         flags: AllDatabaseFlags,
-    ) -> Result<u32> {
+    ) -> Result<(u32, u32)> {
         let mut lock = self.0.dbi_open_mutex.lock().unwrap();
-        match self.raw_open_dbi::<C>(raw_txn, name, flags.bits()) {
+        match self.raw_open_dbi::<C>(txn.txn, name, flags.bits()) {
             Ok(dbi) => {
-                let old_types = lock.entry(dbi).or_insert(types);
-                if *old_types == types {
-                    Ok(dbi)
+                let slot = lock.entry(dbi).or_default();
+                slot.name = name.map(ToOwned::to_owned);
+                // A database dropped by this transaction can be recreated with other types.
+                let old_types = match txn.dropped_dbi_types(dbi, types) {
+                    Some(old_types) => old_types,
+                    None => *slot.types.get_or_insert(types),
+                };
+                if old_types == types {
+                    Ok((dbi, txn.dbi_generation(dbi)))
                 } else {
                     Err(Error::InvalidDatabaseTyping)
                 }
@@ -612,6 +940,44 @@ impl Env {
         }
     }
 
+    /// Opens or creates a database without registering nor checking its types.
+    pub(crate) fn raw_init_untyped_database<C: Comparator + 'static>(
+        &self,
+        txn: &RoTxn,
+        name: Option<&str>,
+        flags: AllDatabaseFlags,
+    ) -> Result<(u32, u32)> {
+        let mut lock = self.0.dbi_open_mutex.lock().unwrap();
+        let dbi = self.raw_open_dbi::<C>(txn.txn, name, flags.bits())?;
+        let slot = lock.entry(dbi).or_default();
+        slot.name = name.map(ToOwned::to_owned);
+        Ok((dbi, txn.dbi_generation(dbi)))
+    }
+
+    /// Returns the name of the database opened with this dbi, `None` for the unnamed one.
+    pub(crate) fn dbi_name(&self, dbi: u32) -> Option<String> {
+        let lock = self.0.dbi_open_mutex.lock().unwrap();
+        lock.get(&dbi).and_then(|slot| slot.name.clone())
+    }
+
+    /// Returns the generation of the dbi, bumped every time a drop of it is committed.
+    pub(crate) fn committed_dbi_generation(&self, dbi: u32) -> u32 {
+        let generation = self.0.dbi_generations.get(dbi as usize);
+        generation.map_or(0, |generation| generation.load(atomic::Ordering::Acquire))
+    }
+
+    /// Applies the drops of a committed transaction: forgets the types of the dropped
+    /// dbis, or registers the types they were recreated with, and invalidates the handles.
+    pub(crate) fn commit_dropped_dbis(&self, dropped: Vec<DroppedDbi>) {
+        let mut lock = self.0.dbi_open_mutex.lock().unwrap();
+        for DroppedDbi { dbi, generation, types } in dropped {
+            lock.entry(dbi).or_default().types = types;
+            if let Some(committed) = self.0.dbi_generations.get(dbi as usize) {
+                committed.store(generation, atomic::Ordering::Release);
+            }
+        }
+    }
+
     fn raw_open_dbi<C: Comparator + 'static>(
         &self,
         raw_txn: *mut ffi::MDB_txn,
@@ -701,6 +1067,64 @@ impl Env {
         Ok(file)
     }
 
+    /// Copy an LMDB environment into the specified writer, with compaction option.
+    ///
+    /// The environment is copied into a pipe by a background thread while the current
+    /// thread forwards everything it reads from the pipe into the writer. This function
+    /// may be used to stream a backup into a compressor or a socket.
+    ///
+    /// Returns the number of bytes written into the writer.
+    ///
+    /// ```
+    /// use heed::{CompactionOption, EnvOpenOptions};
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = EnvOpenOptions::new().open(dir.path())?;
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// wtxn.commit()?;
+    ///
+    /// let mut backup = Vec::new();
+    /// let written = env.copy_to_writer(&mut backup, CompactionOption::Enabled)?;
+    /// assert_eq!(written, backup.len() as u64);
+    /// # Ok(()) }
+    /// ```
+    pub fn copy_to_writer<W: io::Write>(
+        &self,
+        mut writer: W,
+        option: CompactionOption,
+    ) -> Result<u64> {
+        let (mut reader, pipe_writer) = io::pipe()?;
+
+        thread::scope(|s| {
+            let copy = s.spawn(move || {
+                // The pipe writer is dropped once the copy is done
+                // which closes the pipe and stops the forwarding loop.
+                unsafe { self.copy_to_fd(get_file_fd(&pipe_writer), option) }
+            });
+
+            let written =
+                io::copy(&mut reader, &mut writer).and_then(|n| writer.flush().map(|()| n));
+
+            // We must close the reading side before waiting for the copy to finish,
+            // the copy would otherwise be stuck on a full pipe if the writer failed.
+            drop(reader);
+            let copied = match copy.join() {
+                Ok(result) => result,
+                Err(payload) => panic::resume_unwind(payload),
+            };
+
+            match written {
+                Ok(written) => copied.map(|()| written),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
     /// Copy an LMDB environment to the specified file descriptor, with compaction option.
     ///
     /// This function may be used to make a backup of an existing environment.
@@ -835,12 +1259,12 @@ impl fmt::Debug for EnvClosingEvent {
 
 #[cfg(test)]
 mod tests {
-    use std::io::ErrorKind;
+    use std::io::{ErrorKind, Write};
     use std::time::Duration;
-    use std::{fs, thread};
+    use std::{fs, io, thread};
 
     use crate::types::*;
-    use crate::{env_closing_event, EnvOpenOptions, Error};
+    use crate::{env_closing_event, CompactionOption, EnvOpenOptions, Error, MdbError};
 
     #[test]
     fn close_env() {
@@ -885,6 +1309,85 @@ mod tests {
         assert!(env_closing_event(dir.path()).is_none());
     }
 
+    #[test]
+    fn copy_to_writer_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .max_dbs(10)
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("words")).unwrap();
+        for i in 0..1000 {
+            let key = format!("key-{i:04}");
+            db.put(&mut wtxn, &key, "a value that takes some room in the pages").unwrap();
+        }
+        wtxn.commit().unwrap();
+
+        for option in [CompactionOption::Enabled, CompactionOption::Disabled] {
+            let mut backup = Vec::new();
+            let written = env.copy_to_writer(&mut backup, option).unwrap();
+            assert_eq!(written, backup.len() as u64);
+
+            let backup_dir = tempfile::tempdir().unwrap();
+            fs::write(backup_dir.path().join("data.mdb"), &backup).unwrap();
+            let backup_env = EnvOpenOptions::new()
+                .map_size(10 * 1024 * 1024) // 10MB
+                .max_dbs(10)
+                .open(backup_dir.path())
+                .unwrap();
+
+            let rtxn = env.read_txn().unwrap();
+            let backup_rtxn = backup_env.read_txn().unwrap();
+            let backup_db =
+                backup_env.open_database::<Str, Str>(&backup_rtxn, Some("words")).unwrap().unwrap();
+
+            let expected: Vec<_> = db.iter(&rtxn).unwrap().collect::<Result<_, _>>().unwrap();
+            let copied: Vec<_> =
+                backup_db.iter(&backup_rtxn).unwrap().collect::<Result<_, _>>().unwrap();
+            assert_eq!(copied.len(), 1000);
+            assert_eq!(expected, copied);
+        }
+    }
+
+    #[test]
+    fn copy_to_failing_writer_is_err() {
+        struct FailingWriter(usize);
+
+        impl Write for FailingWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(io::Error::other("no space left"));
+                }
+                let len = buf.len().min(self.0);
+                self.0 -= len;
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, None).unwrap();
+        for i in 0..1000 {
+            db.put(&mut wtxn, &format!("key-{i:04}"), "value").unwrap();
+        }
+        wtxn.commit().unwrap();
+
+        let result = env.copy_to_writer(FailingWriter(4096), CompactionOption::Disabled);
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+
     #[test]
     fn reopen_env_with_different_options_is_err() {
         let dir = tempfile::tempdir().unwrap();
@@ -1111,4 +1614,284 @@ mod tests {
             env.prepare_for_closing().wait();
         }
     }
+
+    #[test]
+    #[should_panic(expected = "The database environment doesn't match")]
+    fn env_mismatch_panics_by_default() {
+        let dir1 = tempfile::tempdir().unwrap();
+        let dir2 = tempfile::tempdir().unwrap();
+        let env1 = EnvOpenOptions::new().open(dir1.path()).unwrap();
+        let env2 = EnvOpenOptions::new().open(dir2.path()).unwrap();
+
+        let mut wtxn1 = env1.write_txn().unwrap();
+        let db1 = env1.create_database::<Str, Str>(&mut wtxn1, None).unwrap();
+        wtxn1.commit().unwrap();
+
+        let rtxn2 = env2.read_txn().unwrap();
+        let _ = db1.get(&rtxn2, "hello");
+    }
+
+    #[test]
+    fn env_mismatch_is_an_error() {
+        let dir1 = tempfile::tempdir().unwrap();
+        let dir2 = tempfile::tempdir().unwrap();
+        let env1 = EnvOpenOptions::new().open(dir1.path()).unwrap();
+        let env2 = EnvOpenOptions::new().env_mismatch_errors(true).open(dir2.path()).unwrap();
+
+        let mut wtxn1 = env1.write_txn().unwrap();
+        let db1 = env1.create_database::<Str, Str>(&mut wtxn1, None).unwrap();
+        wtxn1.commit().unwrap();
+
+        let mut wtxn2 = env2.write_txn().unwrap();
+        assert!(matches!(db1.put(&mut wtxn2, "hello", "world"), Err(Error::EnvMismatch)));
+        assert!(matches!(db1.get(&wtxn2, "hello"), Err(Error::EnvMismatch)));
+        assert!(matches!(db1.iter(&wtxn2), Err(Error::EnvMismatch)));
+
+        let result = env1.database_options().types::<Str, Str>().create(&mut wtxn2);
+        assert!(matches!(result, Err(Error::EnvMismatch)));
+    }
+
+    #[test]
+    #[cfg(feature = "crc32c")]
+    fn verify_checksums_of_another_env_is_an_error() {
+        let dir1 = tempfile::tempdir().unwrap();
+        let dir2 = tempfile::tempdir().unwrap();
+        let env1 = EnvOpenOptions::new().open(dir1.path()).unwrap();
+        let env2 = EnvOpenOptions::new().env_mismatch_errors(true).open(dir2.path()).unwrap();
+
+        let mut wtxn1 = env1.write_txn().unwrap();
+        let db1 = env1.create_database::<Str, Checksummed<Str>>(&mut wtxn1, None).unwrap();
+        db1.put(&mut wtxn1, "hello", "world").unwrap();
+        wtxn1.commit().unwrap();
+
+        let rtxn2 = env2.read_txn().unwrap();
+        assert!(matches!(env2.verify_checksums(&rtxn2, db1), Err(Error::EnvMismatch)));
+
+        let rtxn1 = env1.read_txn().unwrap();
+        assert!(env1.verify_checksums(&rtxn1, db1).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "encryption")]
+    fn encryption_keys_are_per_env() {
+        use crate::{Database, IgnoreContext};
+
+        type Secrets = Database<IgnoreContext<Str>, Encrypted<OwnedSlice<u8>>>;
+
+        let dir1 = tempfile::tempdir().unwrap();
+        let dir2 = tempfile::tempdir().unwrap();
+        let env1 = EnvOpenOptions::new().open(dir1.path()).unwrap();
+        let env2 = EnvOpenOptions::new().open(dir2.path()).unwrap();
+        env1.set_encryption_keys(EncryptionKeys::new(1, [1; 32]));
+        env2.set_encryption_keys(EncryptionKeys::new(1, [2; 32]));
+
+        let mut wtxn1 = env1.write_txn().unwrap();
+        let db1: Secrets = env1.create_database(&mut wtxn1, None).unwrap();
+        let keys1 = wtxn1.encryption_keys().unwrap();
+        db1.with_codec_context(&*keys1).put(&mut wtxn1, "hello", b"first").unwrap();
+        wtxn1.commit().unwrap();
+
+        // Setting the keys of the second env didn't replace the ones of the first.
+        let mut wtxn2 = env2.write_txn().unwrap();
+        let db2: Secrets = env2.create_database(&mut wtxn2, None).unwrap();
+        let keys2 = wtxn2.encryption_keys().unwrap();
+        db2.with_codec_context(&*keys2).put(&mut wtxn2, "hello", b"second").unwrap();
+
+        let rtxn1 = env1.read_txn().unwrap();
+        let keys1 = rtxn1.encryption_keys().unwrap();
+        let first = db1.with_codec_context(&*keys1).get(&rtxn1, "hello").unwrap();
+        assert_eq!(first.as_deref(), Some(&b"first"[..]));
+        let second = db2.with_codec_context(&*keys2).get(&wtxn2, "hello").unwrap();
+        assert_eq!(second.as_deref(), Some(&b"second"[..]));
+
+        // The bytes encrypted in the first env can't be decrypted with the keys of the second.
+        let raw = db1.remap_data_type::<ByteSlice>().get(&rtxn1, "hello").unwrap().unwrap();
+        db2.remap_data_type::<ByteSlice>().put(&mut wtxn2, "copied", raw).unwrap();
+        let result = db2.with_codec_context(&*keys2).get(&wtxn2, "copied");
+        assert!(matches!(result, Err(Error::Decoding(_))));
+
+        let dir3 = tempfile::tempdir().unwrap();
+        let other = EnvOpenOptions::new().open(dir3.path()).unwrap();
+        assert!(other.encryption_keys().is_none());
+    }
+
+    #[test]
+    fn dropped_database_handles_are_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let first = env.create_database::<Str, Str>(&mut wtxn, Some("first")).unwrap();
+        first.put(&mut wtxn, "hello", "first").unwrap();
+        let copy = first;
+        first.drop(&mut wtxn).unwrap();
+
+        // LMDB reuses the dbi of the dropped database for the new one.
+        let second = env.create_database::<Str, Str>(&mut wtxn, Some("second")).unwrap();
+        second.put(&mut wtxn, "hello", "second").unwrap();
+        assert_eq!(second.dbi, copy.dbi);
+
+        assert!(matches!(copy.get(&wtxn, "hello"), Err(Error::Mdb(MdbError::BadDbi))));
+        assert!(matches!(copy.put(&mut wtxn, "world", "first"), Err(Error::Mdb(MdbError::BadDbi))));
+        assert_eq!(second.get(&wtxn, "hello").unwrap(), Some("second"));
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn aborted_drops_keep_the_types_and_generation() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("first")).unwrap();
+        db.put(&mut wtxn, "hello", "world").unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        db.drop(&mut wtxn).unwrap();
+        assert!(matches!(db.get(&wtxn, "hello"), Err(Error::Mdb(MdbError::BadDbi))));
+        wtxn.abort();
+
+        // LMDB closes the dbi even when the drop is aborted, the database must be reopened
+        // but it still exists with its types and the previous handles are not stale.
+        let mut wtxn = env.write_txn().unwrap();
+        let result = env.create_database::<Str, ByteSlice>(&mut wtxn, Some("first"));
+        assert!(matches!(result, Err(Error::InvalidDatabaseTyping)));
+        let reopened = env.create_database::<Str, Str>(&mut wtxn, Some("first")).unwrap();
+        assert_eq!(reopened.get(&wtxn, "hello").unwrap(), Some("world"));
+        assert_eq!((reopened.dbi, reopened.generation), (db.dbi, db.generation));
+
+        // The drop of a committed nested transaction is seen by its parent.
+        let mut nested = env.nested_write_txn(&mut wtxn).unwrap();
+        reopened.drop(&mut nested).unwrap();
+        let recreated = env.create_database::<Str, ByteSlice>(&mut nested, Some("first")).unwrap();
+        nested.commit().unwrap();
+        assert!(matches!(db.get(&wtxn, "hello"), Err(Error::Mdb(MdbError::BadDbi))));
+        assert_eq!(recreated.get(&wtxn, "hello").unwrap(), None);
+
+        // And by the other transactions once the parent is committed.
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        assert!(matches!(db.get(&rtxn, "hello"), Err(Error::Mdb(MdbError::BadDbi))));
+        assert_eq!(recreated.get(&rtxn, "hello").unwrap(), None);
+        let result = env.open_database::<Str, Str>(&rtxn, Some("first"));
+        assert!(matches!(result, Err(Error::InvalidDatabaseTyping)));
+    }
+
+    #[test]
+    fn comparator_panics_poison_the_transaction() {
+        use std::cmp::Ordering;
+        use std::ops::Bound;
+
+        use heed_traits::Comparator;
+
+        enum PanickingCmp {}
+
+        impl Comparator for PanickingCmp {
+            fn compare(a: &[u8], b: &[u8]) -> Ordering {
+                if a == b"boom" || b == b"boom" {
+                    panic!("cannot compare boom");
+                }
+                a.cmp(b)
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Str>()
+            .key_comparator::<PanickingCmp>()
+            .name("panicking")
+            .create(&mut wtxn)
+            .unwrap();
+        db.put(&mut wtxn, "hello", "world").unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        db.put(&mut wtxn, "kiki", "kero").unwrap();
+        let error = db.put(&mut wtxn, "boom", "oops").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::ComparatorPanicked(message) if message == "cannot compare boom"
+        ));
+
+        // The transaction is poisoned, it refuses to do anything else.
+        assert!(matches!(db.get(&wtxn, "hello").unwrap_err(), Error::ComparatorPanicked(_)));
+        assert!(matches!(wtxn.commit(), Err(Error::ComparatorPanicked(_))));
+
+        // The poisoned transaction has been aborted.
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(db.get(&rtxn, "hello").unwrap(), Some("world"));
+        assert_eq!(db.get(&rtxn, "kiki").unwrap(), None);
+        drop(rtxn);
+
+        let mut wtxn = env.write_txn().unwrap();
+        db.put(&mut wtxn, "kiki", "kero").unwrap();
+        wtxn.commit().unwrap();
+
+        // The bounds of the iterators are compared without LMDB, with the same outcome.
+        let rtxn = env.read_txn().unwrap();
+        let bounds = (Bound::Included("a"), Bound::Included("boom"));
+        let mut range = db.range(&rtxn, &bounds).unwrap();
+        assert!(matches!(range.next(), Some(Err(Error::ComparatorPanicked(_)))));
+        assert!(matches!(db.get(&rtxn, "hello").unwrap_err(), Error::ComparatorPanicked(_)));
+        drop(range);
+        drop(rtxn);
+
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(db.get(&rtxn, "hello").unwrap(), Some("world"));
+    }
+
+    #[test]
+    fn typed_comparator_sorts_decoded_keys() {
+        use std::cmp::Ordering;
+
+        use crate::{KeyComparator, TypedComparator};
+
+        type Key = SerdeBincode<(String, u64)>;
+
+        // Sorts the keys by their number first, then by their name.
+        enum ByNumber {}
+
+        impl KeyComparator<Key> for ByNumber {
+            fn compare_keys(a: &(String, u64), b: &(String, u64)) -> Ordering {
+                a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0))
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Key, Unit>()
+            .key_comparator::<TypedComparator<Key, ByNumber>>()
+            .name("typed")
+            .create(&mut wtxn)
+            .unwrap();
+
+        db.put(&mut wtxn, &(String::from("kiki"), 300), &()).unwrap();
+        db.put(&mut wtxn, &(String::from("zorro"), 2), &()).unwrap();
+        db.put(&mut wtxn, &(String::from("alice"), 300), &()).unwrap();
+        // Undecodable keys are sorted after the decodable ones.
+        db.remap_key_type::<ByteSlice>().put(&mut wtxn, b"\xFF", &()).unwrap();
+
+        let keys: Vec<_> =
+            db.remap_key_type::<ByteSlice>().iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys.len(), 4);
+        assert_eq!(keys[3], b"\xFF");
+
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().take(3).map(|r| r.unwrap().0).collect();
+        assert_eq!(
+            keys,
+            [(String::from("zorro"), 2), (String::from("alice"), 300), (String::from("kiki"), 300)]
+        );
+        assert!(db.get(&wtxn, &(String::from("alice"), 300)).unwrap().is_some());
+        wtxn.commit().unwrap();
+    }
 }
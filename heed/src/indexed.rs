@@ -0,0 +1,452 @@
+use std::fmt;
+use std::ops::RangeBounds;
+
+use types::ByteSlice;
+
+use crate::mdb::error::Error as MdbError;
+use crate::*;
+
+/// Decodes the data bytes and returns the index keys extracted from them.
+type Extractor = Box<dyn Fn(&[u8]) -> Result<Vec<Vec<u8>>> + Send + Sync>;
+
+/// A database that keeps secondary indexes in sync with its entries.
+///
+/// Every index is a `DUP_SORT` database that maps the index keys produced by an
+/// extractor closure to the keys of the primary database. The indexes are updated
+/// in the same write transaction as the primary database when using the
+/// [`put`](IndexedDatabase::put), [`update`](IndexedDatabase::update) and
+/// [`delete`](IndexedDatabase::delete) methods of this type.
+///
+/// Note that writing directly into the primary database, without going through
+/// this type, will desynchronize the indexes. They can be rebuilt with
+/// [`rebuild_indexes`](IndexedDatabase::rebuild_indexes).
+///
+/// ```
+/// # use heed::EnvOpenOptions;
+/// use std::ops::Bound;
+/// use heed::IndexedDatabase;
+/// use heed::types::*;
+/// use heed::byteorder::BigEndian;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let dir = tempfile::tempdir()?;
+/// # let env = EnvOpenOptions::new()
+/// #     .map_size(10 * 1024 * 1024) // 10MB
+/// #     .max_dbs(3000)
+/// #     .open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let users = env.create_database::<U32<BigEndian>, Str>(&mut wtxn, Some("users"))?;
+///
+/// // We index the users by the first letter of their name.
+/// let mut users = IndexedDatabase::new(users);
+/// users.add_index(&env, &mut wtxn, "users-by-initial", |name: &&str| {
+///     name.chars().next().map(|c| c.to_string().into_bytes()).into_iter().collect()
+/// })?;
+///
+/// users.put(&mut wtxn, &1, "kerollmops")?;
+/// users.put(&mut wtxn, &2, "irevoire")?;
+/// users.put(&mut wtxn, &3, "ManyTheFish")?;
+/// users.update(&mut wtxn, &3, "many")?;
+///
+/// let by_initial = users.index("users-by-initial").unwrap();
+/// assert_eq!(by_initial.get(&wtxn, b"k")?, vec![(1, "kerollmops")]);
+/// assert_eq!(by_initial.get(&wtxn, b"M")?, vec![]);
+///
+/// let range = (Bound::Included(&b"j"[..]), Bound::Unbounded);
+/// assert_eq!(by_initial.range(&wtxn, &range)?, vec![(1, "kerollmops"), (3, "many")]);
+///
+/// wtxn.commit()?;
+/// # Ok(()) }
+/// ```
+pub struct IndexedDatabase<KC, DC> {
+    primary: Database<KC, DC>,
+    indexes: Vec<SecondaryIndex<KC, DC>>,
+}
+
+impl<KC, DC> IndexedDatabase<KC, DC> {
+    /// Wraps a primary database, with no index registered yet.
+    pub fn new(primary: Database<KC, DC>) -> IndexedDatabase<KC, DC> {
+        IndexedDatabase { primary, indexes: Vec::new() }
+    }
+
+    /// Returns the primary database.
+    pub fn primary(&self) -> Database<KC, DC> {
+        self.primary
+    }
+
+    /// Opens or creates the `DUP_SORT` database named `name` and registers it as an index
+    /// of which the keys are produced by the `extractor` closure.
+    ///
+    /// The index is not filled with the entries already present in the primary
+    /// database, use [`rebuild_indexes`](IndexedDatabase::rebuild_indexes) to do so.
+    pub fn add_index<F>(
+        &mut self,
+        env: &Env,
+        wtxn: &mut RwTxn,
+        name: &str,
+        extractor: F,
+    ) -> Result<()>
+    where
+        DC: for<'a> BytesDecode<'a>,
+        F: for<'a> Fn(&<DC as BytesDecode<'a>>::DItem) -> Vec<Vec<u8>> + Send + Sync + 'static,
+    {
+        let db = env
+            .database_options()
+            .types::<ByteSlice, ByteSlice>()
+            .name(name)
+            .flags(DatabaseFlags::DUP_SORT)
+            .create(wtxn)?;
+
+        let index = SecondaryIndex {
+            name: name.to_string(),
+            primary: self.primary,
+            db,
+            extractor: Box::new(move |bytes| {
+                let data = DC::bytes_decode(bytes).map_err(Error::Decoding)?;
+                Ok(extractor(&data))
+            }),
+        };
+
+        match self.indexes.iter_mut().find(|index| index.name == name) {
+            Some(previous) => *previous = index,
+            None => self.indexes.push(index),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the index registered with this name.
+    pub fn index(&self, name: &str) -> Option<&SecondaryIndex<KC, DC>> {
+        self.indexes.iter().find(|index| index.name == name)
+    }
+
+    /// Returns an iterator over all the registered indexes.
+    pub fn indexes(&self) -> impl Iterator<Item = &SecondaryIndex<KC, DC>> {
+        self.indexes.iter()
+    }
+
+    /// Retrieves the value associated with a key in the primary database.
+    pub fn get<'a, 'txn>(&self, txn: &'txn RoTxn, key: &'a KC::EItem) -> Result<Option<DC::DItem>>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesDecode<'txn>,
+    {
+        self.primary.get(txn, key)
+    }
+
+    /// Inserts a key-value pair in the primary database and the related entries in the indexes.
+    ///
+    /// Like [`Database::put`], it returns an error if the key already exists.
+    pub fn put<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>,
+    {
+        let key_bytes = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let data_bytes = DC::bytes_encode(data).map_err(Error::Encoding)?;
+
+        self.raw_primary().put(txn, &key_bytes, &data_bytes)?;
+        self.insert_index_entries(txn, &key_bytes, &data_bytes)
+    }
+
+    /// Inserts or replaces a key-value pair in the primary database and
+    /// replaces the entries of the previous value in the indexes.
+    pub fn update<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>,
+    {
+        let key_bytes = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let data_bytes = DC::bytes_encode(data).map_err(Error::Encoding)?;
+
+        self.delete_index_entries(txn, &key_bytes)?;
+        self.raw_primary().update(txn, &key_bytes, &data_bytes)?;
+        self.insert_index_entries(txn, &key_bytes, &data_bytes)
+    }
+
+    /// Deletes a key-value pair from the primary database and the related entries in the indexes.
+    ///
+    /// If the key does not exist, then `false` is returned.
+    pub fn delete<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem) -> Result<bool>
+    where
+        KC: BytesEncode<'a>,
+    {
+        let key_bytes = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        self.delete_index_entries(txn, &key_bytes)?;
+        self.raw_primary().delete(txn, &key_bytes)
+    }
+
+    /// Clears all the indexes and fills them again from the entries of the primary database.
+    pub fn rebuild_indexes(&self, txn: &mut RwTxn) -> Result<()> {
+        for index in &self.indexes {
+            index.db.clear(txn)?;
+        }
+
+        // We collect the entries first as we can't write while iterating.
+        let mut entries = Vec::new();
+        for result in self.raw_primary().iter(txn)? {
+            let (key, data) = result?;
+            entries.push((key.to_vec(), self.extract_all(data)?));
+        }
+
+        for (key, index_keys) in entries {
+            for (index, index_keys) in self.indexes.iter().zip(index_keys) {
+                index.insert(txn, &key, &index_keys)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn raw_primary(&self) -> Database<ByteSlice, ByteSlice> {
+        self.primary.remap_types()
+    }
+
+    /// Decodes the data and runs every extractor on it, the index keys are sorted and deduplicated.
+    fn extract_all(&self, data_bytes: &[u8]) -> Result<Vec<Vec<Vec<u8>>>> {
+        self.indexes
+            .iter()
+            .map(|index| {
+                let mut keys = (index.extractor)(data_bytes)?;
+                keys.sort_unstable();
+                keys.dedup();
+                Ok(keys)
+            })
+            .collect()
+    }
+
+    fn insert_index_entries(&self, txn: &mut RwTxn, key: &[u8], data: &[u8]) -> Result<()> {
+        if self.indexes.is_empty() {
+            return Ok(());
+        }
+
+        let index_keys = self.extract_all(data)?;
+        for (index, index_keys) in self.indexes.iter().zip(index_keys) {
+            index.insert(txn, key, &index_keys)?;
+        }
+
+        Ok(())
+    }
+
+    fn delete_index_entries(&self, txn: &mut RwTxn, key: &[u8]) -> Result<()> {
+        if self.indexes.is_empty() {
+            return Ok(());
+        }
+
+        // The extracted keys are owned so we don't keep a reference into
+        // the database while deleting the index entries.
+        let index_keys = match self.raw_primary().get(txn, key)? {
+            Some(data) => self.extract_all(data)?,
+            None => return Ok(()),
+        };
+
+        for (index, index_keys) in self.indexes.iter().zip(index_keys) {
+            for index_key in index_keys {
+                index.db.delete_one_duplicate(txn, &index_key, key)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<KC, DC> fmt::Debug for IndexedDatabase<KC, DC> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IndexedDatabase")
+            .field("primary", &self.primary)
+            .field("indexes", &self.indexes)
+            .finish()
+    }
+}
+
+/// A secondary index of an [`IndexedDatabase`].
+///
+/// It maps the index keys produced by its extractor to the keys of the primary database.
+pub struct SecondaryIndex<KC, DC> {
+    name: String,
+    primary: Database<KC, DC>,
+    db: Database<ByteSlice, ByteSlice>,
+    extractor: Extractor,
+}
+
+impl<KC, DC> SecondaryIndex<KC, DC> {
+    /// Returns the name of the `DUP_SORT` database of this index.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the `DUP_SORT` database that maps the index keys to the primary keys.
+    pub fn database(&self) -> Database<ByteSlice, ByteSlice> {
+        self.db
+    }
+
+    /// Returns the entries of the primary database associated with this index key,
+    /// ordered by primary key.
+    pub fn get<'txn>(
+        &self,
+        txn: &'txn RoTxn,
+        index_key: &[u8],
+    ) -> Result<Vec<(KC::DItem, DC::DItem)>>
+    where
+        KC: BytesDecode<'txn>,
+        DC: BytesDecode<'txn>,
+    {
+        let mut entries = Vec::new();
+        if let Some(duplicates) = self.db.get_duplicates(txn, index_key)? {
+            for result in duplicates {
+                let (_, primary_key) = result?;
+                entries.push(self.primary_entry(txn, primary_key)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Returns the entries of the primary database associated with the index keys
+    /// in this range, ordered by index key and then by primary key.
+    pub fn range<'txn, R>(&self, txn: &'txn RoTxn, range: &R) -> Result<Vec<(KC::DItem, DC::DItem)>>
+    where
+        KC: BytesDecode<'txn>,
+        DC: BytesDecode<'txn>,
+        R: RangeBounds<[u8]>,
+    {
+        let mut entries = Vec::new();
+        for result in self.db.range(txn, range)? {
+            let (_, primary_key) = result?;
+            entries.push(self.primary_entry(txn, primary_key)?);
+        }
+        Ok(entries)
+    }
+
+    /// Returns the number of entries in this index.
+    pub fn len(&self, txn: &RoTxn) -> Result<u64> {
+        self.db.len(txn)
+    }
+
+    /// Returns `true` if this index is empty.
+    pub fn is_empty(&self, txn: &RoTxn) -> Result<bool> {
+        self.db.is_empty(txn)
+    }
+
+    fn primary_entry<'txn>(
+        &self,
+        txn: &'txn RoTxn,
+        primary_key: &'txn [u8],
+    ) -> Result<(KC::DItem, DC::DItem)>
+    where
+        KC: BytesDecode<'txn>,
+        DC: BytesDecode<'txn>,
+    {
+        // An index entry without its primary entry means that
+        // the primary database was modified behind our back.
+        let data = self.primary.remap_key_type::<ByteSlice>().get(txn, primary_key)?;
+        let data = data.ok_or(Error::Mdb(MdbError::NotFound))?;
+        let key = KC::bytes_decode(primary_key).map_err(Error::Decoding)?;
+        Ok((key, data))
+    }
+
+    fn insert(&self, txn: &mut RwTxn, key: &[u8], index_keys: &[Vec<u8>]) -> Result<()> {
+        for index_key in index_keys {
+            self.db.put_with_flags(txn, PutFlags::empty(), index_key.as_slice(), key)?;
+        }
+        Ok(())
+    }
+}
+
+impl<KC, DC> fmt::Debug for SecondaryIndex<KC, DC> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecondaryIndex").field("name", &self.name).field("db", &self.db).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use crate::byteorder::BigEndian;
+    use crate::types::*;
+    use crate::{EnvOpenOptions, IndexedDatabase};
+
+    fn words(text: &&str) -> Vec<Vec<u8>> {
+        text.split_whitespace().map(|word| word.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn indexes_follow_the_primary_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .max_dbs(10)
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let primary = env.create_database::<U32<BigEndian>, Str>(&mut wtxn, Some("docs")).unwrap();
+        let mut docs = IndexedDatabase::new(primary);
+        docs.add_index(&env, &mut wtxn, "docs-words", words).unwrap();
+        docs.add_index(&env, &mut wtxn, "docs-len", |text: &&str| {
+            vec![(text.len() as u32).to_be_bytes().to_vec()]
+        })
+        .unwrap();
+
+        docs.put(&mut wtxn, &1, "hello world").unwrap();
+        docs.put(&mut wtxn, &2, "hello hello there").unwrap();
+        docs.put(&mut wtxn, &3, "goodbye world").unwrap();
+        assert!(docs.put(&mut wtxn, &3, "already there").is_err());
+
+        let by_word = docs.index("docs-words").unwrap();
+        let by_len = docs.index("docs-len").unwrap();
+        assert_eq!(
+            by_word.get(&wtxn, b"hello").unwrap(),
+            [(1, "hello world"), (2, "hello hello there")]
+        );
+        assert_eq!(
+            by_word.get(&wtxn, b"world").unwrap(),
+            [(1, "hello world"), (3, "goodbye world")]
+        );
+        assert_eq!(by_word.len(&wtxn).unwrap(), 6);
+
+        docs.update(&mut wtxn, &1, "farewell").unwrap();
+        assert_eq!(by_word.get(&wtxn, b"hello").unwrap(), [(2, "hello hello there")]);
+        assert_eq!(by_word.get(&wtxn, b"world").unwrap(), [(3, "goodbye world")]);
+        assert_eq!(by_word.get(&wtxn, b"farewell").unwrap(), [(1, "farewell")]);
+
+        let eight = 8u32.to_be_bytes();
+        let thirteen = 13u32.to_be_bytes();
+        let range = (Bound::Included(&eight[..]), Bound::Excluded(&thirteen[..]));
+        assert_eq!(by_len.range(&wtxn, &range).unwrap(), [(1, "farewell")]);
+
+        assert!(docs.delete(&mut wtxn, &2).unwrap());
+        assert!(!docs.delete(&mut wtxn, &2).unwrap());
+        assert!(by_word.get(&wtxn, b"hello").unwrap().is_empty());
+        assert!(by_word.get(&wtxn, b"there").unwrap().is_empty());
+        assert_eq!(by_word.len(&wtxn).unwrap(), 3);
+        assert_eq!(by_len.len(&wtxn).unwrap(), 2);
+
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn rebuild_indexes_from_existing_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .max_dbs(10)
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let primary = env.create_database::<U32<BigEndian>, Str>(&mut wtxn, Some("docs")).unwrap();
+        primary.put(&mut wtxn, &1, "written before the index").unwrap();
+
+        let mut docs = IndexedDatabase::new(primary);
+        docs.add_index(&env, &mut wtxn, "docs-words", words).unwrap();
+        let by_word = docs.index("docs-words").unwrap();
+        assert!(by_word.is_empty(&wtxn).unwrap());
+
+        docs.rebuild_indexes(&mut wtxn).unwrap();
+        assert_eq!(by_word.get(&wtxn, b"index").unwrap(), [(1, "written before the index")]);
+        assert_eq!(by_word.len(&wtxn).unwrap(), 4);
+
+        wtxn.commit().unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+use std::borrow::Cow;
+use std::marker;
+
+use types::LazyDecode;
+
+use crate::iteration_method::{IterationMethod, MoveBetweenKeys, MoveThroughDuplicateValues};
+use crate::iterator::{move_on_prefix_seek, seek_forward};
+use crate::*;
+
+/// A read-only prefix iterator structure for the [`ContiguousPrefixComparator`]s.
+///
+/// It seeks on the prefix and stops on the first key that doesn't start with its bytes.
+pub struct RoContiguousPrefix<'txn, KC, DC, C, IM = MoveThroughDuplicateValues> {
+    cursor: RoCursor<'txn>,
+    prefix: Vec<u8>,
+    move_on_first: bool,
+    _phantom: marker::PhantomData<(KC, DC, C, IM)>,
+}
+
+impl<'txn, KC, DC, C, IM> RoContiguousPrefix<'txn, KC, DC, C, IM> {
+    pub(crate) fn new(
+        cursor: RoCursor<'txn>,
+        prefix: Vec<u8>,
+    ) -> RoContiguousPrefix<'txn, KC, DC, C, IM> {
+        RoContiguousPrefix { cursor, prefix, move_on_first: true, _phantom: marker::PhantomData }
+    }
+
+    /// Move the iterator on the first entry of the prefix with a key greater than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: ContiguousPrefixComparator,
+    {
+        let prefix = &self.prefix;
+        let seek = seek_forward::<KC>(&mut self.cursor, key, |cursor, key| {
+            move_on_prefix_seek::<C>(cursor, prefix, key)
+        })?;
+        self.move_on_first = seek.is_first();
+        Ok(())
+    }
+
+    /// Move on the first value of keys, ignoring duplicate values.
+    ///
+    /// For more info, see [`RoIter::move_between_keys`].
+    pub fn move_between_keys(self) -> RoContiguousPrefix<'txn, KC, DC, C, MoveBetweenKeys> {
+        RoContiguousPrefix {
+            cursor: self.cursor,
+            prefix: self.prefix,
+            move_on_first: self.move_on_first,
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Move through key/values entries and output duplicate values.
+    ///
+    /// For more info, see [`RoIter::move_through_duplicate_values`].
+    pub fn move_through_duplicate_values(
+        self,
+    ) -> RoContiguousPrefix<'txn, KC, DC, C, MoveThroughDuplicateValues> {
+        RoContiguousPrefix {
+            cursor: self.cursor,
+            prefix: self.prefix,
+            move_on_first: self.move_on_first,
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Change the codec types of this iterator, specifying the codecs.
+    pub fn remap_types<KC2, DC2>(self) -> RoContiguousPrefix<'txn, KC2, DC2, C, IM> {
+        RoContiguousPrefix {
+            cursor: self.cursor,
+            prefix: self.prefix,
+            move_on_first: self.move_on_first,
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Change the key codec type of this iterator, specifying the new codec.
+    pub fn remap_key_type<KC2>(self) -> RoContiguousPrefix<'txn, KC2, DC, C, IM> {
+        self.remap_types::<KC2, DC>()
+    }
+
+    /// Change the data codec type of this iterator, specifying the new codec.
+    pub fn remap_data_type<DC2>(self) -> RoContiguousPrefix<'txn, KC, DC2, C, IM> {
+        self.remap_types::<KC, DC2>()
+    }
+
+    /// Wrap the data bytes into a lazy decoder.
+    pub fn lazily_decode_data(self) -> RoContiguousPrefix<'txn, KC, LazyDecode<DC>, C, IM> {
+        self.remap_types::<KC, LazyDecode<DC>>()
+    }
+}
+
+impl<'txn, KC, DC, C, IM> Iterator for RoContiguousPrefix<'txn, KC, DC, C, IM>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+    C: ContiguousPrefixComparator,
+    IM: IterationMethod,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = if self.move_on_first {
+            self.move_on_first = false;
+            self.cursor.move_on_key_greater_than_or_equal_to(&self.prefix)
+        } else {
+            self.cursor.move_on_next(IM::MOVE_OPERATION)
+        };
+
+        match result {
+            Ok(Some((key, data))) => {
+                if key.starts_with(&self.prefix) {
+                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                    }
+                } else {
+                    None
+                }
+            }
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl<KC, DC, C, IM> fmt::Debug for RoContiguousPrefix<'_, KC, DC, C, IM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoContiguousPrefix").finish()
+    }
+}
+
+/// A read-write prefix iterator structure for the [`ContiguousPrefixComparator`]s.
+///
+/// It seeks on the prefix and stops on the first key that doesn't start with its bytes.
+pub struct RwContiguousPrefix<'txn, KC, DC, C, IM = MoveThroughDuplicateValues> {
+    cursor: RwCursor<'txn>,
+    prefix: Vec<u8>,
+    move_on_first: bool,
+    _phantom: marker::PhantomData<(KC, DC, C, IM)>,
+}
+
+impl<'txn, KC, DC, C, IM> RwContiguousPrefix<'txn, KC, DC, C, IM> {
+    pub(crate) fn new(
+        cursor: RwCursor<'txn>,
+        prefix: Vec<u8>,
+    ) -> RwContiguousPrefix<'txn, KC, DC, C, IM> {
+        RwContiguousPrefix { cursor, prefix, move_on_first: true, _phantom: marker::PhantomData }
+    }
+
+    /// Delete the entry the cursor is currently pointing to.
+    ///
+    /// Returns `true` if the entry was successfully deleted.
+    ///
+    /// # Safety
+    ///
+    /// Please read the safety notes of the [`RwPrefix::del_current`] method.
+    pub unsafe fn del_current(&mut self) -> Result<bool> {
+        self.cursor.del_current()
+    }
+
+    /// Write a new value to the current entry.
+    ///
+    /// The given key **must** be equal to the one this cursor is pointing otherwise the database
+    /// can be put into an inconsistent state.
+    ///
+    /// Returns `true` if the entry was successfully written.
+    ///
+    /// # Safety
+    ///
+    /// Please read the safety notes of the [`RwPrefix::put_current`] method.
+    pub unsafe fn put_current<'a>(
+        &mut self,
+        key: &'a KC::EItem,
+        data: &'a DC::EItem,
+    ) -> Result<bool>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>,
+    {
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        self.cursor.put_current(&key_bytes, &data_bytes)
+    }
+
+    /// Write a new value to the current entry.
+    ///
+    /// The given key **must** be equal to the one this cursor is pointing otherwise the database
+    /// can be put into an inconsistent state.
+    ///
+    /// Returns `true` if the entry was successfully written.
+    ///
+    /// # Safety
+    ///
+    /// Please read the safety notes of the [`RwPrefix::put_current`] method.
+    pub unsafe fn put_current_reserved<'a, F>(
+        &mut self,
+        key: &'a KC::EItem,
+        data_size: usize,
+        write_func: F,
+    ) -> Result<bool>
+    where
+        KC: BytesEncode<'a>,
+        F: FnMut(&mut ReservedSpace) -> io::Result<()>,
+    {
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        self.cursor.put_current_reserved(&key_bytes, data_size, write_func)
+    }
+
+    /// Insert a key-value pair in this database. The entry is written with the specified flags.
+    ///
+    /// For more info, see [`RwIter::put_current_with_flags`].
+    ///
+    /// # Safety
+    ///
+    /// Please read the safety notes of the [`RwPrefix::put_current`] method.
+    pub unsafe fn put_current_with_flags<'a>(
+        &mut self,
+        flags: PutFlags,
+        key: &'a KC::EItem,
+        data: &'a DC::EItem,
+    ) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>,
+    {
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
+    }
+
+    /// Move the iterator on the first entry of the prefix with a key greater than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: ContiguousPrefixComparator,
+    {
+        let prefix = &self.prefix;
+        let seek = seek_forward::<KC>(&mut self.cursor, key, |cursor, key| {
+            move_on_prefix_seek::<C>(cursor, prefix, key)
+        })?;
+        self.move_on_first = seek.is_first();
+        Ok(())
+    }
+
+    /// Move on the first value of keys, ignoring duplicate values.
+    ///
+    /// For more info, see [`RoIter::move_between_keys`].
+    pub fn move_between_keys(self) -> RwContiguousPrefix<'txn, KC, DC, C, MoveBetweenKeys> {
+        RwContiguousPrefix {
+            cursor: self.cursor,
+            prefix: self.prefix,
+            move_on_first: self.move_on_first,
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Move through key/values entries and output duplicate values.
+    ///
+    /// For more info, see [`RoIter::move_through_duplicate_values`].
+    pub fn move_through_duplicate_values(
+        self,
+    ) -> RwContiguousPrefix<'txn, KC, DC, C, MoveThroughDuplicateValues> {
+        RwContiguousPrefix {
+            cursor: self.cursor,
+            prefix: self.prefix,
+            move_on_first: self.move_on_first,
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Change the codec types of this iterator, specifying the codecs.
+    pub fn remap_types<KC2, DC2>(self) -> RwContiguousPrefix<'txn, KC2, DC2, C, IM> {
+        RwContiguousPrefix {
+            cursor: self.cursor,
+            prefix: self.prefix,
+            move_on_first: self.move_on_first,
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Change the key codec type of this iterator, specifying the new codec.
+    pub fn remap_key_type<KC2>(self) -> RwContiguousPrefix<'txn, KC2, DC, C, IM> {
+        self.remap_types::<KC2, DC>()
+    }
+
+    /// Change the data codec type of this iterator, specifying the new codec.
+    pub fn remap_data_type<DC2>(self) -> RwContiguousPrefix<'txn, KC, DC2, C, IM> {
+        self.remap_types::<KC, DC2>()
+    }
+
+    /// Wrap the data bytes into a lazy decoder.
+    pub fn lazily_decode_data(self) -> RwContiguousPrefix<'txn, KC, LazyDecode<DC>, C, IM> {
+        self.remap_types::<KC, LazyDecode<DC>>()
+    }
+}
+
+impl<'txn, KC, DC, C, IM> Iterator for RwContiguousPrefix<'txn, KC, DC, C, IM>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+    C: ContiguousPrefixComparator,
+    IM: IterationMethod,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = if self.move_on_first {
+            self.move_on_first = false;
+            self.cursor.move_on_key_greater_than_or_equal_to(&self.prefix)
+        } else {
+            self.cursor.move_on_next(IM::MOVE_OPERATION)
+        };
+
+        match result {
+            Ok(Some((key, data))) => {
+                if key.starts_with(&self.prefix) {
+                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                    }
+                } else {
+                    None
+                }
+            }
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl<KC, DC, C, IM> fmt::Debug for RwContiguousPrefix<'_, KC, DC, C, IM> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RwContiguousPrefix").finish()
+    }
+}
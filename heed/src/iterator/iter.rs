@@ -4,18 +4,88 @@ use std::marker;
 use types::LazyDecode;
 
 use crate::iteration_method::{IterationMethod, MoveBetweenKeys, MoveThroughDuplicateValues};
+use crate::iterator::{seek_backward, seek_forward, BackEnd};
 use crate::*;
 
 /// A read-only iterator structure.
 pub struct RoIter<'txn, KC, DC, IM = MoveThroughDuplicateValues> {
     cursor: RoCursor<'txn>,
     move_on_first: bool,
+    back: BackEnd<'txn>,
     _phantom: marker::PhantomData<(KC, DC, IM)>,
 }
 
 impl<'txn, KC, DC, IM> RoIter<'txn, KC, DC, IM> {
     pub(crate) fn new(cursor: RoCursor<'txn>) -> RoIter<'txn, KC, DC, IM> {
-        RoIter { cursor, move_on_first: true, _phantom: marker::PhantomData }
+        RoIter { cursor, move_on_first: true, back: BackEnd::new(), _phantom: marker::PhantomData }
+    }
+
+    /// Move the iterator on the first entry with a key greater than or equal to
+    /// the given one, it is the entry returned by the next call to `next`.
+    ///
+    /// The cursor of the iterator is repositioned with `MDB_SET_RANGE`, which is
+    /// cheaper than creating a new iterator. It can also move the iterator backward.
+    ///
+    /// ```
+    /// # use heed::EnvOpenOptions;
+    /// use heed::types::*;
+    /// use heed::byteorder::BigEndian;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let env = EnvOpenOptions::new()
+    /// #     .map_size(10 * 1024 * 1024) // 10MB
+    /// #     .max_dbs(3000)
+    /// #     .open(dir.path())?;
+    /// type BEU32 = U32<BigEndian>;
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let evens = env.create_database::<BEU32, Unit>(&mut wtxn, Some("evens"))?;
+    /// let threes = env.create_database::<BEU32, Unit>(&mut wtxn, Some("threes"))?;
+    /// for n in (0..30).step_by(2) {
+    ///     evens.put(&mut wtxn, &n, &())?;
+    /// }
+    /// for n in (0..30).step_by(3) {
+    ///     threes.put(&mut wtxn, &n, &())?;
+    /// }
+    ///
+    /// // Intersect both tables by seeking each iterator on the key of the other one.
+    /// let mut a = evens.iter(&wtxn)?;
+    /// let mut b = threes.iter(&wtxn)?;
+    /// let mut common = Vec::new();
+    /// let (mut x, mut y) = (a.next().transpose()?, b.next().transpose()?);
+    /// while let (Some((ka, ())), Some((kb, ()))) = (x, y) {
+    ///     if ka == kb {
+    ///         common.push(ka);
+    ///         x = a.next().transpose()?;
+    ///         y = b.next().transpose()?;
+    ///     } else if ka < kb {
+    ///         a.seek(&kb)?;
+    ///         x = a.next().transpose()?;
+    ///     } else {
+    ///         b.seek(&ka)?;
+    ///         y = b.next().transpose()?;
+    ///     }
+    /// }
+    /// assert_eq!(common, [0, 6, 12, 18, 24]);
+    ///
+    /// drop(a);
+    /// drop(b);
+    /// wtxn.commit()?;
+    /// # Ok(()) }
+    /// ```
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        IM: IterationMethod,
+    {
+        let seek = seek_forward::<KC>(
+            &mut self.cursor,
+            key,
+            RoCursor::move_on_key_greater_than_or_equal_to,
+        )?;
+        self.move_on_first = seek.is_first();
+        self.back.front_seeked(seek.entry(), IM::MOVE_OPERATION)
     }
 
     /// Move on the first value of keys, ignoring duplicate values.
@@ -66,6 +136,7 @@ impl<'txn, KC, DC, IM> RoIter<'txn, KC, DC, IM> {
         RoIter {
             cursor: self.cursor,
             move_on_first: self.move_on_first,
+            back: self.back,
             _phantom: marker::PhantomData,
         }
     }
@@ -121,6 +192,7 @@ impl<'txn, KC, DC, IM> RoIter<'txn, KC, DC, IM> {
         RoIter {
             cursor: self.cursor,
             move_on_first: self.move_on_first,
+            back: self.back,
             _phantom: marker::PhantomData,
         }
     }
@@ -130,6 +202,7 @@ impl<'txn, KC, DC, IM> RoIter<'txn, KC, DC, IM> {
         RoIter {
             cursor: self.cursor,
             move_on_first: self.move_on_first,
+            back: self.back,
             _phantom: marker::PhantomData,
         }
     }
@@ -165,6 +238,7 @@ where
         } else {
             self.cursor.move_on_next(IM::MOVE_OPERATION)
         };
+        let result = self.back.front_reached(result, IM::MOVE_OPERATION, |_| Ok(true));
 
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -177,6 +251,10 @@ where
     }
 
     fn last(mut self) -> Option<Self::Item> {
+        if self.back.is_started() {
+            return self.next_back();
+        }
+
         let result = if self.move_on_first {
             self.cursor.move_on_last(IM::MOVE_OPERATION)
         } else {
@@ -200,6 +278,33 @@ where
     }
 }
 
+impl<'txn, KC, DC, IM> DoubleEndedIterator for RoIter<'txn, KC, DC, IM>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+    IM: IterationMethod,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let result = self.back.cursor(&self.cursor).and_then(|(cursor, opened)| {
+            if opened {
+                cursor.move_on_last(IM::MOVE_OPERATION)
+            } else {
+                cursor.move_on_prev(IM::MOVE_OPERATION)
+            }
+        });
+        let result = self.back.back_reached(result, IM::MOVE_OPERATION, |_| Ok(true));
+
+        match result {
+            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+            },
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
 impl<KC, DC, IM> fmt::Debug for RoIter<'_, KC, DC, IM> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RoIter").finish()
@@ -331,6 +436,21 @@ impl<'txn, KC, DC, IM> RwIter<'txn, KC, DC, IM> {
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
+    /// Move the iterator on the first entry with a key greater than or equal to
+    /// the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+    {
+        let seek = seek_forward::<KC>(
+            &mut self.cursor,
+            key,
+            RoCursor::move_on_key_greater_than_or_equal_to,
+        )?;
+        self.move_on_first = seek.is_first();
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
@@ -446,6 +566,16 @@ impl<'txn, KC, DC, IM> RoRevIter<'txn, KC, DC, IM> {
         RoRevIter { cursor, move_on_last: true, _phantom: marker::PhantomData }
     }
 
+    /// Move the iterator on the last entry with a key lower than or equal to
+    /// the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+    {
+        self.move_on_last = seek_backward::<KC>(&mut self.cursor, key, |_, _| Ok(false))?;
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
@@ -676,6 +806,16 @@ impl<'txn, KC, DC, IM> RwRevIter<'txn, KC, DC, IM> {
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
+    /// Move the iterator on the last entry with a key lower than or equal to
+    /// the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+    {
+        self.move_on_last = seek_backward::<KC>(&mut self.cursor, key, |_, _| Ok(false))?;
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
@@ -1,11 +1,248 @@
+mod contiguous_prefix;
 mod iter;
 mod prefix;
 mod range;
 
+pub use self::contiguous_prefix::{RoContiguousPrefix, RwContiguousPrefix};
 pub use self::iter::{RoIter, RoRevIter, RwIter, RwRevIter};
+pub(crate) use self::prefix::advance_prefix;
 pub use self::prefix::{RoPrefix, RoRevPrefix, RwPrefix, RwRevPrefix};
 pub use self::range::{RoRange, RoRevRange, RwRange, RwRevRange};
 
+use std::cmp::Ordering;
+
+use crate::cursor::MoveOperation;
+use crate::{BytesEncode, Comparator, Error, Result, RoCursor};
+
+/// The back end of a double-ended iterator.
+///
+/// It moves a second cursor from the end of the iterated entries and remembers
+/// the entries last reached by both cursors to stop the iteration when they meet.
+pub(crate) struct BackEnd<'txn> {
+    cursor: Option<RoCursor<'txn>>,
+    front: Option<(&'txn [u8], &'txn [u8])>,
+    back: Option<(&'txn [u8], &'txn [u8])>,
+    seeked: Option<&'txn [u8]>,
+    met: bool,
+}
+
+impl<'txn> BackEnd<'txn> {
+    pub(crate) fn new() -> BackEnd<'txn> {
+        BackEnd { cursor: None, front: None, back: None, seeked: None, met: false }
+    }
+
+    /// Returns `true` if the back cursor has been moved.
+    pub(crate) fn is_started(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Returns the back cursor and `true` if it has just been opened, on the same
+    /// database as the front one, and must be moved on the end of the entries.
+    pub(crate) fn cursor(&mut self, front: &RoCursor<'txn>) -> Result<(&mut RoCursor<'txn>, bool)> {
+        let opened = self.cursor.is_none();
+        if opened {
+            self.cursor = Some(front.sibling()?);
+        }
+        Ok((self.cursor.as_mut().unwrap(), opened))
+    }
+
+    /// Records the entry reached by the front cursor, or returns `None` if it is out
+    /// of the iterated entries or the one the back cursor already returned.
+    ///
+    /// Once an end returned `None` both of them keep returning it.
+    pub(crate) fn front_reached(
+        &mut self,
+        result: Result<Option<(&'txn [u8], &'txn [u8])>>,
+        op: MoveOperation,
+        in_bounds: impl FnOnce(&[u8]) -> Result<bool>,
+    ) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
+        let entry = result?;
+        let entry = match entry {
+            Some(entry) if !self.met && in_bounds(entry.0)? => entry,
+            _ => {
+                self.met = true;
+                return Ok(None);
+            }
+        };
+        if self.back.is_some_and(|b| same(entry, b, op)) {
+            self.met = true;
+            return Ok(None);
+        }
+        self.front = Some(entry);
+        Ok(Some(entry))
+    }
+
+    /// Records the entry the front cursor has been seeked on, the back cursor
+    /// must not return the entries before it and it must not be after the back one.
+    pub(crate) fn front_seeked(
+        &mut self,
+        entry: Option<(&'txn [u8], &'txn [u8])>,
+        op: MoveOperation,
+    ) -> Result<()> {
+        self.front = None;
+        self.seeked = entry.map(|(key, _)| key);
+        self.met = match (entry, self.back, &self.cursor) {
+            (Some((key, _)), Some((back_key, _)), Some(cursor)) => {
+                match cursor.compare_keys(key, back_key)? {
+                    Ordering::Greater => true,
+                    Ordering::Equal => matches!(op, MoveOperation::NoDup),
+                    Ordering::Less => false,
+                }
+            }
+            _ => false,
+        };
+        Ok(())
+    }
+
+    /// Records the entry reached by the back cursor, or returns `None` if it is out
+    /// of the iterated entries or the one the front cursor already returned.
+    ///
+    /// Once an end returned `None` both of them keep returning it.
+    pub(crate) fn back_reached(
+        &mut self,
+        result: Result<Option<(&'txn [u8], &'txn [u8])>>,
+        op: MoveOperation,
+        in_bounds: impl FnOnce(&[u8]) -> Result<bool>,
+    ) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
+        let entry = result?;
+        let entry = match entry {
+            Some(entry) if !self.met && in_bounds(entry.0)? => entry,
+            _ => {
+                self.met = true;
+                return Ok(None);
+            }
+        };
+        if self.front.is_some_and(|f| same(entry, f, op)) {
+            self.met = true;
+            return Ok(None);
+        }
+        if let (Some(seeked), Some(cursor)) = (self.seeked, &self.cursor) {
+            if cursor.compare_keys(entry.0, seeked)?.is_lt() {
+                self.met = true;
+                return Ok(None);
+            }
+        }
+        self.back = Some(entry);
+        Ok(Some(entry))
+    }
+}
+
+/// The position of a forward iterator cursor after a seek.
+pub(crate) enum Seek<'txn> {
+    /// The cursor is just before this entry, moving it forward returns it.
+    Before((&'txn [u8], &'txn [u8])),
+    /// This entry is the first one of the database, the iteration must start over.
+    First((&'txn [u8], &'txn [u8])),
+    /// There is no entry after the seeked key.
+    End,
+}
+
+impl<'txn> Seek<'txn> {
+    /// Moves the cursor just before `entry`, the one it was moved on, so
+    /// that the next move of a forward iterator returns it again.
+    pub(crate) fn before(
+        cursor: &mut RoCursor<'txn>,
+        entry: Option<(&'txn [u8], &'txn [u8])>,
+    ) -> Result<Seek<'txn>> {
+        match entry {
+            Some(entry) => match cursor.move_on_prev(MoveOperation::Any)? {
+                Some(_) => Ok(Seek::Before(entry)),
+                None => Ok(Seek::First(entry)),
+            },
+            None => {
+                // The cursor is moved on the last entry for the next moves to return nothing.
+                cursor.move_on_last(MoveOperation::Any)?;
+                Ok(Seek::End)
+            }
+        }
+    }
+
+    /// Returns `true` if the iteration must start over.
+    pub(crate) fn is_first(&self) -> bool {
+        matches!(self, Seek::First(_))
+    }
+
+    /// Returns the entry the iterator will return next.
+    pub(crate) fn entry(&self) -> Option<(&'txn [u8], &'txn [u8])> {
+        match self {
+            Seek::Before(entry) | Seek::First(entry) => Some(*entry),
+            Seek::End => None,
+        }
+    }
+}
+
+/// Encodes the key of a forward iterator seek and moves the cursor just before the entry
+/// `move_on_key` moves it on, given the encoded key, so that the next move returns it.
+///
+/// The double-ended iterators must then record the seeked entry on their back end.
+pub(crate) fn seek_forward<'a, 'txn, KC>(
+    cursor: &mut RoCursor<'txn>,
+    key: &'a KC::EItem,
+    move_on_key: impl FnOnce(&mut RoCursor<'txn>, &[u8]) -> Result<Option<(&'txn [u8], &'txn [u8])>>,
+) -> Result<Seek<'txn>>
+where
+    KC: BytesEncode<'a>,
+{
+    cursor.txn().with_scratch(|key_bytes| {
+        KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+        let entry = move_on_key(cursor, key_bytes)?;
+        Seek::before(cursor, entry)
+    })
+}
+
+/// Moves the cursor on the first entry with a key greater than or equal to `key`,
+/// or to `prefix` if the key is before it, for the prefix iterators to seek.
+pub(crate) fn move_on_prefix_seek<'txn, C: Comparator>(
+    cursor: &mut RoCursor<'txn>,
+    prefix: &[u8],
+    key: &[u8],
+) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
+    let key = if cursor.compare_with::<C>(key, prefix)?.is_lt() { prefix } else { key };
+    cursor.move_on_key_greater_than_or_equal_to(key)
+}
+
+/// Encodes the key of a backward iterator seek and moves the cursor just after the last
+/// entry lower than or equal to it, so that the next move returns it.
+///
+/// Returns `true` if the iteration must start over from the end, because there is no entry
+/// after the key or because `after_end` tells the key is after the iterated entries.
+pub(crate) fn seek_backward<'a, 'txn, KC>(
+    cursor: &mut RoCursor<'txn>,
+    key: &'a KC::EItem,
+    after_end: impl FnOnce(&RoCursor<'txn>, &[u8]) -> Result<bool>,
+) -> Result<bool>
+where
+    KC: BytesEncode<'a>,
+{
+    cursor.txn().with_scratch(|key_bytes| {
+        KC::encode_into(key, key_bytes).map_err(Error::Encoding)?;
+        Ok(after_end(cursor, key_bytes)? || !seek_after(cursor, key_bytes)?)
+    })
+}
+
+/// Moves the cursor just after the last entry lower than or equal to `key`, so that
+/// the next move of a backward iterator returns it.
+///
+/// Returns `false` if there is no entry after it and the iteration must start over from the end.
+fn seek_after(cursor: &mut RoCursor, key: &[u8]) -> Result<bool> {
+    match cursor.move_on_key_greater_than_or_equal_to(key)? {
+        Some((found, _)) if cursor.compare_keys(found, key)?.is_eq() => {
+            Ok(cursor.move_on_next(MoveOperation::NoDup)?.is_some())
+        }
+        Some(_) => Ok(true),
+        None => Ok(false),
+    }
+}
+
+/// Returns `true` if both entries are at the same position, only the keys
+/// are compared when the iteration moves between keys.
+fn same(a: (&[u8], &[u8]), b: (&[u8], &[u8]), op: MoveOperation) -> bool {
+    match op {
+        MoveOperation::NoDup => a.0 == b.0,
+        MoveOperation::Any | MoveOperation::Dup => a == b,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops;
@@ -616,4 +853,428 @@ mod tests {
 
         wtxn.abort();
     }
+
+    #[test]
+    fn contiguous_prefix_iter_stops_after_the_prefix() {
+        use crate::types::*;
+        use crate::{EnvOpenOptions, ReverseLexicographicComparator};
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<ByteSlice, Unit>()
+            .key_comparator::<ReverseLexicographicComparator>()
+            .create(&mut wtxn)
+            .unwrap();
+        for key in [&[2][..], &[1], &[1, 255], &[1, 7], &[1, 0], &[0, 255]] {
+            db.put(&mut wtxn, key, &()).unwrap();
+        }
+
+        let keys: Vec<_> =
+            db.contiguous_prefix_iter(&wtxn, &[1]).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [&[1][..], &[1, 255], &[1, 7], &[1, 0]]);
+
+        let mut iter = db.contiguous_prefix_iter_mut(&mut wtxn, &[1]).unwrap();
+        while let Some(result) = iter.next() {
+            let (key, ()) = result.unwrap();
+            if key.len() == 2 {
+                assert!(unsafe { iter.del_current() }.unwrap());
+            }
+        }
+        drop(iter);
+
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [&[2][..], &[1], &[0, 255]]);
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn contiguous_prefix_iter_with_a_non_lexicographic_comparator() {
+        use std::cmp::Ordering;
+
+        use crate::types::*;
+        use crate::{Comparator, ContiguousPrefixComparator, EnvOpenOptions};
+
+        /// Compares the first two bytes, then the length and the bytes of the rest,
+        /// the keys sharing a prefix of up to two bytes are next to each other.
+        enum TwoBytesThenLength {}
+
+        impl Comparator for TwoBytesThenLength {
+            fn compare(a: &[u8], b: &[u8]) -> Ordering {
+                let (a_head, a_tail) = a.split_at(a.len().min(2));
+                let (b_head, b_tail) = b.split_at(b.len().min(2));
+                a_head
+                    .cmp(b_head)
+                    .then_with(|| a_tail.len().cmp(&b_tail.len()))
+                    .then_with(|| a_tail.cmp(b_tail))
+            }
+        }
+
+        impl ContiguousPrefixComparator for TwoBytesThenLength {}
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<ByteSlice, Unit>()
+            .key_comparator::<TwoBytesThenLength>()
+            .create(&mut wtxn)
+            .unwrap();
+        for key in [&[1, 2, 9][..], &[2, 0], &[1, 2, 0, 0], &[1, 3], &[1, 2], &[1, 1, 5, 5, 5]] {
+            db.put(&mut wtxn, key, &()).unwrap();
+        }
+
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(
+            keys,
+            [&[1, 1, 5, 5, 5][..], &[1, 2], &[1, 2, 9], &[1, 2, 0, 0], &[1, 3], &[2, 0]]
+        );
+
+        let keys: Vec<_> =
+            db.contiguous_prefix_iter(&wtxn, &[1, 2]).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [&[1, 2][..], &[1, 2, 9], &[1, 2, 0, 0]]);
+
+        let keys: Vec<_> =
+            db.contiguous_prefix_iter(&wtxn, &[1]).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [&[1, 1, 5, 5, 5][..], &[1, 2], &[1, 2, 9], &[1, 2, 0, 0], &[1, 3]]);
+
+        // The seek follows the comparator, a short key is sorted before the longer ones.
+        let mut iter = db.contiguous_prefix_iter(&wtxn, &[1, 2]).unwrap();
+        iter.seek(&[1, 2, 5]).unwrap();
+        let keys: Vec<_> = iter.map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [&[1, 2, 9][..], &[1, 2, 0, 0]]);
+
+        assert_eq!(db.contiguous_prefix_iter(&wtxn, &[1, 4]).unwrap().count(), 0);
+        wtxn.abort();
+    }
+
+    #[test]
+    fn double_ended_iterators_stop_when_both_ends_meet() {
+        use crate::byteorder::BigEndian;
+        use crate::types::*;
+        use crate::{DatabaseFlags, EnvOpenOptions, PutFlags};
+
+        type BEU32 = U32<BigEndian>;
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<BEU32, Unit>(&mut wtxn, Some("plain")).unwrap();
+        for key in 1..=6 {
+            db.put(&mut wtxn, &key, &()).unwrap();
+        }
+
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().rev().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [6, 5, 4, 3, 2, 1]);
+
+        let mut iter = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(6));
+        assert_eq!(iter.next_back(), Some(5));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        drop(iter);
+
+        let keys: Vec<_> = db.range(&wtxn, &(2..5)).unwrap().rev().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [4, 3, 2]);
+
+        let mut iter = db.range(&wtxn, &(2..=5)).unwrap().map(|r| r.unwrap().0);
+        assert_eq!(iter.next_back(), Some(5));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        drop(iter);
+
+        // An end that left the range stops the other one.
+        let mut iter = db.range(&wtxn, &(2..=5)).unwrap().map(|r| r.unwrap().0);
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), [2, 3, 4, 5]);
+        assert_eq!(iter.next_back(), None);
+        drop(iter);
+
+        let mut iter = db.range(&wtxn, &(2..=5)).unwrap().map(|r| r.unwrap().0);
+        assert_eq!(iter.by_ref().rev().collect::<Vec<_>>(), [5, 4, 3, 2]);
+        assert_eq!(iter.next(), None);
+        drop(iter);
+
+        let db = env
+            .database_options()
+            .types::<Str, BEU32>()
+            .name("dups")
+            .flags(DatabaseFlags::DUP_SORT)
+            .create(&mut wtxn)
+            .unwrap();
+        for (key, data) in [("a", 1), ("ba", 1), ("ba", 2), ("bb", 1), ("bb", 2), ("c", 1)] {
+            db.put_with_flags(&mut wtxn, PutFlags::empty(), key, &data).unwrap();
+        }
+
+        let entries: Vec<_> =
+            db.prefix_iter(&wtxn, "b").unwrap().rev().map(|r| r.unwrap()).collect();
+        assert_eq!(entries, [("bb", 2), ("bb", 1), ("ba", 2), ("ba", 1)]);
+
+        let mut iter = db.prefix_iter(&wtxn, "b").unwrap().map(|r| r.unwrap());
+        assert_eq!(iter.next(), Some(("ba", 1)));
+        assert_eq!(iter.next_back(), Some(("bb", 2)));
+        assert_eq!(iter.next_back(), Some(("bb", 1)));
+        assert_eq!(iter.next(), Some(("ba", 2)));
+        assert_eq!(iter.next_back(), None);
+        drop(iter);
+
+        let mut iter = db.prefix_iter(&wtxn, "b").unwrap().map(|r| r.unwrap());
+        assert_eq!(iter.by_ref().count(), 4);
+        assert_eq!(iter.next_back(), None);
+        drop(iter);
+
+        let mut iter = db.iter(&wtxn).unwrap().move_between_keys().map(|r| r.unwrap().0);
+        assert_eq!(iter.next(), Some("a"));
+        assert_eq!(iter.next_back(), Some("c"));
+        assert_eq!(iter.next_back(), Some("bb"));
+        assert_eq!(iter.next(), Some("ba"));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        drop(iter);
+
+        wtxn.abort();
+    }
+
+    #[test]
+    fn seek_repositions_iterators_within_their_bounds() {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        use crate::byteorder::BigEndian;
+        use crate::types::*;
+        use crate::EnvOpenOptions;
+
+        type BEU32 = U32<BigEndian>;
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<BEU32, Unit>(&mut wtxn, Some("numbers")).unwrap();
+        for key in (10..=50).step_by(10) {
+            db.put(&mut wtxn, &key, &()).unwrap();
+        }
+
+        let mut iter = db.iter(&wtxn).unwrap();
+        iter.seek(&25).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((30, ())));
+        iter.seek(&10).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((10, ())));
+        assert_eq!(iter.next().transpose().unwrap(), Some((20, ())));
+        iter.seek(&40).unwrap();
+        assert_eq!(iter.last().transpose().unwrap(), Some((50, ())));
+
+        let mut iter = db.iter(&wtxn).unwrap();
+        iter.seek(&51).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        drop(iter);
+
+        let mut iter = db.rev_iter(&wtxn).unwrap();
+        iter.seek(&35).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((30, ())));
+        iter.seek(&50).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((50, ())));
+        iter.seek(&100).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((50, ())));
+        iter.seek(&5).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        drop(iter);
+
+        let mut range = db.range(&wtxn, &(Excluded(20), Included(40))).unwrap();
+        range.seek(&0).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((30, ())));
+        range.seek(&40).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((40, ())));
+        range.seek(&41).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), None);
+        drop(range);
+
+        let mut range = db.rev_range(&wtxn, &(Unbounded, Excluded(40))).unwrap();
+        range.seek(&45).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((30, ())));
+        range.seek(&20).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((20, ())));
+        assert_eq!(range.next().transpose().unwrap(), Some((10, ())));
+        assert_eq!(range.next().transpose().unwrap(), None);
+        drop(range);
+
+        let mut iter = db.iter_mut(&mut wtxn).unwrap();
+        iter.seek(&30).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((30, ())));
+        assert!(unsafe { iter.del_current() }.unwrap());
+        drop(iter);
+        let keys: Vec<_> = db.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [10, 20, 40, 50]);
+
+        let db = env.create_database::<Str, BEU32>(&mut wtxn, Some("words")).unwrap();
+        for (i, key) in ["apple", "banana", "bar", "bat", "cherry"].iter().enumerate() {
+            db.put(&mut wtxn, key, &(i as u32)).unwrap();
+        }
+
+        let mut iter = db.prefix_iter(&wtxn, "ba").unwrap();
+        iter.seek("a").unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some(("banana", 1)));
+        iter.seek("bas").unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some(("bat", 3)));
+        iter.seek("bb").unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        drop(iter);
+
+        let mut iter = db.rev_prefix_iter(&wtxn, "ba").unwrap();
+        iter.seek("z").unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some(("bat", 3)));
+        iter.seek("bas").unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some(("bar", 2)));
+        iter.seek("apple").unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        drop(iter);
+
+        wtxn.abort();
+    }
+
+    #[test]
+    fn seek_past_the_bounds_and_the_consumed_entries() {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        use crate::byteorder::BigEndian;
+        use crate::types::*;
+        use crate::EnvOpenOptions;
+
+        type BEU32 = U32<BigEndian>;
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<BEU32, Unit>(&mut wtxn, None).unwrap();
+        for key in (10..=50).step_by(10) {
+            db.put(&mut wtxn, &key, &()).unwrap();
+        }
+
+        // Seeking backward past the start bound goes back to the first entry of the range.
+        let mut range = db.range(&wtxn, &(Included(20), Unbounded)).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((20, ())));
+        assert_eq!(range.next().transpose().unwrap(), Some((30, ())));
+        range.seek(&5).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((20, ())));
+        drop(range);
+
+        let mut range = db.rev_range(&wtxn, &(Excluded(20), Unbounded)).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((50, ())));
+        range.seek(&10).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), None);
+        range.seek(&100).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((50, ())));
+        drop(range);
+
+        let mut range = db.range_mut(&mut wtxn, &(Excluded(20), Unbounded)).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((30, ())));
+        assert_eq!(range.next().transpose().unwrap(), Some((40, ())));
+        range.seek(&0).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((30, ())));
+        drop(range);
+
+        // Seeking the front onto entries the back has already returned yields nothing more.
+        let mut range = db.range(&wtxn, &(Included(10), Included(50))).unwrap();
+        assert_eq!(range.next_back().transpose().unwrap(), Some((50, ())));
+        assert_eq!(range.next_back().transpose().unwrap(), Some((40, ())));
+        range.seek(&35).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), None);
+        range.seek(&25).unwrap();
+        assert_eq!(range.next().transpose().unwrap(), Some((30, ())));
+        assert_eq!(range.next().transpose().unwrap(), None);
+        assert_eq!(range.next_back().transpose().unwrap(), None);
+        drop(range);
+
+        let mut iter = db.iter(&wtxn).unwrap();
+        assert_eq!(iter.next_back().transpose().unwrap(), Some((50, ())));
+        iter.seek(&50).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        assert_eq!(iter.next_back().transpose().unwrap(), None);
+        drop(iter);
+
+        wtxn.abort();
+    }
+
+    #[test]
+    fn seek_in_duplicates_and_double_ended_iterators() {
+        use crate::byteorder::BigEndian;
+        use crate::types::*;
+        use crate::{DatabaseFlags, EnvOpenOptions, PutFlags};
+
+        type BEU32 = U32<BigEndian>;
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<BEU32, BEU32>()
+            .flags(DatabaseFlags::DUP_SORT)
+            .create(&mut wtxn)
+            .unwrap();
+        for (key, data) in [(1, 1), (2, 1), (2, 2), (2, 3), (3, 1), (3, 2)] {
+            db.put_with_flags(&mut wtxn, PutFlags::empty(), &key, &data).unwrap();
+        }
+
+        let mut iter = db.iter(&wtxn).unwrap();
+        iter.seek(&2).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((2, 1)));
+        assert_eq!(iter.next().transpose().unwrap(), Some((2, 2)));
+        drop(iter);
+
+        let mut iter = db.iter(&wtxn).unwrap().move_between_keys();
+        iter.seek(&2).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((2, 1)));
+        assert_eq!(iter.next().transpose().unwrap(), Some((3, 1)));
+        drop(iter);
+
+        let mut iter = db.rev_iter(&wtxn).unwrap();
+        iter.seek(&2).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((2, 3)));
+        assert_eq!(iter.next().transpose().unwrap(), Some((2, 2)));
+        drop(iter);
+
+        let mut iter = db.rev_iter(&wtxn).unwrap().move_between_keys();
+        iter.seek(&2).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((2, 3)));
+        assert_eq!(iter.next().transpose().unwrap(), Some((1, 1)));
+        drop(iter);
+
+        // The front cannot be seeked past the entries returned by the back.
+        let mut iter = db.iter(&wtxn).unwrap();
+        assert_eq!(iter.next_back().transpose().unwrap(), Some((3, 2)));
+        iter.seek(&3).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some((3, 1)));
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        drop(iter);
+
+        let mut iter = db.iter(&wtxn).unwrap().move_between_keys();
+        assert_eq!(iter.next_back().transpose().unwrap(), Some((3, 2)));
+        iter.seek(&3).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        drop(iter);
+
+        // The back stops before the entry the front has been seeked on.
+        let mut iter = db.iter(&wtxn).unwrap();
+        iter.seek(&2).unwrap();
+        let entries: Vec<_> = iter.by_ref().rev().map(|r| r.unwrap()).collect();
+        assert_eq!(entries, [(3, 2), (3, 1), (2, 3), (2, 2), (2, 1)]);
+        assert_eq!(iter.next().transpose().unwrap(), None);
+        drop(iter);
+
+        wtxn.abort();
+    }
 }
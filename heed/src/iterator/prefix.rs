@@ -7,13 +7,14 @@ use types::LazyDecode;
 use crate::cursor::MoveOperation;
 use crate::env::DefaultComparator;
 use crate::iteration_method::{IterationMethod, MoveBetweenKeys, MoveThroughDuplicateValues};
+use crate::iterator::{move_on_prefix_seek, seek_backward, seek_forward, BackEnd};
 use crate::*;
 
 /// Advances `bytes` to the immediate lexicographic successor of equal length, as
 /// defined by the `C` comparator. If no successor exists (i.e., `bytes` is the maximal
 /// value), it remains unchanged and the function returns `false`. Otherwise, updates
 /// `bytes` and returns `true`.
-fn advance_prefix<C: LexicographicComparator>(bytes: &mut Vec<u8>) -> bool {
+pub(crate) fn advance_prefix<C: LexicographicComparator>(bytes: &mut Vec<u8>) -> bool {
     let mut idx = bytes.len();
     while idx > 0 && bytes[idx - 1] == C::max_elem() {
         idx -= 1;
@@ -47,6 +48,16 @@ fn retreat_prefix<C: LexicographicComparator>(bytes: &mut Vec<u8>) -> bool {
     true
 }
 
+/// Returns `true` if the key starts with a sequence of bytes
+/// the `C` comparator considers equal to the prefix.
+fn starts_with<C: LexicographicComparator>(
+    cursor: &RoCursor,
+    key: &[u8],
+    prefix: &[u8],
+) -> Result<bool> {
+    Ok(key.len() >= prefix.len() && cursor.compare_with::<C>(&key[..prefix.len()], prefix)?.is_eq())
+}
+
 fn move_on_prefix_end<'txn, C: LexicographicComparator>(
     cursor: &mut RoCursor<'txn>,
     prefix: &mut Vec<u8>,
@@ -68,22 +79,46 @@ pub struct RoPrefix<'txn, KC, DC, C = DefaultComparator, IM = MoveThroughDuplica
     cursor: RoCursor<'txn>,
     prefix: Vec<u8>,
     move_on_first: bool,
+    back: BackEnd<'txn>,
     _phantom: marker::PhantomData<(KC, DC, C, IM)>,
 }
 
 impl<'txn, KC, DC, C, IM> RoPrefix<'txn, KC, DC, C, IM> {
     pub(crate) fn new(cursor: RoCursor<'txn>, prefix: Vec<u8>) -> RoPrefix<'txn, KC, DC, C, IM> {
-        RoPrefix { cursor, prefix, move_on_first: true, _phantom: marker::PhantomData }
+        RoPrefix {
+            cursor,
+            prefix,
+            move_on_first: true,
+            back: BackEnd::new(),
+            _phantom: marker::PhantomData,
+        }
+    }
+
+    /// Move the iterator on the first entry of the prefix with a key greater than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: LexicographicComparator,
+        IM: IterationMethod,
+    {
+        let prefix = &self.prefix;
+        let seek = seek_forward::<KC>(&mut self.cursor, key, |cursor, key| {
+            move_on_prefix_seek::<C>(cursor, prefix, key)
+        })?;
+        self.move_on_first = seek.is_first();
+        self.back.front_seeked(seek.entry(), IM::MOVE_OPERATION)
     }
 
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RoPrefix<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RoPrefix<'txn, KC, DC, C, MoveBetweenKeys> {
         RoPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
             move_on_first: self.move_on_first,
+            back: self.back,
             _phantom: marker::PhantomData,
         }
     }
@@ -93,37 +128,39 @@ impl<'txn, KC, DC, C, IM> RoPrefix<'txn, KC, DC, C, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RoPrefix<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RoPrefix<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RoPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
             move_on_first: self.move_on_first,
+            back: self.back,
             _phantom: marker::PhantomData,
         }
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RoPrefix<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RoPrefix<'txn, KC2, DC2, C, IM> {
         RoPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
             move_on_first: self.move_on_first,
+            back: self.back,
             _phantom: marker::PhantomData,
         }
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RoPrefix<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RoPrefix<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RoPrefix<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RoPrefix<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RoPrefix<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RoPrefix<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
@@ -144,24 +181,26 @@ where
         } else {
             self.cursor.move_on_next(IM::MOVE_OPERATION)
         };
+        let (cursor, prefix) = (&self.cursor, &self.prefix);
+        let result = self
+            .back
+            .front_reached(result, IM::MOVE_OPERATION, |key| starts_with::<C>(cursor, key, prefix));
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
     }
 
     fn last(mut self) -> Option<Self::Item> {
+        if self.back.is_started() {
+            return self.next_back();
+        }
+
         let result = if self.move_on_first {
             move_on_prefix_end::<C>(&mut self.cursor, &mut self.prefix)
         } else {
@@ -178,16 +217,46 @@ where
         };
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
+            Ok(Some((key, data))) => match starts_with::<C>(&self.cursor, key, &self.prefix) {
+                Ok(true) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                },
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl<'txn, KC, DC, C, IM> DoubleEndedIterator for RoPrefix<'txn, KC, DC, C, IM>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+    C: LexicographicComparator,
+    IM: IterationMethod,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let prefix = &mut self.prefix;
+        let result = self.back.cursor(&self.cursor).and_then(|(cursor, opened)| {
+            if opened {
+                move_on_prefix_end::<C>(cursor, prefix)
+            } else {
+                cursor.move_on_prev(IM::MOVE_OPERATION)
             }
+        });
+        let (cursor, prefix) = (&self.cursor, &self.prefix);
+        let result = self
+            .back
+            .back_reached(result, IM::MOVE_OPERATION, |key| starts_with::<C>(cursor, key, prefix));
+
+        match result {
+            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
@@ -326,10 +395,25 @@ impl<'txn, KC, DC, C, IM> RwPrefix<'txn, KC, DC, C, IM> {
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
+    /// Move the iterator on the first entry of the prefix with a key greater than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: LexicographicComparator,
+    {
+        let prefix = &self.prefix;
+        let seek = seek_forward::<KC>(&mut self.cursor, key, |cursor, key| {
+            move_on_prefix_seek::<C>(cursor, prefix, key)
+        })?;
+        self.move_on_first = seek.is_first();
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RwPrefix<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RwPrefix<'txn, KC, DC, C, MoveBetweenKeys> {
         RwPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -343,7 +427,7 @@ impl<'txn, KC, DC, C, IM> RwPrefix<'txn, KC, DC, C, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RwPrefix<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RwPrefix<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RwPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -353,7 +437,7 @@ impl<'txn, KC, DC, C, IM> RwPrefix<'txn, KC, DC, C, IM> {
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RwPrefix<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RwPrefix<'txn, KC2, DC2, C, IM> {
         RwPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -363,17 +447,17 @@ impl<'txn, KC, DC, C, IM> RwPrefix<'txn, KC, DC, C, IM> {
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RwPrefix<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RwPrefix<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RwPrefix<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RwPrefix<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RwPrefix<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RwPrefix<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
@@ -396,16 +480,14 @@ where
         };
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match starts_with::<C>(&self.cursor, key, &self.prefix) {
+                Ok(true) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                },
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
@@ -428,16 +510,14 @@ where
         };
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match starts_with::<C>(&self.cursor, key, &self.prefix) {
+                Ok(true) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                },
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
@@ -463,10 +543,25 @@ impl<'txn, KC, DC, C, IM> RoRevPrefix<'txn, KC, DC, C, IM> {
         RoRevPrefix { cursor, prefix, move_on_last: true, _phantom: marker::PhantomData }
     }
 
+    /// Move the iterator on the last entry of the prefix with a key lower than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: LexicographicComparator,
+    {
+        let prefix = &self.prefix;
+        self.move_on_last = seek_backward::<KC>(&mut self.cursor, key, |cursor, key| {
+            Ok(cursor.compare_with::<C>(key, prefix)?.is_gt()
+                && !starts_with::<C>(cursor, key, prefix)?)
+        })?;
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RoRevPrefix<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RoRevPrefix<'txn, KC, DC, C, MoveBetweenKeys> {
         RoRevPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -480,7 +575,7 @@ impl<'txn, KC, DC, C, IM> RoRevPrefix<'txn, KC, DC, C, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RoRevPrefix<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RoRevPrefix<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RoRevPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -490,7 +585,7 @@ impl<'txn, KC, DC, C, IM> RoRevPrefix<'txn, KC, DC, C, IM> {
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RoRevPrefix<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RoRevPrefix<'txn, KC2, DC2, C, IM> {
         RoRevPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -500,17 +595,17 @@ impl<'txn, KC, DC, C, IM> RoRevPrefix<'txn, KC, DC, C, IM> {
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RoRevPrefix<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RoRevPrefix<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RoRevPrefix<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RoRevPrefix<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RoRevPrefix<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RoRevPrefix<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
@@ -533,16 +628,14 @@ where
         };
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match starts_with::<C>(&self.cursor, key, &self.prefix) {
+                Ok(true) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                },
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
@@ -564,16 +657,14 @@ where
         };
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match starts_with::<C>(&self.cursor, key, &self.prefix) {
+                Ok(true) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                },
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
@@ -712,10 +803,25 @@ impl<'txn, KC, DC, C, IM> RwRevPrefix<'txn, KC, DC, C, IM> {
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
+    /// Move the iterator on the last entry of the prefix with a key lower than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: LexicographicComparator,
+    {
+        let prefix = &self.prefix;
+        self.move_on_last = seek_backward::<KC>(&mut self.cursor, key, |cursor, key| {
+            Ok(cursor.compare_with::<C>(key, prefix)?.is_gt()
+                && !starts_with::<C>(cursor, key, prefix)?)
+        })?;
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RwRevPrefix<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RwRevPrefix<'txn, KC, DC, C, MoveBetweenKeys> {
         RwRevPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -729,7 +835,7 @@ impl<'txn, KC, DC, C, IM> RwRevPrefix<'txn, KC, DC, C, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RwRevPrefix<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RwRevPrefix<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RwRevPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -739,7 +845,7 @@ impl<'txn, KC, DC, C, IM> RwRevPrefix<'txn, KC, DC, C, IM> {
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RwRevPrefix<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RwRevPrefix<'txn, KC2, DC2, C, IM> {
         RwRevPrefix {
             cursor: self.cursor,
             prefix: self.prefix,
@@ -749,17 +855,17 @@ impl<'txn, KC, DC, C, IM> RwRevPrefix<'txn, KC, DC, C, IM> {
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RwRevPrefix<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RwRevPrefix<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RwRevPrefix<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RwRevPrefix<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RwRevPrefix<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RwRevPrefix<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
@@ -782,16 +888,14 @@ where
         };
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match starts_with::<C>(&self.cursor, key, &self.prefix) {
+                Ok(true) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                },
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
@@ -813,16 +917,14 @@ where
         };
 
         match result {
-            Ok(Some((key, data))) => {
-                if key.starts_with(&self.prefix) {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match starts_with::<C>(&self.cursor, key, &self.prefix) {
+                Ok(true) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                    (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                },
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
@@ -5,18 +5,21 @@ use std::ops::Bound;
 use types::LazyDecode;
 
 use crate::cursor::MoveOperation;
+use crate::env::DefaultComparator;
 use crate::iteration_method::{IterationMethod, MoveBetweenKeys, MoveThroughDuplicateValues};
+use crate::iterator::{seek_backward, seek_forward, BackEnd};
 use crate::*;
 
-fn move_on_range_end<'txn>(
+fn move_on_range_end<'txn, C: Comparator>(
     cursor: &mut RoCursor<'txn>,
     end_bound: &Bound<Vec<u8>>,
 ) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
     match end_bound {
-        Bound::Included(end) => match cursor.move_on_key_greater_than_or_equal_to(end) {
-            Ok(Some((key, data))) if key == &end[..] => Ok(Some((key, data))),
-            Ok(_) => cursor.move_on_prev(MoveOperation::NoDup),
-            Err(e) => Err(e),
+        Bound::Included(end) => match cursor.move_on_key_greater_than_or_equal_to(end)? {
+            Some((key, data)) if cursor.compare_with::<C>(key, end)?.is_eq() => {
+                Ok(Some((key, data)))
+            }
+            _ => cursor.move_on_prev(MoveOperation::NoDup),
         },
         Bound::Excluded(end) => cursor
             .move_on_key_greater_than_or_equal_to(end)
@@ -25,51 +28,111 @@ fn move_on_range_end<'txn>(
     }
 }
 
-fn move_on_range_start<'txn>(
+fn move_on_range_start<'txn, C: Comparator>(
     cursor: &mut RoCursor<'txn>,
     start_bound: &mut Bound<Vec<u8>>,
 ) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
     match start_bound {
         Bound::Included(start) => cursor.move_on_key_greater_than_or_equal_to(start),
         Bound::Excluded(start) => match cursor.move_on_key_greater_than_or_equal_to(start)? {
-            Some((key, _)) if key == start => cursor.move_on_next(MoveOperation::NoDup),
+            Some((key, _)) if cursor.compare_with::<C>(key, start)?.is_eq() => {
+                cursor.move_on_next(MoveOperation::NoDup)
+            }
             result => Ok(result),
         },
         Bound::Unbounded => cursor.move_on_first(MoveOperation::NoDup),
     }
 }
 
+/// Moves the cursor on the first entry of the range greater than or equal to `key`.
+fn move_on_range_seek<'txn, C: Comparator>(
+    cursor: &mut RoCursor<'txn>,
+    start_bound: &mut Bound<Vec<u8>>,
+    key: &[u8],
+) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
+    if after_range_start::<C>(cursor, start_bound, key)? {
+        cursor.move_on_key_greater_than_or_equal_to(key)
+    } else {
+        move_on_range_start::<C>(cursor, start_bound)
+    }
+}
+
+/// Returns `true` if `key` is not before the start of the range.
+fn after_range_start<C: Comparator>(
+    cursor: &RoCursor,
+    start_bound: &Bound<Vec<u8>>,
+    key: &[u8],
+) -> Result<bool> {
+    match start_bound {
+        Bound::Included(start) => Ok(cursor.compare_with::<C>(key, start)?.is_ge()),
+        Bound::Excluded(start) => Ok(cursor.compare_with::<C>(key, start)?.is_gt()),
+        Bound::Unbounded => Ok(true),
+    }
+}
+
+/// Returns `true` if `key` is not after the end of the range.
+fn before_range_end<C: Comparator>(
+    cursor: &RoCursor,
+    end_bound: &Bound<Vec<u8>>,
+    key: &[u8],
+) -> Result<bool> {
+    match end_bound {
+        Bound::Included(end) => Ok(cursor.compare_with::<C>(key, end)?.is_le()),
+        Bound::Excluded(end) => Ok(cursor.compare_with::<C>(key, end)?.is_lt()),
+        Bound::Unbounded => Ok(true),
+    }
+}
+
 /// A read-only range iterator structure.
-pub struct RoRange<'txn, KC, DC, IM = MoveThroughDuplicateValues> {
+pub struct RoRange<'txn, KC, DC, C = DefaultComparator, IM = MoveThroughDuplicateValues> {
     cursor: RoCursor<'txn>,
     move_on_start: bool,
+    back: BackEnd<'txn>,
     start_bound: Bound<Vec<u8>>,
     end_bound: Bound<Vec<u8>>,
-    _phantom: marker::PhantomData<(KC, DC, IM)>,
+    _phantom: marker::PhantomData<(KC, DC, C, IM)>,
 }
 
-impl<'txn, KC, DC, IM> RoRange<'txn, KC, DC, IM> {
+impl<'txn, KC, DC, C, IM> RoRange<'txn, KC, DC, C, IM> {
     pub(crate) fn new(
         cursor: RoCursor<'txn>,
         start_bound: Bound<Vec<u8>>,
         end_bound: Bound<Vec<u8>>,
-    ) -> RoRange<'txn, KC, DC, IM> {
+    ) -> RoRange<'txn, KC, DC, C, IM> {
         RoRange {
             cursor,
             move_on_start: true,
+            back: BackEnd::new(),
             start_bound,
             end_bound,
             _phantom: marker::PhantomData,
         }
     }
 
+    /// Move the iterator on the first entry of the range with a key greater than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: Comparator,
+        IM: IterationMethod,
+    {
+        let start_bound = &mut self.start_bound;
+        let seek = seek_forward::<KC>(&mut self.cursor, key, |cursor, key| {
+            move_on_range_seek::<C>(cursor, start_bound, key)
+        })?;
+        self.move_on_start = seek.is_first();
+        self.back.front_seeked(seek.entry(), IM::MOVE_OPERATION)
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RoRange<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RoRange<'txn, KC, DC, C, MoveBetweenKeys> {
         RoRange {
             cursor: self.cursor,
             move_on_start: self.move_on_start,
+            back: self.back,
             start_bound: self.start_bound,
             end_bound: self.end_bound,
             _phantom: marker::PhantomData,
@@ -81,10 +144,11 @@ impl<'txn, KC, DC, IM> RoRange<'txn, KC, DC, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RoRange<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RoRange<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RoRange {
             cursor: self.cursor,
             move_on_start: self.move_on_start,
+            back: self.back,
             start_bound: self.start_bound,
             end_bound: self.end_bound,
             _phantom: marker::PhantomData,
@@ -92,10 +156,11 @@ impl<'txn, KC, DC, IM> RoRange<'txn, KC, DC, IM> {
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RoRange<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RoRange<'txn, KC2, DC2, C, IM> {
         RoRange {
             cursor: self.cursor,
             move_on_start: self.move_on_start,
+            back: self.back,
             start_bound: self.start_bound,
             end_bound: self.end_bound,
             _phantom: marker::PhantomData,
@@ -103,23 +168,24 @@ impl<'txn, KC, DC, IM> RoRange<'txn, KC, DC, IM> {
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RoRange<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RoRange<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RoRange<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RoRange<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RoRange<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RoRange<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
 
-impl<'txn, KC, DC, IM> Iterator for RoRange<'txn, KC, DC, IM>
+impl<'txn, KC, DC, C, IM> Iterator for RoRange<'txn, KC, DC, C, IM>
 where
+    C: Comparator,
     KC: BytesDecode<'txn>,
     DC: BytesDecode<'txn>,
     IM: IterationMethod,
@@ -129,38 +195,35 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let result = if self.move_on_start {
             self.move_on_start = false;
-            move_on_range_start(&mut self.cursor, &mut self.start_bound)
+            move_on_range_start::<C>(&mut self.cursor, &mut self.start_bound)
         } else {
             self.cursor.move_on_next(IM::MOVE_OPERATION)
         };
+        let (cursor, end_bound) = (&self.cursor, &self.end_bound);
+        let result = self.back.front_reached(result, IM::MOVE_OPERATION, |key| {
+            before_range_end::<C>(cursor, end_bound, key)
+        });
 
         match result {
-            Ok(Some((key, data))) => {
-                let must_be_returned = match &self.end_bound {
-                    Bound::Included(end) => key <= end,
-                    Bound::Excluded(end) => key < end,
-                    Bound::Unbounded => true,
-                };
-
-                if must_be_returned {
-                    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
-                        (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
-                    }
-                } else {
-                    None
-                }
-            }
+            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+            },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
     }
 
     fn last(mut self) -> Option<Self::Item> {
+        if self.back.is_started() {
+            return self.next_back();
+        }
+
         let result = if self.move_on_start {
-            move_on_range_end(&mut self.cursor, &self.end_bound)
+            move_on_range_end::<C>(&mut self.cursor, &self.end_bound)
         } else {
-            match (self.cursor.current(), move_on_range_end(&mut self.cursor, &self.end_bound)) {
+            match (self.cursor.current(), move_on_range_end::<C>(&mut self.cursor, &self.end_bound))
+            {
                 (Ok(Some((ckey, _))), Ok(Some((key, data)))) if ckey != key => {
                     Ok(Some((key, data)))
                 }
@@ -171,11 +234,11 @@ where
 
         match result {
             Ok(Some((key, data))) => {
-                let must_be_returned = match &self.start_bound {
-                    Bound::Included(start) => key >= start,
-                    Bound::Excluded(start) => key > start,
-                    Bound::Unbounded => true,
-                };
+                let must_be_returned =
+                    match after_range_start::<C>(&self.cursor, &self.start_bound, key) {
+                        Ok(must_be_returned) => must_be_returned,
+                        Err(e) => return Some(Err(e)),
+                    };
 
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -192,27 +255,59 @@ where
     }
 }
 
-impl<KC, DC, IM> fmt::Debug for RoRange<'_, KC, DC, IM> {
+impl<'txn, KC, DC, C, IM> DoubleEndedIterator for RoRange<'txn, KC, DC, C, IM>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+    C: Comparator,
+    IM: IterationMethod,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let end_bound = &self.end_bound;
+        let result = self.back.cursor(&self.cursor).and_then(|(cursor, opened)| {
+            if opened {
+                move_on_range_end::<C>(cursor, end_bound)
+            } else {
+                cursor.move_on_prev(IM::MOVE_OPERATION)
+            }
+        });
+        let (cursor, start_bound) = (&self.cursor, &self.start_bound);
+        let result = self.back.back_reached(result, IM::MOVE_OPERATION, |key| {
+            after_range_start::<C>(cursor, start_bound, key)
+        });
+
+        match result {
+            Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+                (Ok(key), Ok(data)) => Some(Ok((key, data))),
+                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+            },
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl<KC, DC, C, IM> fmt::Debug for RoRange<'_, KC, DC, C, IM> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RoRange").finish()
     }
 }
 
 /// A read-write range iterator structure.
-pub struct RwRange<'txn, KC, DC, IM = MoveThroughDuplicateValues> {
+pub struct RwRange<'txn, KC, DC, C = DefaultComparator, IM = MoveThroughDuplicateValues> {
     cursor: RwCursor<'txn>,
     move_on_start: bool,
     start_bound: Bound<Vec<u8>>,
     end_bound: Bound<Vec<u8>>,
-    _phantom: marker::PhantomData<(KC, DC, IM)>,
+    _phantom: marker::PhantomData<(KC, DC, C, IM)>,
 }
 
-impl<'txn, KC, DC, IM> RwRange<'txn, KC, DC, IM> {
+impl<'txn, KC, DC, C, IM> RwRange<'txn, KC, DC, C, IM> {
     pub(crate) fn new(
         cursor: RwCursor<'txn>,
         start_bound: Bound<Vec<u8>>,
         end_bound: Bound<Vec<u8>>,
-    ) -> RwRange<'txn, KC, DC, IM> {
+    ) -> RwRange<'txn, KC, DC, C, IM> {
         RwRange {
             cursor,
             move_on_start: true,
@@ -335,10 +430,25 @@ impl<'txn, KC, DC, IM> RwRange<'txn, KC, DC, IM> {
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
+    /// Move the iterator on the first entry of the range with a key greater than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: Comparator,
+    {
+        let start_bound = &mut self.start_bound;
+        let seek = seek_forward::<KC>(&mut self.cursor, key, |cursor, key| {
+            move_on_range_seek::<C>(cursor, start_bound, key)
+        })?;
+        self.move_on_start = seek.is_first();
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RwRange<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RwRange<'txn, KC, DC, C, MoveBetweenKeys> {
         RwRange {
             cursor: self.cursor,
             move_on_start: self.move_on_start,
@@ -353,7 +463,7 @@ impl<'txn, KC, DC, IM> RwRange<'txn, KC, DC, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RwRange<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RwRange<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RwRange {
             cursor: self.cursor,
             move_on_start: self.move_on_start,
@@ -364,7 +474,7 @@ impl<'txn, KC, DC, IM> RwRange<'txn, KC, DC, IM> {
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RwRange<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RwRange<'txn, KC2, DC2, C, IM> {
         RwRange {
             cursor: self.cursor,
             move_on_start: self.move_on_start,
@@ -375,23 +485,24 @@ impl<'txn, KC, DC, IM> RwRange<'txn, KC, DC, IM> {
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RwRange<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RwRange<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RwRange<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RwRange<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RwRange<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RwRange<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
 
-impl<'txn, KC, DC, IM> Iterator for RwRange<'txn, KC, DC, IM>
+impl<'txn, KC, DC, C, IM> Iterator for RwRange<'txn, KC, DC, C, IM>
 where
+    C: Comparator,
     KC: BytesDecode<'txn>,
     DC: BytesDecode<'txn>,
     IM: IterationMethod,
@@ -401,18 +512,18 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let result = if self.move_on_start {
             self.move_on_start = false;
-            move_on_range_start(&mut self.cursor, &mut self.start_bound)
+            move_on_range_start::<C>(&mut self.cursor, &mut self.start_bound)
         } else {
             self.cursor.move_on_next(IM::MOVE_OPERATION)
         };
 
         match result {
             Ok(Some((key, data))) => {
-                let must_be_returned = match self.end_bound {
-                    Bound::Included(ref end) => key <= end,
-                    Bound::Excluded(ref end) => key < end,
-                    Bound::Unbounded => true,
-                };
+                let must_be_returned =
+                    match before_range_end::<C>(&self.cursor, &self.end_bound, key) {
+                        Ok(must_be_returned) => must_be_returned,
+                        Err(e) => return Some(Err(e)),
+                    };
 
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -430,9 +541,10 @@ where
 
     fn last(mut self) -> Option<Self::Item> {
         let result = if self.move_on_start {
-            move_on_range_end(&mut self.cursor, &self.end_bound)
+            move_on_range_end::<C>(&mut self.cursor, &self.end_bound)
         } else {
-            match (self.cursor.current(), move_on_range_end(&mut self.cursor, &self.end_bound)) {
+            match (self.cursor.current(), move_on_range_end::<C>(&mut self.cursor, &self.end_bound))
+            {
                 (Ok(Some((ckey, _))), Ok(Some((key, data)))) if ckey != key => {
                     Ok(Some((key, data)))
                 }
@@ -443,11 +555,11 @@ where
 
         match result {
             Ok(Some((key, data))) => {
-                let must_be_returned = match &self.start_bound {
-                    Bound::Included(start) => key >= start,
-                    Bound::Excluded(start) => key > start,
-                    Bound::Unbounded => true,
-                };
+                let must_be_returned =
+                    match after_range_start::<C>(&self.cursor, &self.start_bound, key) {
+                        Ok(must_be_returned) => must_be_returned,
+                        Err(e) => return Some(Err(e)),
+                    };
 
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -464,27 +576,27 @@ where
     }
 }
 
-impl<KC, DC, IM> fmt::Debug for RwRange<'_, KC, DC, IM> {
+impl<KC, DC, C, IM> fmt::Debug for RwRange<'_, KC, DC, C, IM> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RwRange").finish()
     }
 }
 
 /// A reverse read-only range iterator structure.
-pub struct RoRevRange<'txn, KC, DC, IM = MoveThroughDuplicateValues> {
+pub struct RoRevRange<'txn, KC, DC, C = DefaultComparator, IM = MoveThroughDuplicateValues> {
     cursor: RoCursor<'txn>,
     move_on_end: bool,
     start_bound: Bound<Vec<u8>>,
     end_bound: Bound<Vec<u8>>,
-    _phantom: marker::PhantomData<(KC, DC, IM)>,
+    _phantom: marker::PhantomData<(KC, DC, C, IM)>,
 }
 
-impl<'txn, KC, DC, IM> RoRevRange<'txn, KC, DC, IM> {
+impl<'txn, KC, DC, C, IM> RoRevRange<'txn, KC, DC, C, IM> {
     pub(crate) fn new(
         cursor: RoCursor<'txn>,
         start_bound: Bound<Vec<u8>>,
         end_bound: Bound<Vec<u8>>,
-    ) -> RoRevRange<'txn, KC, DC, IM> {
+    ) -> RoRevRange<'txn, KC, DC, C, IM> {
         RoRevRange {
             cursor,
             move_on_end: true,
@@ -494,10 +606,24 @@ impl<'txn, KC, DC, IM> RoRevRange<'txn, KC, DC, IM> {
         }
     }
 
+    /// Move the iterator on the last entry of the range with a key lower than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: Comparator,
+    {
+        let end_bound = &self.end_bound;
+        self.move_on_end = seek_backward::<KC>(&mut self.cursor, key, |cursor, key| {
+            Ok(!before_range_end::<C>(cursor, end_bound, key)?)
+        })?;
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RoRevRange<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RoRevRange<'txn, KC, DC, C, MoveBetweenKeys> {
         RoRevRange {
             cursor: self.cursor,
             move_on_end: self.move_on_end,
@@ -512,7 +638,7 @@ impl<'txn, KC, DC, IM> RoRevRange<'txn, KC, DC, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RoRevRange<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RoRevRange<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RoRevRange {
             cursor: self.cursor,
             move_on_end: self.move_on_end,
@@ -523,7 +649,7 @@ impl<'txn, KC, DC, IM> RoRevRange<'txn, KC, DC, IM> {
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RoRevRange<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RoRevRange<'txn, KC2, DC2, C, IM> {
         RoRevRange {
             cursor: self.cursor,
             move_on_end: self.move_on_end,
@@ -534,23 +660,24 @@ impl<'txn, KC, DC, IM> RoRevRange<'txn, KC, DC, IM> {
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RoRevRange<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RoRevRange<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RoRevRange<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RoRevRange<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RoRevRange<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RoRevRange<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
 
-impl<'txn, KC, DC, IM> Iterator for RoRevRange<'txn, KC, DC, IM>
+impl<'txn, KC, DC, C, IM> Iterator for RoRevRange<'txn, KC, DC, C, IM>
 where
+    C: Comparator,
     KC: BytesDecode<'txn>,
     DC: BytesDecode<'txn>,
     IM: IterationMethod,
@@ -560,18 +687,18 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let result = if self.move_on_end {
             self.move_on_end = false;
-            move_on_range_end(&mut self.cursor, &self.end_bound)
+            move_on_range_end::<C>(&mut self.cursor, &self.end_bound)
         } else {
             self.cursor.move_on_prev(IM::MOVE_OPERATION)
         };
 
         match result {
             Ok(Some((key, data))) => {
-                let must_be_returned = match &self.start_bound {
-                    Bound::Included(start) => key >= start,
-                    Bound::Excluded(start) => key > start,
-                    Bound::Unbounded => true,
-                };
+                let must_be_returned =
+                    match after_range_start::<C>(&self.cursor, &self.start_bound, key) {
+                        Ok(must_be_returned) => must_be_returned,
+                        Err(e) => return Some(Err(e)),
+                    };
 
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -589,10 +716,10 @@ where
 
     fn last(mut self) -> Option<Self::Item> {
         let result = if self.move_on_end {
-            move_on_range_start(&mut self.cursor, &mut self.start_bound)
+            move_on_range_start::<C>(&mut self.cursor, &mut self.start_bound)
         } else {
             let current = self.cursor.current();
-            let start = move_on_range_start(&mut self.cursor, &mut self.start_bound);
+            let start = move_on_range_start::<C>(&mut self.cursor, &mut self.start_bound);
             match (current, start) {
                 (Ok(Some((ckey, _))), Ok(Some((key, data)))) if ckey != key => {
                     Ok(Some((key, data)))
@@ -604,11 +731,11 @@ where
 
         match result {
             Ok(Some((key, data))) => {
-                let must_be_returned = match &self.end_bound {
-                    Bound::Included(end) => key <= end,
-                    Bound::Excluded(end) => key < end,
-                    Bound::Unbounded => true,
-                };
+                let must_be_returned =
+                    match before_range_end::<C>(&self.cursor, &self.end_bound, key) {
+                        Ok(must_be_returned) => must_be_returned,
+                        Err(e) => return Some(Err(e)),
+                    };
 
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -625,27 +752,27 @@ where
     }
 }
 
-impl<KC, DC, IM> fmt::Debug for RoRevRange<'_, KC, DC, IM> {
+impl<KC, DC, C, IM> fmt::Debug for RoRevRange<'_, KC, DC, C, IM> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RoRevRange").finish()
     }
 }
 
 /// A reverse read-write range iterator structure.
-pub struct RwRevRange<'txn, KC, DC, IM = MoveThroughDuplicateValues> {
+pub struct RwRevRange<'txn, KC, DC, C = DefaultComparator, IM = MoveThroughDuplicateValues> {
     cursor: RwCursor<'txn>,
     move_on_end: bool,
     start_bound: Bound<Vec<u8>>,
     end_bound: Bound<Vec<u8>>,
-    _phantom: marker::PhantomData<(KC, DC, IM)>,
+    _phantom: marker::PhantomData<(KC, DC, C, IM)>,
 }
 
-impl<'txn, KC, DC, IM> RwRevRange<'txn, KC, DC, IM> {
+impl<'txn, KC, DC, C, IM> RwRevRange<'txn, KC, DC, C, IM> {
     pub(crate) fn new(
         cursor: RwCursor<'txn>,
         start_bound: Bound<Vec<u8>>,
         end_bound: Bound<Vec<u8>>,
-    ) -> RwRevRange<'txn, KC, DC, IM> {
+    ) -> RwRevRange<'txn, KC, DC, C, IM> {
         RwRevRange {
             cursor,
             move_on_end: true,
@@ -768,10 +895,24 @@ impl<'txn, KC, DC, IM> RwRevRange<'txn, KC, DC, IM> {
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
+    /// Move the iterator on the last entry of the range with a key lower than or
+    /// equal to the given one, it is the entry returned by the next call to `next`.
+    pub fn seek<'a>(&mut self, key: &'a KC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        C: Comparator,
+    {
+        let end_bound = &self.end_bound;
+        self.move_on_end = seek_backward::<KC>(&mut self.cursor, key, |cursor, key| {
+            Ok(!before_range_end::<C>(cursor, end_bound, key)?)
+        })?;
+        Ok(())
+    }
+
     /// Move on the first value of keys, ignoring duplicate values.
     ///
     /// For more info, see [`RoIter::move_between_keys`].
-    pub fn move_between_keys(self) -> RwRevRange<'txn, KC, DC, MoveBetweenKeys> {
+    pub fn move_between_keys(self) -> RwRevRange<'txn, KC, DC, C, MoveBetweenKeys> {
         RwRevRange {
             cursor: self.cursor,
             move_on_end: self.move_on_end,
@@ -786,7 +927,7 @@ impl<'txn, KC, DC, IM> RwRevRange<'txn, KC, DC, IM> {
     /// For more info, see [`RoIter::move_through_duplicate_values`].
     pub fn move_through_duplicate_values(
         self,
-    ) -> RwRevRange<'txn, KC, DC, MoveThroughDuplicateValues> {
+    ) -> RwRevRange<'txn, KC, DC, C, MoveThroughDuplicateValues> {
         RwRevRange {
             cursor: self.cursor,
             move_on_end: self.move_on_end,
@@ -797,7 +938,7 @@ impl<'txn, KC, DC, IM> RwRevRange<'txn, KC, DC, IM> {
     }
 
     /// Change the codec types of this iterator, specifying the codecs.
-    pub fn remap_types<KC2, DC2>(self) -> RwRevRange<'txn, KC2, DC2, IM> {
+    pub fn remap_types<KC2, DC2>(self) -> RwRevRange<'txn, KC2, DC2, C, IM> {
         RwRevRange {
             cursor: self.cursor,
             move_on_end: self.move_on_end,
@@ -808,23 +949,24 @@ impl<'txn, KC, DC, IM> RwRevRange<'txn, KC, DC, IM> {
     }
 
     /// Change the key codec type of this iterator, specifying the new codec.
-    pub fn remap_key_type<KC2>(self) -> RwRevRange<'txn, KC2, DC, IM> {
+    pub fn remap_key_type<KC2>(self) -> RwRevRange<'txn, KC2, DC, C, IM> {
         self.remap_types::<KC2, DC>()
     }
 
     /// Change the data codec type of this iterator, specifying the new codec.
-    pub fn remap_data_type<DC2>(self) -> RwRevRange<'txn, KC, DC2, IM> {
+    pub fn remap_data_type<DC2>(self) -> RwRevRange<'txn, KC, DC2, C, IM> {
         self.remap_types::<KC, DC2>()
     }
 
     /// Wrap the data bytes into a lazy decoder.
-    pub fn lazily_decode_data(self) -> RwRevRange<'txn, KC, LazyDecode<DC>, IM> {
+    pub fn lazily_decode_data(self) -> RwRevRange<'txn, KC, LazyDecode<DC>, C, IM> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
 }
 
-impl<'txn, KC, DC, IM> Iterator for RwRevRange<'txn, KC, DC, IM>
+impl<'txn, KC, DC, C, IM> Iterator for RwRevRange<'txn, KC, DC, C, IM>
 where
+    C: Comparator,
     KC: BytesDecode<'txn>,
     DC: BytesDecode<'txn>,
     IM: IterationMethod,
@@ -834,18 +976,18 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let result = if self.move_on_end {
             self.move_on_end = false;
-            move_on_range_end(&mut self.cursor, &self.end_bound)
+            move_on_range_end::<C>(&mut self.cursor, &self.end_bound)
         } else {
             self.cursor.move_on_prev(IM::MOVE_OPERATION)
         };
 
         match result {
             Ok(Some((key, data))) => {
-                let must_be_returned = match &self.start_bound {
-                    Bound::Included(start) => key >= start,
-                    Bound::Excluded(start) => key > start,
-                    Bound::Unbounded => true,
-                };
+                let must_be_returned =
+                    match after_range_start::<C>(&self.cursor, &self.start_bound, key) {
+                        Ok(must_be_returned) => must_be_returned,
+                        Err(e) => return Some(Err(e)),
+                    };
 
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -863,10 +1005,10 @@ where
 
     fn last(mut self) -> Option<Self::Item> {
         let result = if self.move_on_end {
-            move_on_range_start(&mut self.cursor, &mut self.start_bound)
+            move_on_range_start::<C>(&mut self.cursor, &mut self.start_bound)
         } else {
             let current = self.cursor.current();
-            let start = move_on_range_start(&mut self.cursor, &mut self.start_bound);
+            let start = move_on_range_start::<C>(&mut self.cursor, &mut self.start_bound);
             match (current, start) {
                 (Ok(Some((ckey, _))), Ok(Some((key, data)))) if ckey != key => {
                     Ok(Some((key, data)))
@@ -878,11 +1020,11 @@ where
 
         match result {
             Ok(Some((key, data))) => {
-                let must_be_returned = match &self.end_bound {
-                    Bound::Included(end) => key <= end,
-                    Bound::Excluded(end) => key < end,
-                    Bound::Unbounded => true,
-                };
+                let must_be_returned =
+                    match before_range_end::<C>(&self.cursor, &self.end_bound, key) {
+                        Ok(must_be_returned) => must_be_returned,
+                        Err(e) => return Some(Err(e)),
+                    };
 
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
@@ -899,7 +1041,7 @@ where
     }
 }
 
-impl<KC, DC, IM> fmt::Debug for RwRevRange<'_, KC, DC, IM> {
+impl<KC, DC, C, IM> fmt::Debug for RwRevRange<'_, KC, DC, C, IM> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RwRevRange").finish()
     }
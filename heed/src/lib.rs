@@ -48,12 +48,20 @@
 //! ```
 #![warn(missing_docs)]
 
+mod backup;
+mod check;
+mod codec_context;
+mod comparator;
 mod cursor;
 mod database;
 mod env;
+mod indexed;
 pub mod iteration_method;
 mod iterator;
 mod mdb;
+mod metadata;
+mod migration;
+mod namespace;
 mod reserved_space;
 mod txn;
 
@@ -63,21 +71,41 @@ use std::{error, fmt, io, mem, result};
 use heed_traits as traits;
 pub use {bytemuck, byteorder, heed_types as types};
 
+pub use self::backup::{BackupHandle, BackupScheduler, Snapshot};
+pub use self::check::{Anomaly, CheckReport, DatabaseReport};
+pub use self::codec_context::{ContextDatabase, ContextIter, ContextPrefix, ContextRange};
+pub use self::comparator::{
+    AsciiCaseInsensitiveComparator, NativeEndianComparator, NaturalComparator,
+    ReverseLexicographicComparator,
+};
 use self::cursor::{RoCursor, RwCursor};
 pub use self::database::{Database, DatabaseOpenOptions};
 pub use self::env::{
-    env_closing_event, CompactionOption, Env, EnvClosingEvent, EnvInfo, EnvOpenOptions,
+    env_closing_event, CompactionOption, DefaultComparator, Env, EnvClosingEvent, EnvInfo,
+    EnvOpenOptions,
 };
+pub use self::indexed::{IndexedDatabase, SecondaryIndex};
 pub use self::iterator::{
-    RoIter, RoPrefix, RoRange, RoRevIter, RoRevPrefix, RoRevRange, RwIter, RwPrefix, RwRange,
-    RwRevIter, RwRevPrefix, RwRevRange,
+    RoContiguousPrefix, RoIter, RoPrefix, RoRange, RoRevIter, RoRevPrefix, RoRevRange,
+    RwContiguousPrefix, RwIter, RwPrefix, RwRange, RwRevIter, RwRevPrefix, RwRevRange,
 };
 pub use self::mdb::error::Error as MdbError;
 use self::mdb::ffi::{from_val, into_val};
 pub use self::mdb::flags::{DatabaseFlags, EnvFlags, PutFlags};
+pub use self::metadata::{TypeMetadata, TYPE_METADATA_DATABASE_NAME};
+pub use self::migration::{
+    DatabaseMigration, MigrationReport, MigrationStep, Migrator, SCHEMA_VERSIONS_DATABASE_NAME,
+};
+pub use self::namespace::{Namespace, NamespaceIter, NamespaceRange};
 pub use self::reserved_space::ReservedSpace;
-pub use self::traits::{BoxedError, BytesDecode, BytesEncode, Comparator, LexicographicComparator};
+pub use self::traits::{
+    BoxedError, BytesDecode, BytesDecodeWith, BytesEncode, BytesEncodeWith, Comparator,
+    ContiguousPrefixComparator, IgnoreContext, KeyComparator, LexicographicComparator,
+    TypedComparator,
+};
 pub use self::txn::{RoTxn, RwTxn};
+#[cfg(feature = "derive")]
+pub use heed_derive::{HeedKey, HeedValue};
 
 /// The underlying LMDB library version information.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -137,6 +165,11 @@ pub enum Error {
     InvalidDatabaseTyping,
     /// Database closing in progress
     DatabaseClosing,
+    /// The database or environment doesn't belong to the environment of the transaction,
+    /// only returned once enabled with [`EnvOpenOptions::env_mismatch_errors`]
+    EnvMismatch,
+    /// A custom comparator panicked, the transaction is poisoned and can only be aborted
+    ComparatorPanicked(String),
     /// Attempt to open Env with different options
     BadOpenOptions {
         /// The options that were used to originally open this env.
@@ -144,6 +177,133 @@ pub enum Error {
         /// The env opened with the original options.
         env: Env,
     },
+    /// The persisted type metadata of a database differs from the requested one
+    TypeMetadataMismatch {
+        /// The name of the database, `None` for the unnamed one.
+        name: Option<String>,
+        /// The metadata recorded when the database was created.
+        stored: Box<TypeMetadata>,
+        /// The metadata of the types the database is opened with.
+        requested: Box<TypeMetadata>,
+    },
+    /// The schema version of a database is newer than the one known by the [`Migrator`]
+    SchemaVersionTooNew {
+        /// The name of the database.
+        name: String,
+        /// The schema version stored in the environment.
+        stored: u32,
+        /// The latest schema version known by the migrator.
+        known: u32,
+    },
+    /// An error returned by a [`Database`] operation along with the context of this operation,
+    /// only returned once enabled with [`EnvOpenOptions::error_context`]
+    WithContext {
+        /// The error returned by the operation.
+        error: Box<Error>,
+        /// The database, operation and key the error is related to.
+        context: Box<ErrorContext>,
+    },
+}
+
+impl Error {
+    /// Returns the context of the [`Database`] operation that returned this error, if any.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its context, useful to match on the kind of error.
+    ///
+    /// ```
+    /// # use heed::{Error, MdbError};
+    /// fn is_key_exist(error: &Error) -> bool {
+    ///     matches!(error.without_context(), Error::Mdb(MdbError::KeyExist))
+    /// }
+    /// ```
+    pub fn without_context(&self) -> &Error {
+        match self {
+            Error::WithContext { error, .. } => error,
+            error => error,
+        }
+    }
+
+    /// Converts this error into the error without its context.
+    pub fn into_without_context(self) -> Error {
+        match self {
+            Error::WithContext { error, .. } => *error,
+            error => error,
+        }
+    }
+
+    /// Attaches a context to this error unless it already has one.
+    pub(crate) fn with_context(
+        self,
+        database: Option<String>,
+        operation: &'static str,
+        key: Option<&[u8]>,
+    ) -> Error {
+        match self {
+            Error::WithContext { .. } => self,
+            error => {
+                let context = ErrorContext::new(database, operation, key);
+                Error::WithContext { error: Box::new(error), context: Box::new(context) }
+            }
+        }
+    }
+}
+
+/// The context of a [`Database`] operation that returned an [`Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    database: Option<String>,
+    operation: &'static str,
+    key: Option<Vec<u8>>,
+    key_truncated: bool,
+}
+
+impl ErrorContext {
+    /// The maximum number of bytes of the encoded key kept in the context.
+    pub const MAX_KEY_LEN: usize = 64;
+
+    fn new(database: Option<String>, operation: &'static str, key: Option<&[u8]>) -> ErrorContext {
+        let key_truncated = key.is_some_and(|key| key.len() > Self::MAX_KEY_LEN);
+        let key = key.map(|key| key[..key.len().min(Self::MAX_KEY_LEN)].to_vec());
+        ErrorContext { database, operation, key, key_truncated }
+    }
+
+    /// The name of the database, `None` for the unnamed one.
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    /// The name of the operation, e.g. `get`, `put` or `range`.
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// The encoded key of the operation, truncated to [`ErrorContext::MAX_KEY_LEN`] bytes.
+    ///
+    /// It is `None` when the operation is not about a key or when the key cannot be encoded.
+    pub fn key(&self) -> Option<&[u8]> {
+        self.key.as_deref()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} in ", self.operation)?;
+        match &self.database {
+            Some(name) => write!(f, "database {:?}", name)?,
+            None => f.write_str("the unnamed database")?,
+        }
+        if let Some(key) = &self.key {
+            let ellipsis = if self.key_truncated { "..." } else { "" };
+            write!(f, " with key b\"{}{}\"", key.escape_ascii(), ellipsis)?;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for Error {
@@ -159,9 +319,26 @@ impl fmt::Display for Error {
             Error::DatabaseClosing => {
                 f.write_str("database is in a closing phase, you can't open it at the same time")
             }
+            Error::EnvMismatch => {
+                f.write_str("the database environment doesn't match the transaction's environment")
+            }
+            Error::ComparatorPanicked(message) => {
+                write!(f, "a custom comparator panicked: {}", message)
+            }
             Error::BadOpenOptions { .. } => {
                 f.write_str("an environment is already opened with different options")
             }
+            Error::TypeMetadataMismatch { name, stored, requested } => write!(
+                f,
+                "database {:?} was created with ({}) but is opened with ({})",
+                name, stored, requested
+            ),
+            Error::SchemaVersionTooNew { name, stored, known } => write!(
+                f,
+                "database {:?} is at schema version {} but only versions up to {} are known",
+                name, stored, known
+            ),
+            Error::WithContext { error, context } => write!(f, "{} (during {})", error, context),
         }
     }
 }
@@ -194,25 +371,35 @@ pub type Result<T> = result::Result<T, Error>;
 /// to properly define them.
 pub struct Unspecified;
 
-macro_rules! assert_eq_env_db_txn {
+/// Panics, or returns an [`Error::EnvMismatch`] if the environment of the transaction enabled
+/// [`EnvOpenOptions::env_mismatch_errors`], when the database doesn't come from the transaction's
+/// environment and returns an [`MdbError::BadDbi`] if the database has been dropped.
+macro_rules! ensure_same_env_db_txn {
     ($database:ident, $txn:ident) => {
-        assert!(
-            $database.env_ident == $txn.env_mut_ptr() as usize,
-            "The database environment doesn't match the transaction's environment"
-        );
+        if $database.env_ident != $txn.env_mut_ptr() as usize {
+            if $txn.env().env_mismatch_errors() {
+                return Err($crate::Error::EnvMismatch);
+            }
+            panic!("The database environment doesn't match the transaction's environment");
+        }
+        $txn.check_dbi_generation($database.dbi, $database.generation)?;
     };
 }
 
-macro_rules! assert_eq_env_txn {
+/// Panics, or returns an [`Error::EnvMismatch`] if the environment of the transaction enabled
+/// [`EnvOpenOptions::env_mismatch_errors`], when the environment isn't the transaction's one.
+macro_rules! ensure_same_env_txn {
     ($env:expr, $txn:ident) => {
-        assert!(
-            $env.env_mut_ptr() == $txn.env_mut_ptr(),
-            "The environment doesn't match the transaction's environment"
-        );
+        if $env.env_mut_ptr() != $txn.env_mut_ptr() {
+            if $txn.env().env_mismatch_errors() {
+                return Err($crate::Error::EnvMismatch);
+            }
+            panic!("The environment doesn't match the transaction's environment");
+        }
     };
 }
 
-pub(crate) use {assert_eq_env_db_txn, assert_eq_env_txn};
+pub(crate) use {ensure_same_env_db_txn, ensure_same_env_txn};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,294 @@
+use std::fmt;
+
+use types::Str;
+
+use crate::*;
+
+/// The name of the database in which the type metadata of the other databases are stored.
+pub const TYPE_METADATA_DATABASE_NAME: &str = "__heed_type_metadata";
+
+/// The key under which the metadata of the unnamed database is stored,
+/// database names can't contain a NUL byte so it can't collide with a named one.
+const UNNAMED_DATABASE_KEY: &str = "\0";
+
+/// The codecs, comparator and flags a database was created with.
+///
+/// It is recorded in the [type metadata database](TYPE_METADATA_DATABASE_NAME) when
+/// using [`DatabaseOpenOptions::persist_types`] and checked when the database is reopened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMetadata {
+    /// The identifier of the key codec, its type name by default.
+    pub key: String,
+    /// The identifier of the data codec, its type name by default.
+    pub data: String,
+    /// The type name of the key comparator.
+    pub comparator: String,
+    /// The raw flags the database was created with, they are not compared.
+    pub flags: u32,
+}
+
+impl TypeMetadata {
+    fn to_bytes_string(&self) -> Result<String> {
+        if [&self.key, &self.data, &self.comparator].iter().any(|s| s.contains('\n')) {
+            return Err(Error::Encoding("type metadata must not contain new lines".into()));
+        }
+        Ok(format!("{}\n{}\n{}\n{}", self.key, self.data, self.comparator, self.flags))
+    }
+
+    fn from_bytes_str(s: &str) -> Result<TypeMetadata> {
+        let mut lines = s.split('\n');
+        let metadata = (|| {
+            let metadata = TypeMetadata {
+                key: lines.next()?.to_string(),
+                data: lines.next()?.to_string(),
+                comparator: lines.next()?.to_string(),
+                flags: lines.next()?.parse().ok()?,
+            };
+            lines.next().is_none().then_some(metadata)
+        })();
+
+        metadata.ok_or_else(|| Error::Decoding(format!("invalid type metadata: {:?}", s).into()))
+    }
+}
+
+impl fmt::Display for TypeMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "key: {}, data: {}, comparator: {}, flags: {:#x}",
+            self.key, self.data, self.comparator, self.flags
+        )
+    }
+}
+
+fn metadata_key(name: Option<&str>) -> &str {
+    name.unwrap_or(UNNAMED_DATABASE_KEY)
+}
+
+/// Records the metadata of the database if it isn't already, checks it otherwise.
+pub(crate) fn record_or_check(
+    env: &Env,
+    wtxn: &mut RwTxn,
+    name: Option<&str>,
+    requested: TypeMetadata,
+) -> Result<()> {
+    let metadata_db = env
+        .database_options()
+        .types::<Str, Str>()
+        .name(TYPE_METADATA_DATABASE_NAME)
+        .create(wtxn)?;
+
+    match metadata_db.get(wtxn, metadata_key(name))? {
+        Some(stored) => compare(name, TypeMetadata::from_bytes_str(stored)?, requested),
+        None => metadata_db.put(wtxn, metadata_key(name), &requested.to_bytes_string()?),
+    }
+}
+
+/// Forgets the metadata of a dropped database, it can then be created again with other types.
+pub(crate) fn remove(env: &Env, wtxn: &mut RwTxn, name: Option<&str>) -> Result<()> {
+    if name == Some(TYPE_METADATA_DATABASE_NAME) {
+        return Ok(());
+    }
+
+    let metadata_db =
+        env.database_options().types::<Str, Str>().name(TYPE_METADATA_DATABASE_NAME).open(wtxn)?;
+
+    match metadata_db {
+        Some(metadata_db) => metadata_db.delete(wtxn, metadata_key(name)).map(drop),
+        None => Ok(()),
+    }
+}
+
+/// Checks the metadata of the database if one was recorded.
+pub(crate) fn check(
+    env: &Env,
+    rtxn: &RoTxn,
+    name: Option<&str>,
+    requested: TypeMetadata,
+) -> Result<()> {
+    let metadata_db =
+        env.database_options().types::<Str, Str>().name(TYPE_METADATA_DATABASE_NAME).open(rtxn)?;
+
+    match metadata_db {
+        Some(metadata_db) => match metadata_db.get(rtxn, metadata_key(name))? {
+            Some(stored) => compare(name, TypeMetadata::from_bytes_str(stored)?, requested),
+            None => Ok(()),
+        },
+        None => Ok(()),
+    }
+}
+
+/// Compares the codecs and the comparator, LMDB ignores the flags
+/// given to open a database that already exists and keeps its own.
+fn compare(name: Option<&str>, stored: TypeMetadata, requested: TypeMetadata) -> Result<()> {
+    if stored.key == requested.key
+        && stored.data == requested.data
+        && stored.comparator == requested.comparator
+    {
+        Ok(())
+    } else {
+        Err(Error::TypeMetadataMismatch {
+            name: name.map(ToOwned::to_owned),
+            stored: Box::new(stored),
+            requested: Box::new(requested),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::byteorder::BigEndian;
+    use crate::types::*;
+    use crate::{DatabaseFlags, EnvOpenOptions, Error};
+
+    #[test]
+    fn type_metadata_is_checked_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024).max_dbs(10); // 10MB
+
+        let env = options.open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        env.database_options()
+            .types::<Str, Str>()
+            .name("numbers")
+            .persist_types()
+            .create(&mut wtxn)
+            .unwrap();
+        env.database_options()
+            .types::<U32<BigEndian>, Str>()
+            .name("tagged")
+            .type_tags("user-id", "user-name-v1")
+            .create(&mut wtxn)
+            .unwrap();
+        wtxn.commit().unwrap();
+        env.prepare_for_closing().wait();
+
+        let env = options.open(dir.path()).unwrap();
+        let rtxn = env.read_txn().unwrap();
+
+        let result = env
+            .database_options()
+            .types::<Str, ByteSlice>()
+            .name("numbers")
+            .persist_types()
+            .open(&rtxn);
+        match result {
+            Err(Error::TypeMetadataMismatch { name, stored, requested }) => {
+                assert_eq!(name.as_deref(), Some("numbers"));
+                assert_eq!(stored.data, std::any::type_name::<Str>());
+                assert_eq!(requested.data, std::any::type_name::<ByteSlice>());
+            }
+            otherwise => panic!("expected a type metadata mismatch, got {:?}", otherwise),
+        }
+
+        let db = env
+            .database_options()
+            .types::<Str, Str>()
+            .name("numbers")
+            .persist_types()
+            .open(&rtxn)
+            .unwrap();
+        assert!(db.is_some());
+
+        // The tags are compared, not the type names.
+        let db = env
+            .database_options()
+            .types::<U32<BigEndian>, ByteSlice>()
+            .name("tagged")
+            .type_tags("user-id", "user-name-v1")
+            .open(&rtxn)
+            .unwrap();
+        assert!(db.is_some());
+
+        let result = env
+            .database_options()
+            .types::<U32<BigEndian>, Str>()
+            .name("tagged")
+            .type_tags("user-id", "user-name-v2")
+            .open(&rtxn);
+        assert!(matches!(result, Err(Error::TypeMetadataMismatch { .. })));
+    }
+
+    #[test]
+    fn type_metadata_is_removed_with_the_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .max_dbs(10)
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Str>()
+            .name("recreated")
+            .persist_types()
+            .create(&mut wtxn)
+            .unwrap();
+        db.put(&mut wtxn, "hello", "world").unwrap();
+        wtxn.commit().unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        db.drop(&mut wtxn).unwrap();
+        let db = env
+            .database_options()
+            .types::<U32<BigEndian>, ByteSlice>()
+            .name("recreated")
+            .persist_types()
+            .create(&mut wtxn)
+            .unwrap();
+        db.put(&mut wtxn, &42, b"forty-two").unwrap();
+        wtxn.commit().unwrap();
+
+        // The new types are the ones recorded.
+        let rtxn = env.read_txn().unwrap();
+        let result = env
+            .database_options()
+            .types::<Str, Str>()
+            .name("recreated")
+            .persist_types()
+            .open(&rtxn);
+        assert!(matches!(result, Err(Error::TypeMetadataMismatch { .. })));
+        let db = env
+            .database_options()
+            .types::<U32<BigEndian>, ByteSlice>()
+            .name("recreated")
+            .persist_types()
+            .open(&rtxn)
+            .unwrap()
+            .unwrap();
+        assert_eq!(db.get(&rtxn, &42).unwrap(), Some(&b"forty-two"[..]));
+    }
+
+    #[test]
+    fn type_metadata_ignores_the_flags_of_an_existing_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .max_dbs(10)
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        env.database_options()
+            .types::<Str, Str>()
+            .name("dups")
+            .flags(DatabaseFlags::DUP_SORT)
+            .persist_types()
+            .create(&mut wtxn)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Str>()
+            .name("dups")
+            .persist_types()
+            .open(&rtxn)
+            .unwrap();
+        assert!(db.is_some());
+    }
+}
@@ -0,0 +1,376 @@
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use types::{ByteSlice, Str, U32};
+
+use crate::byteorder::BigEndian;
+use crate::mdb::lmdb_flags::AllDatabaseFlags;
+use crate::*;
+
+/// The name of the database in which the schema version of the other databases are stored.
+pub const SCHEMA_VERSIONS_DATABASE_NAME: &str = "__heed_schema_versions";
+
+/// A migration step that brings a database from one schema version to the next one.
+///
+/// It is given the raw database to migrate, inside the write transaction of the migration,
+/// opened with the key comparator the step was [registered](Migrator::register_with_comparator) with.
+pub type MigrationStep<C = DefaultComparator> =
+    fn(&mut RwTxn, Database<ByteSlice, ByteSlice, C>) -> Result<()>;
+
+/// A migration step of which the comparator has been erased, given the opened database.
+type ErasedStep = Arc<dyn Fn(&mut RwTxn, usize, u32, u32) -> Result<()> + Send + Sync>;
+
+/// Opens a database with its comparator, returns its dbi and generation.
+type OpenDatabase = fn(&Env, &RwTxn, &str, AllDatabaseFlags) -> Result<(u32, u32)>;
+
+/// The migration steps of a database and the way to open it with its comparator.
+#[derive(Clone)]
+struct DatabaseSteps {
+    comparator: TypeId,
+    open: OpenDatabase,
+    steps: Vec<ErasedStep>,
+}
+
+/// Stores a schema version per named database and runs the registered
+/// migration steps to bring the databases to the version known by the code.
+///
+/// The n-th step registered for a database migrates it from version `n - 1` to version `n`,
+/// the version known by the code is therefore the number of steps registered.
+/// All the steps are run inside a single write transaction, nothing is
+/// committed if one of them fails.
+///
+/// A database that doesn't exist yet is considered up to date, its version is directly
+/// set to the one known by the code. A database that exists without a recorded version is
+/// considered to be at version `0`. The versions are stored in the
+/// [schema versions database](SCHEMA_VERSIONS_DATABASE_NAME), which counts in the
+/// [`max_dbs`](EnvOpenOptions::max_dbs).
+///
+/// ```
+/// use heed::{Database, EnvOpenOptions, Migrator, RwTxn};
+/// use heed::types::*;
+///
+/// fn uppercase_names(wtxn: &mut RwTxn, db: Database<ByteSlice, ByteSlice>) -> heed::Result<()> {
+///     let mut iter = db.iter_mut(wtxn)?;
+///     while let Some((key, name)) = iter.next().transpose()? {
+///         let (key, name) = (key.to_vec(), name.to_ascii_uppercase());
+///         unsafe { iter.put_current(&key, &name)? };
+///     }
+///     Ok(())
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = tempfile::tempdir()?;
+/// let mut options = EnvOpenOptions::new();
+/// options.max_dbs(10);
+///
+/// let env = options.open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let users: Database<Str, Str> = env.create_database(&mut wtxn, Some("users"))?;
+/// users.put(&mut wtxn, "1", "kerollmops")?;
+/// wtxn.commit()?;
+///
+/// let mut migrator = Migrator::new();
+/// migrator.register("users", uppercase_names);
+///
+/// let report = migrator.dry_run(&env)?;
+/// assert_eq!(report.migrated().count(), 1);
+///
+/// let report = migrator.migrate(&env)?;
+/// assert_eq!(report.migrated().count(), 1);
+///
+/// let rtxn = env.read_txn()?;
+/// assert_eq!(users.get(&rtxn, "1")?, Some("KEROLLMOPS"));
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Default)]
+pub struct Migrator {
+    steps: BTreeMap<String, DatabaseSteps>,
+}
+
+impl Migrator {
+    /// Creates a migrator without any registered step.
+    pub fn new() -> Migrator {
+        Migrator::default()
+    }
+
+    /// Registers the next migration step of the named database.
+    ///
+    /// The database is opened with the [`DefaultComparator`], use
+    /// [`Migrator::register_with_comparator`] for the databases using a custom one.
+    pub fn register(&mut self, database: &str, step: MigrationStep) -> &mut Self {
+        self.register_with_comparator::<DefaultComparator>(database, step)
+    }
+
+    /// Registers the next migration step of the named database, which uses a custom key comparator.
+    ///
+    /// The database is opened with this comparator for its keys to be written in the right order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if steps have already been registered for this database with another comparator.
+    pub fn register_with_comparator<C: Comparator + 'static>(
+        &mut self,
+        database: &str,
+        step: MigrationStep<C>,
+    ) -> &mut Self {
+        let steps = self.steps.entry(database.to_string()).or_insert_with(|| DatabaseSteps {
+            comparator: TypeId::of::<C>(),
+            open: raw_open_database::<C>,
+            steps: Vec::new(),
+        });
+        assert!(
+            steps.comparator == TypeId::of::<C>(),
+            "the steps of the {database:?} database must be registered with the same comparator"
+        );
+        steps.steps.push(Arc::new(move |wtxn, env_ident, dbi, generation| {
+            (step)(wtxn, Database::new(env_ident, dbi, generation))
+        }));
+        self
+    }
+
+    /// Returns the schema version of the named database known by this migrator.
+    pub fn known_version(&self, database: &str) -> u32 {
+        self.steps.get(database).map_or(0, |db| db.steps.len() as u32)
+    }
+
+    /// Opens the environment and runs the pending migration steps.
+    ///
+    /// It refuses to open the environment if one of the databases is at
+    /// a version that is newer than the one known by this migrator.
+    pub fn open_env<P: AsRef<Path>>(&self, options: &EnvOpenOptions, path: P) -> Result<Env> {
+        let env = options.open(path)?;
+        self.migrate(&env)?;
+        Ok(env)
+    }
+
+    /// Runs the pending migration steps and commits them.
+    pub fn migrate(&self, env: &Env) -> Result<MigrationReport> {
+        let mut wtxn = env.write_txn()?;
+        let report = self.run(env, &mut wtxn)?;
+        wtxn.commit()?;
+        Ok(report)
+    }
+
+    /// Runs the pending migration steps but aborts the transaction instead of committing it.
+    ///
+    /// It can be used to make sure that the migrations succeed without modifying the environment.
+    pub fn dry_run(&self, env: &Env) -> Result<MigrationReport> {
+        let mut wtxn = env.write_txn()?;
+        let report = self.run(env, &mut wtxn)?;
+        wtxn.abort();
+        Ok(report)
+    }
+
+    fn run(&self, env: &Env, wtxn: &mut RwTxn) -> Result<MigrationReport> {
+        let versions = env
+            .database_options()
+            .types::<Str, U32<BigEndian>>()
+            .name(SCHEMA_VERSIONS_DATABASE_NAME)
+            .create(wtxn)?;
+
+        let mut report = MigrationReport::default();
+        for (name, DatabaseSteps { open, steps, .. }) in &self.steps {
+            let known = steps.len() as u32;
+            let from = match versions.get(wtxn, name)? {
+                Some(stored) if stored > known => {
+                    return Err(Error::SchemaVersionTooNew { name: name.clone(), stored, known })
+                }
+                Some(stored) => stored,
+                None => match open(env, wtxn, name, AllDatabaseFlags::empty()) {
+                    Ok(_) => 0,
+                    Err(Error::Mdb(e)) if e.not_found() => known,
+                    Err(e) => return Err(e),
+                },
+            };
+
+            if from < known {
+                let (dbi, generation) = open(env, wtxn, name, AllDatabaseFlags::CREATE)?;
+                for step in &steps[from as usize..] {
+                    (step)(wtxn, env.env_mut_ptr() as _, dbi, generation)?;
+                }
+            }
+
+            versions.put_with_flags(wtxn, PutFlags::empty(), name, &known)?;
+            report.databases.push(DatabaseMigration { name: name.clone(), from, to: known });
+        }
+
+        Ok(report)
+    }
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let versions = self.steps.iter().map(|(name, db)| (name, db.steps.len()));
+        f.debug_struct("Migrator").field("versions", &versions.collect::<BTreeMap<_, _>>()).finish()
+    }
+}
+
+/// Opens the database with its comparator but without registering its types
+/// as the users of the migrator are likely to open it with other codecs afterward.
+fn raw_open_database<C: Comparator + 'static>(
+    env: &Env,
+    wtxn: &RwTxn,
+    name: &str,
+    flags: AllDatabaseFlags,
+) -> Result<(u32, u32)> {
+    env.raw_init_untyped_database::<C>(wtxn, Some(name), flags)
+}
+
+/// The outcome of a [`Migrator`] run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The databases known by the migrator, with their version before and after the run.
+    pub databases: Vec<DatabaseMigration>,
+}
+
+impl MigrationReport {
+    /// Returns the databases on which at least one migration step was run.
+    pub fn migrated(&self) -> impl Iterator<Item = &DatabaseMigration> {
+        self.databases.iter().filter(|db| db.from != db.to)
+    }
+}
+
+/// The schema versions of a database before and after a [`Migrator`] run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseMigration {
+    /// The name of the database.
+    pub name: String,
+    /// The schema version of the database before the run.
+    pub from: u32,
+    /// The schema version of the database after the run.
+    pub to: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefix_v1(wtxn: &mut RwTxn, db: Database<ByteSlice, ByteSlice>) -> Result<()> {
+        let mut iter = db.iter_mut(wtxn)?;
+        while let Some((key, value)) = iter.next().transpose()? {
+            let key = key.to_vec();
+            let value = [b"v1:", value].concat();
+            unsafe { iter.put_current(&key, &value)? };
+        }
+        Ok(())
+    }
+
+    fn prefix_v2(wtxn: &mut RwTxn, db: Database<ByteSlice, ByteSlice>) -> Result<()> {
+        let mut iter = db.iter_mut(wtxn)?;
+        while let Some((key, value)) = iter.next().transpose()? {
+            let key = key.to_vec();
+            let value = [b"v2:", value].concat();
+            unsafe { iter.put_current(&key, &value)? };
+        }
+        Ok(())
+    }
+
+    fn unreachable_step(_: &mut RwTxn, _: Database<ByteSlice, ByteSlice>) -> Result<()> {
+        panic!("a new database must not be migrated")
+    }
+
+    #[test]
+    fn migrate_dry_run_and_refuse_newer_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024).max_dbs(10); // 10MB
+
+        let env = options.open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("values")).unwrap();
+        db.put(&mut wtxn, "hello", "world").unwrap();
+        wtxn.commit().unwrap();
+
+        let mut migrator = Migrator::new();
+        migrator.register("values", prefix_v1).register("values", prefix_v2);
+        migrator.register("fresh", unreachable_step);
+        assert_eq!(migrator.known_version("values"), 2);
+
+        let report = migrator.dry_run(&env).unwrap();
+        let migrated: Vec<_> = report.migrated().collect();
+        assert_eq!(migrated, [&DatabaseMigration { name: "values".into(), from: 0, to: 2 }]);
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(db.get(&rtxn, "hello").unwrap(), Some("world"));
+        drop(rtxn);
+
+        migrator.migrate(&env).unwrap();
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(db.get(&rtxn, "hello").unwrap(), Some("v2:v1:world"));
+        drop(rtxn);
+
+        // Nothing more to migrate.
+        let report = migrator.migrate(&env).unwrap();
+        assert_eq!(report.migrated().count(), 0);
+        env.prepare_for_closing().wait();
+
+        // An older code must refuse to open the environment.
+        let mut migrator = Migrator::new();
+        migrator.register("values", prefix_v1);
+        match migrator.open_env(&options, dir.path()) {
+            Err(Error::SchemaVersionTooNew { name, stored, known }) => {
+                assert_eq!((name.as_str(), stored, known), ("values", 2, 1));
+            }
+            otherwise => panic!("expected a schema version error, got {:?}", otherwise),
+        }
+    }
+
+    fn add_reversed_keys(
+        wtxn: &mut RwTxn,
+        db: Database<ByteSlice, ByteSlice, ReverseLexicographicComparator>,
+    ) -> Result<()> {
+        for key in ["b", "d"] {
+            db.put(wtxn, key.as_bytes(), b"migrated")?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_with_a_custom_comparator() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.map_size(10 * 1024 * 1024).max_dbs(10); // 10MB
+
+        let env = options.open(dir.path()).unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Str>()
+            .key_comparator::<ReverseLexicographicComparator>()
+            .name("reversed")
+            .create(&mut wtxn)
+            .unwrap();
+        for key in ["a", "c", "e"] {
+            db.put(&mut wtxn, key, "original").unwrap();
+        }
+        wtxn.commit().unwrap();
+        env.prepare_for_closing().wait();
+
+        // The migration runs before the database is opened by the program.
+        let mut migrator = Migrator::new();
+        migrator.register_with_comparator("reversed", add_reversed_keys);
+        let env = migrator.open_env(&options, dir.path()).unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Str>()
+            .key_comparator::<ReverseLexicographicComparator>()
+            .name("reversed")
+            .open(&rtxn)
+            .unwrap()
+            .unwrap();
+        let keys: Vec<_> = db.iter(&rtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["e", "d", "c", "b", "a"]);
+        assert_eq!(db.get(&rtxn, "d").unwrap(), Some("migrated"));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be registered with the same comparator")]
+    fn register_steps_with_different_comparators() {
+        let mut migrator = Migrator::new();
+        migrator.register("reversed", prefix_v1);
+        migrator.register_with_comparator("reversed", add_reversed_keys);
+    }
+}
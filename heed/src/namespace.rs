@@ -0,0 +1,363 @@
+use std::ops::{Bound, RangeBounds};
+use std::{fmt, marker};
+
+use heed_traits::LexicographicComparator;
+use types::{ByteSlice, DecodeIgnore};
+
+use crate::env::DefaultComparator;
+use crate::iterator::advance_prefix;
+use crate::*;
+
+/// A view of a [`Database`] restricted to the keys starting with a fixed byte prefix.
+///
+/// The prefix is transparently prepended to the keys when writing or seeking them
+/// and stripped from the keys before decoding them. It can be used to store many
+/// logical tables in a single LMDB database to stay under the `max_dbs` limit.
+///
+/// This view is created with [`Database::namespace`].
+///
+/// ```
+/// # use heed::EnvOpenOptions;
+/// use heed::Database;
+/// use heed::types::*;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let dir = tempfile::tempdir()?;
+/// # let env = EnvOpenOptions::new()
+/// #     .map_size(10 * 1024 * 1024) // 10MB
+/// #     .max_dbs(3000)
+/// #     .open(dir.path())?;
+/// let mut wtxn = env.write_txn()?;
+/// let db: Database<Str, Str> = env.create_database(&mut wtxn, Some("tables"))?;
+///
+/// let users = db.namespace(b"users/");
+/// let groups = db.namespace(b"groups/");
+///
+/// users.put(&mut wtxn, "kerollmops", "Clément")?;
+/// groups.put(&mut wtxn, "kerollmops", "admins")?;
+///
+/// assert_eq!(users.get(&wtxn, "kerollmops")?, Some("Clément"));
+/// assert_eq!(db.get(&wtxn, "groups/kerollmops")?, Some("admins"));
+///
+/// let mut iter = users.iter(&wtxn)?;
+/// assert_eq!(iter.next().transpose()?, Some(("kerollmops", "Clément")));
+/// assert_eq!(iter.next().transpose()?, None);
+/// drop(iter);
+///
+/// users.clear(&mut wtxn)?;
+/// assert_eq!(users.len(&wtxn)?, 0);
+/// assert_eq!(groups.len(&wtxn)?, 1);
+///
+/// wtxn.commit()?;
+/// # Ok(()) }
+/// ```
+pub struct Namespace<KC, DC, C = DefaultComparator> {
+    db: Database<KC, DC, C>,
+    prefix: Vec<u8>,
+}
+
+impl<KC, DC, C> Namespace<KC, DC, C> {
+    pub(crate) fn new(db: Database<KC, DC, C>, prefix: Vec<u8>) -> Namespace<KC, DC, C> {
+        Namespace { db, prefix }
+    }
+
+    /// Returns the prefix prepended to the keys of this namespace.
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Returns the database this namespace is a view of.
+    pub fn database(&self) -> Database<KC, DC, C> {
+        self.db
+    }
+
+    /// Retrieves the value associated with a key of this namespace.
+    ///
+    /// If the key does not exist, then `None` is returned.
+    pub fn get<'a, 'txn>(&self, txn: &'txn RoTxn, key: &'a KC::EItem) -> Result<Option<DC::DItem>>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesDecode<'txn>,
+    {
+        let key_bytes = self.prefixed_key::<KC>(key)?;
+        self.db.remap_key_type::<ByteSlice>().get(txn, &key_bytes)
+    }
+
+    /// Insert a key-value pair in this namespace.
+    ///
+    /// Like [`Database::put`], it returns an error if the key already exists.
+    pub fn put<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>,
+    {
+        let key_bytes = self.prefixed_key::<KC>(key)?;
+        let data_bytes = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        self.db.remap_types::<ByteSlice, ByteSlice>().put(txn, &key_bytes, &data_bytes)
+    }
+
+    /// Deletes a key-value pair in this namespace.
+    ///
+    /// If the key does not exist, then `false` is returned.
+    pub fn delete<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem) -> Result<bool>
+    where
+        KC: BytesEncode<'a>,
+    {
+        let key_bytes = self.prefixed_key::<KC>(key)?;
+        self.db.remap_key_type::<ByteSlice>().delete(txn, &key_bytes)
+    }
+
+    /// Return an ordered iterator of all key-value pairs in this namespace.
+    ///
+    /// It is built on top of [`Database::prefix_iter`] and the keys are returned without the prefix.
+    pub fn iter<'txn>(&self, txn: &'txn RoTxn) -> Result<NamespaceIter<'txn, KC, DC, C>>
+    where
+        C: LexicographicComparator,
+    {
+        let iter = self.db.remap_key_type::<ByteSlice>().prefix_iter(txn, &self.prefix)?;
+        Ok(NamespaceIter { iter, prefix_len: self.prefix.len(), _phantom: marker::PhantomData })
+    }
+
+    /// Return an ordered iterator of a range of key-value pairs in this namespace.
+    ///
+    /// The bounds are prefixed before seeking and unbounded ends are
+    /// restricted to the keys of this namespace.
+    pub fn range<'a, 'txn, R>(
+        &self,
+        txn: &'txn RoTxn,
+        range: &'a R,
+    ) -> Result<NamespaceRange<'txn, KC, DC, C>>
+    where
+        KC: BytesEncode<'a>,
+        C: LexicographicComparator,
+        R: RangeBounds<KC::EItem>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(bound) => Bound::Included(self.prefixed_key::<KC>(bound)?),
+            Bound::Excluded(bound) => Bound::Excluded(self.prefixed_key::<KC>(bound)?),
+            Bound::Unbounded => Bound::Included(self.prefix.clone()),
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(bound) => Bound::Included(self.prefixed_key::<KC>(bound)?),
+            Bound::Excluded(bound) => Bound::Excluded(self.prefixed_key::<KC>(bound)?),
+            Bound::Unbounded => self.prefix_end(),
+        };
+
+        let range = (start.as_ref().map(Vec::as_slice), end.as_ref().map(Vec::as_slice));
+        let iter = self.db.remap_key_type::<ByteSlice>().range(txn, &range)?;
+        Ok(NamespaceRange { iter, prefix_len: self.prefix.len(), _phantom: marker::PhantomData })
+    }
+
+    /// Returns the number of entries in this namespace.
+    ///
+    /// The entries are counted by iterating over the namespace.
+    pub fn len(&self, txn: &RoTxn) -> Result<u64>
+    where
+        C: LexicographicComparator,
+    {
+        let db = self.db.remap_types::<ByteSlice, DecodeIgnore>();
+        let mut count = 0;
+        for result in db.prefix_iter(txn, &self.prefix)? {
+            result?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Returns `true` if this namespace is empty.
+    pub fn is_empty(&self, txn: &RoTxn) -> Result<bool>
+    where
+        C: LexicographicComparator,
+    {
+        let db = self.db.remap_types::<ByteSlice, DecodeIgnore>();
+        let mut iter = db.prefix_iter(txn, &self.prefix)?;
+        iter.next().transpose().map(|entry| entry.is_none())
+    }
+
+    /// Deletes all key-value pairs in this namespace and returns the number of deleted entries.
+    ///
+    /// It is built on top of [`Database::delete_range`], other namespaces are left untouched.
+    pub fn clear(&self, txn: &mut RwTxn) -> Result<usize>
+    where
+        C: LexicographicComparator,
+    {
+        let end = self.prefix_end();
+        let range = (Bound::Included(self.prefix.as_slice()), end.as_ref().map(Vec::as_slice));
+        self.db.remap_types::<ByteSlice, DecodeIgnore>().delete_range(txn, &range)
+    }
+
+    fn prefixed_key<'a, K: BytesEncode<'a>>(&self, key: &'a K::EItem) -> Result<Vec<u8>> {
+        let key_bytes = K::bytes_encode(key).map_err(Error::Encoding)?;
+        let mut bytes = Vec::with_capacity(self.prefix.len() + key_bytes.len());
+        bytes.extend_from_slice(&self.prefix);
+        bytes.extend_from_slice(&key_bytes);
+        Ok(bytes)
+    }
+
+    /// Returns the bound that excludes every key greater than the keys of this namespace.
+    fn prefix_end(&self) -> Bound<Vec<u8>>
+    where
+        C: LexicographicComparator,
+    {
+        let mut end = self.prefix.clone();
+        if advance_prefix::<C>(&mut end) {
+            Bound::Excluded(end)
+        } else {
+            Bound::Unbounded
+        }
+    }
+}
+
+impl<KC, DC, C> Clone for Namespace<KC, DC, C> {
+    fn clone(&self) -> Namespace<KC, DC, C> {
+        Namespace { db: self.db, prefix: self.prefix.clone() }
+    }
+}
+
+impl<KC, DC, C> fmt::Debug for Namespace<KC, DC, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Namespace").field("db", &self.db).field("prefix", &self.prefix).finish()
+    }
+}
+
+/// An iterator over the key-value pairs of a [`Namespace`].
+pub struct NamespaceIter<'txn, KC, DC, C = DefaultComparator> {
+    iter: RoPrefix<'txn, ByteSlice, DC, C>,
+    prefix_len: usize,
+    _phantom: marker::PhantomData<KC>,
+}
+
+impl<'txn, KC, DC, C> Iterator for NamespaceIter<'txn, KC, DC, C>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+    C: LexicographicComparator,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let prefix_len = self.prefix_len;
+        self.iter
+            .next()
+            .map(|result| result.and_then(|entry| strip_and_decode::<KC, _>(entry, prefix_len)))
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        let prefix_len = self.prefix_len;
+        self.iter
+            .last()
+            .map(|result| result.and_then(|entry| strip_and_decode::<KC, _>(entry, prefix_len)))
+    }
+}
+
+impl<KC, DC, C> fmt::Debug for NamespaceIter<'_, KC, DC, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NamespaceIter").finish()
+    }
+}
+
+/// An iterator over a range of key-value pairs of a [`Namespace`].
+pub struct NamespaceRange<'txn, KC, DC, C = DefaultComparator> {
+    iter: RoRange<'txn, ByteSlice, DC, C>,
+    prefix_len: usize,
+    _phantom: marker::PhantomData<KC>,
+}
+
+impl<'txn, KC, DC, C> Iterator for NamespaceRange<'txn, KC, DC, C>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+    C: Comparator,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let prefix_len = self.prefix_len;
+        self.iter
+            .next()
+            .map(|result| result.and_then(|entry| strip_and_decode::<KC, _>(entry, prefix_len)))
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        let prefix_len = self.prefix_len;
+        self.iter
+            .last()
+            .map(|result| result.and_then(|entry| strip_and_decode::<KC, _>(entry, prefix_len)))
+    }
+}
+
+impl<KC, DC, C> fmt::Debug for NamespaceRange<'_, KC, DC, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NamespaceRange").finish()
+    }
+}
+
+fn strip_and_decode<'txn, KC, D>(
+    (key, data): (&'txn [u8], D),
+    prefix_len: usize,
+) -> Result<(KC::DItem, D)>
+where
+    KC: BytesDecode<'txn>,
+{
+    let key = KC::bytes_decode(&key[prefix_len..]).map_err(Error::Decoding)?;
+    Ok((key, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::byteorder::BigEndian;
+    use crate::types::*;
+    use crate::EnvOpenOptions;
+
+    #[test]
+    fn namespaces_are_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new()
+            .map_size(10 * 1024 * 1024) // 10MB
+            .open(dir.path())
+            .unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<U32<BigEndian>, Str>(&mut wtxn, None).unwrap();
+        let first = db.namespace(&[0]);
+        let second = db.namespace(&[1]);
+        let last = db.namespace(&[255, 255]);
+
+        for i in 0..10 {
+            first.put(&mut wtxn, &i, "first").unwrap();
+            second.put(&mut wtxn, &i, "second").unwrap();
+            last.put(&mut wtxn, &i, "last").unwrap();
+        }
+
+        assert_eq!(db.len(&wtxn).unwrap(), 30);
+        assert_eq!(first.len(&wtxn).unwrap(), 10);
+        assert_eq!(second.get(&wtxn, &3).unwrap(), Some("second"));
+        assert_eq!(last.get(&wtxn, &3).unwrap(), Some("last"));
+        assert_eq!(second.get(&wtxn, &10).unwrap(), None);
+
+        assert!(second.delete(&mut wtxn, &3).unwrap());
+        assert!(!second.delete(&mut wtxn, &3).unwrap());
+        assert_eq!(first.get(&wtxn, &3).unwrap(), Some("first"));
+
+        let keys: Vec<_> = second.iter(&wtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [0, 1, 2, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(second.iter(&wtxn).unwrap().last().transpose().unwrap(), Some((9, "second")));
+
+        let keys: Vec<_> = first.range(&wtxn, &(7..)).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [7, 8, 9]);
+        let keys: Vec<_> = second.range(&wtxn, &(..=2)).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [0, 1, 2]);
+        let keys: Vec<_> = last.range(&wtxn, &(8..)).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [8, 9]);
+
+        assert_eq!(first.clear(&mut wtxn).unwrap(), 10);
+        assert!(first.is_empty(&wtxn).unwrap());
+        assert_eq!(second.len(&wtxn).unwrap(), 9);
+
+        assert_eq!(last.clear(&mut wtxn).unwrap(), 10);
+        assert_eq!(db.len(&wtxn).unwrap(), 9);
+
+        wtxn.commit().unwrap();
+    }
+}
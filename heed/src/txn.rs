@@ -1,9 +1,15 @@
+use std::any::TypeId;
+use std::cell::{Cell, RefCell};
 use std::ops::Deref;
 use std::ptr;
 
+use crate::env::take_comparator_panic;
 use crate::mdb::error::mdb_result;
 use crate::mdb::ffi;
-use crate::{Env, Result};
+use crate::{Env, Error, MdbError, Result};
+
+/// The capacity the scratch buffer of a transaction keeps between two uses.
+const MAX_SCRATCH_CAPACITY: usize = 64 * 1024;
 
 /// A read-only transaction.
 ///
@@ -27,6 +33,25 @@ use crate::{Env, Result};
 pub struct RoTxn<'e> {
     pub(crate) txn: *mut ffi::MDB_txn,
     env: &'e Env,
+    scratch: Cell<Vec<u8>>,
+    /// The transaction this one is nested in, if any.
+    parent: Option<&'e RoTxn<'e>>,
+    /// The dbis dropped by this transaction, only applied to the environment on commit.
+    dropped_dbis: RefCell<Vec<DroppedDbi>>,
+    /// The message of the comparator panic that poisoned this transaction, if any.
+    ///
+    /// It is boxed to keep its address when the transaction moves, the handles point to it.
+    poison: Box<Cell<Option<String>>>,
+}
+
+/// A dbi dropped by a transaction that is not committed yet.
+#[derive(Clone)]
+pub(crate) struct DroppedDbi {
+    pub(crate) dbi: u32,
+    /// The generation the dbi will have once the drop is committed.
+    pub(crate) generation: u32,
+    /// The types the dbi was recreated with after the drop, if any.
+    pub(crate) types: Option<(TypeId, TypeId, TypeId)>,
 }
 
 impl<'e> RoTxn<'e> {
@@ -42,13 +67,130 @@ impl<'e> RoTxn<'e> {
             ))?
         };
 
-        Ok(RoTxn { txn, env })
+        Ok(RoTxn::from_raw(txn, env, None))
+    }
+
+    fn from_raw(txn: *mut ffi::MDB_txn, env: &'e Env, parent: Option<&'e RoTxn<'e>>) -> RoTxn<'e> {
+        // A panic no operation reported doesn't belong to this transaction.
+        take_comparator_panic();
+        RoTxn {
+            txn,
+            env,
+            scratch: Cell::default(),
+            parent,
+            dropped_dbis: RefCell::default(),
+            poison: Box::default(),
+        }
+    }
+
+    pub(crate) fn env(&self) -> &'e Env {
+        self.env
     }
 
     pub(crate) fn env_mut_ptr(&self) -> *mut ffi::MDB_env {
         self.env.env_mut_ptr()
     }
 
+    /// Returns the keys installed in the environment of this transaction, to be given as the
+    /// context of the [`Encrypted`](crate::types::Encrypted) codecs, see [`Env::set_encryption_keys`].
+    #[cfg(feature = "encryption")]
+    pub fn encryption_keys(&self) -> Option<std::sync::Arc<crate::types::EncryptionKeys>> {
+        self.env.encryption_keys()
+    }
+
+    /// Returns the generation of the dbi as seen by this transaction, the drops of a
+    /// transaction are only visible to the other ones once committed.
+    pub(crate) fn dbi_generation(&self, dbi: u32) -> u32 {
+        let mut txn = Some(self);
+        while let Some(current) = txn {
+            let dropped = current.dropped_dbis.borrow();
+            if let Some(dropped) = dropped.iter().find(|dropped| dropped.dbi == dbi) {
+                return dropped.generation;
+            }
+            txn = current.parent;
+        }
+        self.env.committed_dbi_generation(dbi)
+    }
+
+    /// Returns a `BadDbi` error if the dbi has been dropped since this generation of it was opened.
+    pub(crate) fn check_dbi_generation(&self, dbi: u32, generation: u32) -> Result<()> {
+        if self.dbi_generation(dbi) == generation {
+            Ok(())
+        } else {
+            Err(Error::Mdb(MdbError::BadDbi))
+        }
+    }
+
+    /// Records that the dbi has been dropped by this transaction, invalidating the handles on it.
+    pub(crate) fn record_dropped_dbi(&self, dbi: u32) {
+        let generation = self.dbi_generation(dbi).wrapping_add(1);
+        let mut dropped = self.dropped_dbis.borrow_mut();
+        dropped.retain(|dropped| dropped.dbi != dbi);
+        dropped.push(DroppedDbi { dbi, generation, types: None });
+    }
+
+    /// If the dbi has been dropped by this transaction or its parents, registers the
+    /// types it is recreated with, unless already done, and returns the registered ones.
+    pub(crate) fn dropped_dbi_types(
+        &self,
+        dbi: u32,
+        types: (TypeId, TypeId, TypeId),
+    ) -> Option<(TypeId, TypeId, TypeId)> {
+        let mut txn = Some(self);
+        let mut found = None;
+        while let Some(current) = txn {
+            let dropped = current.dropped_dbis.borrow();
+            if let Some(dropped) = dropped.iter().find(|dropped| dropped.dbi == dbi) {
+                found = Some(dropped.clone());
+                break;
+            }
+            txn = current.parent;
+        }
+
+        // The types are registered in this transaction, its parents see them once it is committed.
+        let mut found = found?;
+        let mut dropped = self.dropped_dbis.borrow_mut();
+        dropped.retain(|dropped| dropped.dbi != dbi);
+        let types = *found.types.get_or_insert(types);
+        dropped.push(found);
+        Some(types)
+    }
+
+    /// Applies the drops of this committed transaction to its parent or to the environment.
+    fn commit_dropped_dbis(&self) {
+        let dropped = self.dropped_dbis.take();
+        match self.parent {
+            Some(parent) => {
+                let mut parent_dropped = parent.dropped_dbis.borrow_mut();
+                parent_dropped.retain(|old| dropped.iter().all(|new| new.dbi != old.dbi));
+                parent_dropped.extend(dropped);
+            }
+            None => self.env.commit_dropped_dbis(dropped),
+        }
+    }
+
+    /// Returns what an operation needs to report on this transaction while borrowing it.
+    pub(crate) fn handle(&self) -> TxnHandle<'e> {
+        TxnHandle { env: self.env, poison: &*self.poison }
+    }
+
+    /// Gives an empty buffer to encode keys and values into, its allocation
+    /// is reused by the next calls made with this transaction.
+    ///
+    /// The allocation is shrunk to [`MAX_SCRATCH_CAPACITY`] bytes once used,
+    /// a large value doesn't keep its memory for the rest of the transaction.
+    pub(crate) fn with_scratch<T>(&self, f: impl FnOnce(&mut Vec<u8>) -> T) -> T {
+        // A codec may use the transaction while the buffer is taken,
+        // it will then work with a new buffer that is dropped afterward.
+        let mut buffer = self.scratch.take();
+        buffer.clear();
+        let result = f(&mut buffer);
+        buffer.clear();
+        buffer.shrink_to(MAX_SCRATCH_CAPACITY);
+        self.scratch.set(buffer);
+        result
+    }
+
     /// Commit a read transaction.
     ///
     /// Synchronizing some [Env] metadata with the global handle.
@@ -76,6 +218,54 @@ impl Drop for RoTxn<'_> {
 #[cfg(feature = "read-txn-no-tls")]
 unsafe impl Send for RoTxn<'_> {}
 
+/// The environment and the poison flag of a transaction.
+///
+/// It is copied out of the transaction before an operation borrows it,
+/// so that the operation can still report a comparator panic on it.
+/// It must not outlive the transaction it comes from.
+#[derive(Clone, Copy)]
+pub(crate) struct TxnHandle<'e> {
+    pub(crate) env: &'e Env,
+    poison: *const Cell<Option<String>>,
+}
+
+impl TxnHandle<'_> {
+    /// Returns an [`Error::ComparatorPanicked`] if a custom comparator panicked
+    /// during an operation of this transaction, now or previously.
+    ///
+    /// The transaction is poisoned by the panic, it refuses to commit
+    /// and all the following database operations fail.
+    pub(crate) fn check_comparator_panic(&self) -> Result<()> {
+        // Safety: the handle doesn't outlive the transaction and its boxed poison flag.
+        let poison = unsafe { &*self.poison };
+        let message = poison.take();
+        poison.set(message.clone());
+        match message {
+            Some(message) => Err(Error::ComparatorPanicked(message)),
+            None => self.report_comparator_panic(),
+        }
+    }
+
+    /// Returns an [`Error::ComparatorPanicked`] if a custom comparator panicked during
+    /// the operation that just returned, the transaction is poisoned by the panic.
+    pub(crate) fn report_comparator_panic(&self) -> Result<()> {
+        match take_comparator_panic() {
+            Some(message) => Err(self.poison(message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Poisons the transaction with the panic of a custom comparator, the first
+    /// panic is kept, and returns the [`Error::ComparatorPanicked`] to report.
+    pub(crate) fn poison(&self, message: String) -> Error {
+        // Safety: the handle doesn't outlive the transaction and its boxed poison flag.
+        let poison = unsafe { &*self.poison };
+        let message = poison.take().unwrap_or(message);
+        poison.set(Some(message.clone()));
+        Error::ComparatorPanicked(message)
+    }
+}
+
 fn abort_txn(txn: *mut ffi::MDB_txn) {
     // Asserts that the transaction hasn't been already committed.
     assert!(!txn.is_null());
@@ -84,6 +274,10 @@ fn abort_txn(txn: *mut ffi::MDB_txn) {
 
 /// A read-write transaction.
 ///
+/// The operations that write into the databases require a mutable reference to it,
+/// LMDB invalidates the values read from a transaction when it is updated, the borrow
+/// checker therefore rejects the programs that keep using them after a write.
+///
 /// ## LMDB Limitations
 ///
 /// Only one [RwTxn] may exist in the same environment at the same time,
@@ -110,7 +304,7 @@ impl<'p> RwTxn<'p> {
 
         unsafe { mdb_result(ffi::mdb_txn_begin(env.env_mut_ptr(), ptr::null_mut(), 0, &mut txn))? };
 
-        Ok(RwTxn { txn: RoTxn { txn, env } })
+        Ok(RwTxn { txn: RoTxn::from_raw(txn, env, None) })
     }
 
     pub(crate) fn nested(env: &'p Env, parent: &'p mut RwTxn) -> Result<RwTxn<'p>> {
@@ -119,7 +313,7 @@ impl<'p> RwTxn<'p> {
 
         unsafe { mdb_result(ffi::mdb_txn_begin(env.env_mut_ptr(), parent_ptr, 0, &mut txn))? };
 
-        Ok(RwTxn { txn: RoTxn { txn, env } })
+        Ok(RwTxn { txn: RoTxn::from_raw(txn, env, Some(&parent.txn)) })
     }
 
     pub(crate) fn env_mut_ptr(&self) -> *mut ffi::MDB_env {
@@ -128,10 +322,20 @@ impl<'p> RwTxn<'p> {
 
     /// Commit all the operations of a transaction into the database.
     /// The transaction is reset.
+    ///
+    /// If a custom comparator panicked during one of the operations the transaction is
+    /// aborted instead, and an [`Error::ComparatorPanicked`] is returned.
     pub fn commit(mut self) -> Result<()> {
+        if let Some(message) = self.txn.poison.take() {
+            abort_txn(self.txn.txn);
+            self.txn.txn = ptr::null_mut();
+            return Err(Error::ComparatorPanicked(message));
+        }
         let result = unsafe { mdb_result(ffi::mdb_txn_commit(self.txn.txn)) };
         self.txn.txn = ptr::null_mut();
-        result.map_err(Into::into)
+        result?;
+        self.txn.commit_dropped_dbis();
+        Ok(())
     }
 
     /// Abandon all the operations of the transaction instead of saving them.
@@ -149,3 +353,109 @@ impl<'p> Deref for RwTxn<'p> {
         &self.txn
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::cell::Cell;
+    use std::ops::Bound;
+
+    use super::MAX_SCRATCH_CAPACITY;
+    use crate::types::*;
+    use crate::{BoxedError, BytesEncode, Database, EnvOpenOptions, RoTxn};
+
+    thread_local! {
+        /// The transaction and database used by the `Aliased` codec to resolve the aliases.
+        static ALIASES: Cell<Option<(*const RoTxn<'static>, Database<Str, Str>)>> = const { Cell::new(None) };
+    }
+
+    /// Encodes the keys as `user:` followed by the name the alias resolves to, it
+    /// reads the aliases with the transaction that is currently encoding the key.
+    enum Aliased {}
+
+    impl<'a> BytesEncode<'a> for Aliased {
+        type EItem = str;
+
+        fn bytes_encode(item: &'a str) -> Result<Cow<'a, [u8]>, BoxedError> {
+            let mut buffer = Vec::new();
+            Self::encode_into(item, &mut buffer)?;
+            Ok(Cow::Owned(buffer))
+        }
+
+        fn encode_into(item: &'a str, buffer: &mut Vec<u8>) -> Result<(), BoxedError> {
+            buffer.extend_from_slice(b"user:");
+            let (txn, aliases) = ALIASES.with(Cell::get).ok_or("no aliases")?;
+            // Safety: the pointer is set by the test for the duration of the operation.
+            let txn = unsafe { &*txn };
+            let name = aliases.get(txn, item)?.ok_or("unknown alias")?;
+            buffer.extend_from_slice(name.as_bytes());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn codec_reenters_the_transaction_while_the_scratch_is_taken() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().max_dbs(2).open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let aliases = env.create_database::<Str, Str>(&mut wtxn, Some("aliases")).unwrap();
+        let users = env.create_database::<Str, Str>(&mut wtxn, Some("users")).unwrap();
+        aliases.put(&mut wtxn, "kero", "kerollmops").unwrap();
+        users.put(&mut wtxn, "user:kerollmops", "Clément").unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        // Grow the scratch buffer so that a clobbered buffer would be noticed.
+        assert_eq!(users.get(&rtxn, &"x".repeat(100)).unwrap(), None);
+
+        let txn: *const RoTxn = &rtxn;
+        ALIASES.with(|cell| cell.set(Some((txn.cast(), aliases))));
+        let result = users.remap_key_type::<Aliased>().get(&rtxn, "kero");
+        ALIASES.with(|cell| cell.set(None));
+        assert_eq!(result.unwrap(), Some("Clément"));
+
+        // The transaction keeps working with a buffer after the nested use.
+        let result = users.remap_key_type::<Aliased>().get(&rtxn, "kero");
+        assert!(result.is_err());
+        assert_eq!(users.get(&rtxn, "user:kerollmops").unwrap(), Some("Clément"));
+    }
+
+    #[test]
+    fn scratch_buffer_is_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, None).unwrap();
+        db.put(&mut wtxn, &"k".repeat(64), "value").unwrap();
+
+        let buffer = wtxn.scratch.take();
+        assert!(buffer.capacity() >= 64 + "value".len());
+        let ptr = buffer.as_ptr();
+        wtxn.scratch.set(buffer);
+
+        assert_eq!(db.get(&wtxn, &"k".repeat(64)).unwrap(), Some("value"));
+        let buffer = wtxn.scratch.take();
+        assert_eq!(buffer.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn scratch_buffer_keeps_a_bounded_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = EnvOpenOptions::new().open(dir.path()).unwrap();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, None).unwrap();
+        let large = "v".repeat(4 * MAX_SCRATCH_CAPACITY);
+        db.put(&mut wtxn, "large", &large).unwrap();
+        assert!(wtxn.scratch.take().capacity() <= MAX_SCRATCH_CAPACITY);
+
+        // The range bounds are encoded into the scratch buffer too.
+        let bound = "k".repeat(2 * MAX_SCRATCH_CAPACITY);
+        let range = (Bound::Included(bound.as_str()), Bound::Unbounded);
+        drop(db.range(&wtxn, &range).unwrap());
+        assert!(wtxn.scratch.take().capacity() <= MAX_SCRATCH_CAPACITY);
+        assert_eq!(db.get(&wtxn, "large").unwrap(), Some(&large[..]));
+    }
+}
@@ -0,0 +1,14 @@
+use heed::types::Str;
+use heed::{Database, EnvOpenOptions};
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = EnvOpenOptions::new().open(dir.path()).unwrap();
+    let mut wtxn = env.write_txn().unwrap();
+    let db: Database<Str, Str> = env.create_database(&mut wtxn, None).unwrap();
+
+    for result in db.iter(&wtxn).unwrap() {
+        let (key, _value) = result.unwrap();
+        db.put(&mut wtxn, key, "overwritten").unwrap();
+    }
+}
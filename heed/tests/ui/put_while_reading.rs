@@ -0,0 +1,13 @@
+use heed::types::Str;
+use heed::{Database, EnvOpenOptions};
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = EnvOpenOptions::new().open(dir.path()).unwrap();
+    let mut wtxn = env.write_txn().unwrap();
+    let db: Database<Str, Str> = env.create_database(&mut wtxn, None).unwrap();
+
+    let value = db.get(&wtxn, "hello").unwrap();
+    db.put(&mut wtxn, "world", "is mine").unwrap();
+    println!("{:?}", value);
+}